@@ -124,7 +124,9 @@ core::arch::global_asm! {
   mgba_logging_enable_request = const MGBA_LOGGING_ENABLE_REQUEST,
 }
 
-// This handler DOES NOT allow nested interrupts at this time.
+// The IRQ mode `lr` and `spsr` are saved on the user stack before the Rust
+// handler is called, so the Rust handler is allowed to re-enable interrupts
+// (see `interrupts::enter_nestable`) without breaking our return to the BIOS.
 core::arch::global_asm! {
   bracer::put_fn_in_section!(".iwram.__runtime_irq_handler"),
   ".global __runtime_irq_handler",
@@ -149,12 +151,14 @@ core::arch::global_asm! {
     "ldr r12, [r12]",
     bracer::when!(("r12" != "#0")[1] {
       bracer::a32_read_spsr_to!("r3"),
+      "mov r2, lr",
       bracer::a32_set_cpu_control!(System, irq_masked = true, fiq_masked = true),
-      "push {{r3, lr}}",
+      "push {{r2, r3, r12, lr}}",
       bracer::a32_fake_blx!("r12"),
-      "pop {{r3, lr}}",
+      "pop {{r2, r3, r12, lr}}",
       bracer::a32_set_cpu_control!(IRQ, irq_masked = true, fiq_masked = true),
       bracer::a32_write_spsr_from!("r3"),
+      "mov lr, r2",
     }),
 
     // return to the BIOS
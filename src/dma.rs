@@ -42,6 +42,15 @@
 //! In the future the situation may improve.
 
 use crate::macros::{pub_const_fn_new_zeroed, u16_bool_field, u16_enum_field};
+#[cfg(feature = "on_gba")]
+use crate::{
+  mem::debug_assert_not_sram,
+  mmio::{
+    DMA0_CONTROL, DMA0_COUNT, DMA0_DEST, DMA0_SRC, DMA1_CONTROL, DMA1_COUNT,
+    DMA1_DEST, DMA1_SRC, DMA2_CONTROL, DMA2_COUNT, DMA2_DEST, DMA2_SRC,
+    DMA3_CONTROL, DMA3_COUNT, DMA3_DEST, DMA3_SRC, FIFO_A, FIFO_B,
+  },
+};
 
 /// Sets the change in destination address after each transfer.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -146,6 +155,7 @@ impl DmaControl {
 /// * `dest` address for the transfer
 /// * `count_ctrl` is the count in the low half and control in the upper half
 #[inline]
+#[cfg(feature = "on_gba")]
 #[allow(dead_code)]
 // we may make this pub in the future, until then this is basically a note
 unsafe fn stm_dma(
@@ -162,3 +172,242 @@ unsafe fn stm_dma(
     options(nostack, preserves_flags)
   );
 }
+
+/// Runs DMA3 as an immediate transfer, splitting large transfers as needed.
+///
+/// The CPU is halted while the DMA unit works, so each transfer is already
+/// complete by the time the control write returns.
+#[inline]
+#[cfg_attr(feature = "track_caller", track_caller)]
+#[cfg(feature = "on_gba")]
+unsafe fn dma3_immediate(
+  mut src: usize, mut dest: usize, mut count: usize, transfer_32bit: bool,
+) {
+  debug_assert_not_sram(dest);
+  // A count of 0 means "the maximum", so we keep each chunk
+  // below that just to avoid thinking about it.
+  const CHUNK: usize = 0x8000;
+  let size = if transfer_32bit { 4 } else { 2 };
+  let ctrl =
+    DmaControl::new().with_transfer_32bit(transfer_32bit).with_enabled(true);
+  while count > 0 {
+    let n = count.min(CHUNK);
+    DMA3_SRC.write(src as _);
+    DMA3_DEST.write(dest as _);
+    DMA3_COUNT.write(n as u16);
+    DMA3_CONTROL.write(ctrl);
+    src += n * size;
+    dest += n * size;
+    count -= n;
+  }
+}
+
+/// Copies `count` `u32` values from `src` to `dest` using DMA3.
+///
+/// The copy is complete when this returns. A `count` of 0 copies nothing.
+///
+/// ## Safety
+/// * `src` must be aligned and valid to read `count` elements.
+/// * `dest` must be aligned and valid to write `count` elements.
+/// * Neither region can be in SRAM. With `debug_assertions` on, a `dest` in
+///   SRAM is checked for, and will panic.
+/// * See the [module level](self) safety notes: `dest` should generally be
+///   VRAM, PALRAM, or OAM.
+#[inline]
+#[cfg_attr(feature = "track_caller", track_caller)]
+#[cfg(feature = "on_gba")]
+pub unsafe fn dma3_copy_u32(src: *const u32, dest: *mut u32, count: usize) {
+  dma3_immediate(src as usize, dest as usize, count, true)
+}
+
+/// Copies `count` `u16` values from `src` to `dest` using DMA3.
+///
+/// The copy is complete when this returns. A `count` of 0 copies nothing.
+///
+/// ## Safety
+/// * `src` must be aligned and valid to read `count` elements.
+/// * `dest` must be aligned and valid to write `count` elements.
+/// * Neither region can be in SRAM. With `debug_assertions` on, a `dest` in
+///   SRAM is checked for, and will panic.
+/// * See the [module level](self) safety notes: `dest` should generally be
+///   VRAM, PALRAM, or OAM.
+#[inline]
+#[cfg_attr(feature = "track_caller", track_caller)]
+#[cfg(feature = "on_gba")]
+pub unsafe fn dma3_copy_u16(src: *const u16, dest: *mut u16, count: usize) {
+  dma3_immediate(src as usize, dest as usize, count, false)
+}
+
+/// Starts DMA0 copying one `u16` per scanline from `table` into `dest`.
+///
+/// This is the usual way to do per-scanline effects, such as setting a
+/// background's horizontal offset from a table made by
+/// [`sine_scroll_table`](crate::video::sine_scroll_table). Entry `y` of the
+/// table is the value used while drawing scanline `y`:
+/// * `table[0]` is written to `dest` right away.
+/// * After each scanline's horizontal blank starts, DMA0 copies the next entry.
+///
+/// Call this once per frame during vertical blank, since the DMA source keeps
+/// advancing through the frame and has to be reset. DMA0 keeps running until
+/// its control is cleared (`DMA0_CONTROL.write(DmaControl::new())`).
+///
+/// The horizontal blank of scanline 159 also triggers a transfer, which reads
+/// the `u16` just past the end of `table` and writes it to `dest` while the
+/// display is in vertical blank. That write isn't visible, and the next call
+/// to this function puts `table[0]` back.
+///
+/// ## Safety
+/// * `dest` must be an MMIO register that's safe to write any `u16` value to.
+/// * `table` must be in IWRAM or EWRAM (DMA0 can't read the ROM), and must not
+///   be moved, changed, or dropped while the DMA is running.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub unsafe fn hblank_dma0_u16(table: &[u16; 160], dest: *mut u16) {
+  DMA0_CONTROL.write(DmaControl::new());
+  dest.write_volatile(table[0]);
+  DMA0_SRC.write(table.as_ptr().add(1).cast());
+  DMA0_DEST.write(dest.cast());
+  DMA0_COUNT.write(1);
+  DMA0_CONTROL.write(
+    DmaControl::new()
+      .with_dest_addr_control(DestAddrControl::Fixed)
+      .with_src_addr_control(SrcAddrControl::Increment)
+      .with_repeat(true)
+      .with_start_time(DmaStartTime::HBlank)
+      .with_enabled(true),
+  );
+}
+
+/// One of the two Direct Sound FIFO buffers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SoundFifo {
+  /// Direct Sound A, `FIFO_A`.
+  A,
+  /// Direct Sound B, `FIFO_B`.
+  B,
+}
+impl SoundFifo {
+  /// The address of this FIFO's MMIO register.
+  #[inline]
+  #[must_use]
+  #[cfg(feature = "on_gba")]
+  pub const fn addr(self) -> usize {
+    match self {
+      SoundFifo::A => FIFO_A.as_usize(),
+      SoundFifo::B => FIFO_B.as_usize(),
+    }
+  }
+}
+
+/// An error from [`configure_fifo_dma`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FifoDmaError {
+  /// Only DMA1 and DMA2 can refill a sound FIFO. The invalid channel number is
+  /// given.
+  InvalidChannel(usize),
+}
+
+/// The DMA control used to feed a sound FIFO.
+///
+/// Sound FIFO transfers have to be set up in exactly this way, anything else
+/// plays noise or nothing at all:
+/// * 32-bit transfers (the hardware always moves 4 words per refill).
+/// * A fixed destination, so every word goes into the FIFO register.
+/// * Repeat, so the DMA runs again each time the FIFO needs more data.
+/// * The `Special` start time, which is "when the FIFO runs low" for DMA1 and
+///   DMA2.
+///
+/// ```
+/// # use gba::prelude::*;
+/// assert_eq!(fifo_dma_control().to_u16(), 0xB640);
+/// ```
+#[inline]
+#[must_use]
+pub const fn fifo_dma_control() -> DmaControl {
+  DmaControl::new()
+    .with_dest_addr_control(DestAddrControl::Fixed)
+    .with_src_addr_control(SrcAddrControl::Increment)
+    .with_repeat(true)
+    .with_transfer_32bit(true)
+    .with_start_time(DmaStartTime::Special)
+    .with_enabled(true)
+}
+
+/// Checks that `channel` is one of the DMA units that can feed a sound FIFO.
+#[inline]
+#[cfg(any(feature = "on_gba", test))]
+const fn check_fifo_channel(channel: usize) -> Result<(), FifoDmaError> {
+  match channel {
+    1 | 2 => Ok(()),
+    _ => Err(FifoDmaError::InvalidChannel(channel)),
+  }
+}
+
+/// Starts DMA1 or DMA2 feeding samples from `source` into a sound FIFO.
+///
+/// The DMA is set up with [`fifo_dma_control`]. Any transfer already running
+/// on the channel is stopped first.
+///
+/// This only handles the DMA side of things. A timer also needs to be running
+/// at the sample rate, and the FIFO has to be connected to that timer and to
+/// the speakers with [`SOUND_MIX`](crate::mmio::SOUND_MIX).
+///
+/// ```no_run
+/// # use gba::prelude::*;
+/// # static SAMPLES: [i8; 4] = [0; 4];
+/// unsafe {
+///   assert!(configure_fifo_dma(1, SoundFifo::A, SAMPLES.as_ptr()).is_ok());
+///   assert_eq!(
+///     configure_fifo_dma(3, SoundFifo::A, SAMPLES.as_ptr()),
+///     Err(FifoDmaError::InvalidChannel(3))
+///   );
+/// }
+/// ```
+///
+/// ## Failures
+/// * If `channel` isn't 1 or 2, nothing is changed and
+///   [`InvalidChannel`](FifoDmaError::InvalidChannel) is returned.
+///
+/// ## Safety
+/// * `source` must be aligned to 4.
+/// * The DMA keeps reading forward from `source` until it's stopped, so all of
+///   the memory it reads in that time must be readable. That memory can't be
+///   moved, changed, or dropped while the DMA is running.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub unsafe fn configure_fifo_dma(
+  channel: usize, fifo: SoundFifo, source: *const i8,
+) -> Result<(), FifoDmaError> {
+  check_fifo_channel(channel)?;
+  let (src, dest, count, control) = if channel == 1 {
+    (DMA1_SRC, DMA1_DEST, DMA1_COUNT, DMA1_CONTROL)
+  } else {
+    (DMA2_SRC, DMA2_DEST, DMA2_COUNT, DMA2_CONTROL)
+  };
+  control.write(DmaControl::new());
+  src.write(source.cast());
+  dest.write(fifo.addr() as _);
+  // The count is ignored for FIFO transfers, it's always 4 words.
+  count.write(4);
+  control.write(fifo_dma_control());
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn fifo_dma_rules() {
+    let control = fifo_dma_control();
+    assert_eq!(control.dest_addr_control(), DestAddrControl::Fixed);
+    assert_eq!(control.src_addr_control(), SrcAddrControl::Increment);
+    assert!(control.repeat() && control.transfer_32bit() && control.enabled());
+    assert_eq!(control.start_time(), DmaStartTime::Special);
+    assert_eq!(control.to_u16(), 0xB640);
+    assert_eq!(check_fifo_channel(1), Ok(()));
+    assert_eq!(check_fifo_channel(2), Ok(()));
+    assert_eq!(check_fifo_channel(0), Err(FifoDmaError::InvalidChannel(0)));
+    assert_eq!(check_fifo_channel(3), Err(FifoDmaError::InvalidChannel(3)));
+  }
+}
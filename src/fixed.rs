@@ -290,6 +290,48 @@ macro_rules! impl_signed_fixed_ops {
         self.0 < 0
       }
 
+      /// The smaller of the two values.
+      #[inline]
+      #[must_use]
+      #[cfg_attr(feature = "track_caller", track_caller)]
+      pub const fn min(self, other: Self) -> Self {
+        if self.0 <= other.0 {
+          self
+        } else {
+          other
+        }
+      }
+
+      /// The larger of the two values.
+      #[inline]
+      #[must_use]
+      #[cfg_attr(feature = "track_caller", track_caller)]
+      pub const fn max(self, other: Self) -> Self {
+        if self.0 >= other.0 {
+          self
+        } else {
+          other
+        }
+      }
+
+      /// The sign of the value: -1, 0, or 1 (in this fixed point format).
+      ///
+      /// If 1 can't be represented (`B` is one less than the number of bits)
+      /// then positive values give the wrapped value instead.
+      ///
+      /// ```
+      /// # use gba::fixed::i16fx8;
+      /// assert_eq!(i16fx8::from_bits(-300).signum(), i16fx8::from_bits(-256));
+      /// assert_eq!(i16fx8::from_bits(0).signum(), i16fx8::from_bits(0));
+      /// assert_eq!(i16fx8::from_bits(5).signum(), i16fx8::from_bits(256));
+      /// ```
+      #[inline]
+      #[must_use]
+      #[cfg_attr(feature = "track_caller", track_caller)]
+      pub const fn signum(self) -> Self {
+        Self(self.0.signum() << B)
+      }
+
       /// Multiply.
       #[inline]
       #[must_use]
@@ -399,3 +441,424 @@ macro_rules! impl_unsigned_fixed_ops {
 impl_unsigned_fixed_ops!(u8);
 impl_unsigned_fixed_ops!(u16);
 impl_unsigned_fixed_ops!(u32);
+
+/// An error from parsing a [`Fixed`] value with
+/// [`FromStr`](core::str::FromStr).
+///
+/// ```
+/// # use gba::fixed::{Fixed, ParseFixedError};
+/// let x: Fixed<i16, 8> = "1.5".parse().unwrap();
+/// assert_eq!(x.to_bits(), 0x180);
+/// let x: Fixed<i16, 8> = "-0.25".parse().unwrap();
+/// assert_eq!(x.to_bits(), -0x40);
+/// let err = "abc".parse::<Fixed<i16, 8>>();
+/// assert_eq!(err, Err(ParseFixedError::InvalidDigit));
+/// assert_eq!("-".parse::<Fixed<i16, 8>>(), Err(ParseFixedError::Empty));
+/// assert_eq!("128".parse::<Fixed<i16, 8>>(), Err(ParseFixedError::Overflow));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParseFixedError {
+  /// The string didn't have any digits.
+  Empty,
+  /// The string had a character that isn't a digit, or isn't in the right
+  /// place.
+  InvalidDigit,
+  /// The value doesn't fit in the type.
+  Overflow,
+}
+
+/// Parses an optional sign, integer digits, and optional fraction digits into
+/// the magnitude as raw fixed point bits with `b` fractional bits.
+fn parse_fixed_bits(s: &str, b: u32) -> Result<(bool, u64), ParseFixedError> {
+  // more fraction digits than this can't affect the result, and skipping them
+  // keeps the math within a `u64`.
+  const MAX_FRACT_DIGITS: u32 = 9;
+  let bytes = s.as_bytes();
+  let (negative, bytes) = match bytes {
+    [b'-', rest @ ..] => (true, rest),
+    [b'+', rest @ ..] => (false, rest),
+    _ => (false, bytes),
+  };
+  let (whole, fract) = match bytes.iter().position(|&c| c == b'.') {
+    Some(i) => (&bytes[..i], &bytes[i + 1..]),
+    None => (bytes, &bytes[..0]),
+  };
+  if whole.is_empty() && fract.is_empty() {
+    return Err(ParseFixedError::Empty);
+  }
+  let mut int: u64 = 0;
+  for &c in whole {
+    if !c.is_ascii_digit() {
+      return Err(ParseFixedError::InvalidDigit);
+    }
+    int = int
+      .checked_mul(10)
+      .and_then(|i| i.checked_add(u64::from(c - b'0')))
+      .ok_or(ParseFixedError::Overflow)?;
+  }
+  let mut fract_num: u64 = 0;
+  let mut fract_den: u64 = 1;
+  for (i, &c) in fract.iter().enumerate() {
+    if !c.is_ascii_digit() {
+      return Err(ParseFixedError::InvalidDigit);
+    }
+    if (i as u32) < MAX_FRACT_DIGITS {
+      fract_num = fract_num * 10 + u64::from(c - b'0');
+      fract_den *= 10;
+    }
+  }
+  let fract_bits = ((fract_num << b) + fract_den / 2) / fract_den;
+  let bits = int
+    .checked_mul(1 << b)
+    .and_then(|i| i.checked_add(fract_bits))
+    .ok_or(ParseFixedError::Overflow)?;
+  Ok((negative, bits))
+}
+
+macro_rules! impl_from_str {
+  ($t:ty) => {
+    /// Parses a decimal number, such as `"3.25"` or `"-0.5"`.
+    ///
+    /// This doesn't use any floating point math. A value can only hold `B`
+    /// fractional bits, so the fraction is rounded to the nearest multiple of
+    /// `1 / 2**B` (halves round away from zero). Fraction digits past the
+    /// ninth are checked to be digits but otherwise ignored.
+    impl<const B: u32> core::str::FromStr for Fixed<$t, B> {
+      type Err = ParseFixedError;
+      #[inline]
+      fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, bits) = parse_fixed_bits(s, B)?;
+        let bits =
+          i64::try_from(bits).map_err(|_| ParseFixedError::Overflow)?;
+        let bits = if negative { -bits } else { bits };
+        <$t>::try_from(bits)
+          .map(Self::from_bits)
+          .map_err(|_| ParseFixedError::Overflow)
+      }
+    }
+  };
+}
+impl_from_str!(i8);
+impl_from_str!(i16);
+impl_from_str!(i32);
+impl_from_str!(u8);
+impl_from_str!(u16);
+impl_from_str!(u32);
+
+/// `sin` for the first quarter of a circle, in 1.14 fixed point, at 64 steps
+/// per quarter.
+const QUARTER_SINE: [i16; 65] = [
+  0, 402, 804, 1205, 1606, 2006, 2404, 2801, 3196, 3590, 3981, 4370, 4756,
+  5139, 5520, 5897, 6270, 6639, 7005, 7366, 7723, 8076, 8423, 8765, 9102, 9434,
+  9760, 10080, 10394, 10702, 11003, 11297, 11585, 11866, 12140, 12406, 12665,
+  12916, 13160, 13395, 13623, 13842, 14053, 14256, 14449, 14635, 14811, 14978,
+  15137, 15286, 15426, 15557, 15679, 15791, 15893, 15986, 16069, 16143, 16207,
+  16261, 16305, 16340, 16364, 16379, 16384,
+];
+
+/// `sin` at one of 256 steps around the circle, as raw 1.14 bits.
+#[inline]
+const fn sine_step(step: usize) -> i32 {
+  let k = step & 63;
+  let v = match (step >> 6) & 3 {
+    0 => QUARTER_SINE[k],
+    1 => QUARTER_SINE[64 - k],
+    2 => -QUARTER_SINE[k],
+    _ => -QUARTER_SINE[64 - k],
+  };
+  v as i32
+}
+
+/// Sine of an angle.
+///
+/// The angle is in "binary" units where `0x1_0000` is a full circle (so
+/// `0x4000` is a quarter turn), the same as the angles of
+/// [`ArcTan2`](crate::bios::ArcTan2). This is a table lookup with linear
+/// interpolation, accurate to within about 1/5,000.
+#[inline]
+#[must_use]
+pub const fn sin(angle: u16) -> i16fx14 {
+  let step = (angle >> 8) as usize;
+  let frac = (angle & 0xFF) as i32;
+  let a = sine_step(step);
+  let b = sine_step(step + 1);
+  i16fx14::from_bits((a + (((b - a) * frac) >> 8)) as i16)
+}
+
+/// Cosine of an angle, see [`sin`] for the angle units.
+#[inline]
+#[must_use]
+pub const fn cos(angle: u16) -> i16fx14 {
+  sin(angle.wrapping_add(0x4000))
+}
+
+/// Gets the bits of an `i16fx8` as an `i32`.
+#[inline]
+#[allow(clippy::unnecessary_cast)]
+pub(crate) const fn fx8_bits(v: i16fx8) -> i32 {
+  // With the `fixed` feature `i16fx8` is backed by an `i32`.
+  v.to_bits() as i32
+}
+
+/// Makes an `i16fx8` from bits held in an `i32`.
+#[inline]
+#[allow(clippy::unnecessary_cast)]
+pub(crate) const fn fx8_from_bits(bits: i32) -> i16fx8 {
+  i16fx8::from_bits(bits as _)
+}
+
+/// Another name for [`Mat2`], for when it's used as a set of affine
+/// parameters.
+pub type AffineMatrix = Mat2;
+
+/// A 2x2 matrix of `i16fx8` values, in the layout of the affine parameters.
+///
+/// ```text
+/// | a b |
+/// | c d |
+/// ```
+///
+/// The GBA's affine parameters map *screen* pixels to *texture* pixels, so
+/// the image you see is transformed by the inverse of the matrix: a
+/// [`scale`](Mat2::scale) of 2 makes the image look half as big. Compose
+/// matrices with [`mul`](Mat2::mul) and then write the result with
+/// [`to_bg_params`](Mat2::to_bg_params).
+///
+/// Values are rounded to 8 fractional bits after every operation, so long
+/// chains of multiplications gradually lose precision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Mat2 {
+  #[allow(missing_docs)]
+  pub a: i16fx8,
+  #[allow(missing_docs)]
+  pub b: i16fx8,
+  #[allow(missing_docs)]
+  pub c: i16fx8,
+  #[allow(missing_docs)]
+  pub d: i16fx8,
+}
+impl Mat2 {
+  /// The identity matrix, which leaves everything as it is.
+  #[inline]
+  #[must_use]
+  pub const fn identity() -> Self {
+    Self::from_bits(1 << 8, 0, 0, 1 << 8)
+  }
+
+  /// A rotation matrix.
+  ///
+  /// The angle uses the same units as [`sin`], and is counter-clockwise in
+  /// texture space.
+  #[inline]
+  #[must_use]
+  pub const fn rotation(angle: u16) -> Self {
+    // rounding from 14 fractional bits down to 8
+    let s = (sin(angle).to_bits() as i32 + 32) >> 6;
+    let c = (cos(angle).to_bits() as i32 + 32) >> 6;
+    Self::from_bits(c, -s, s, c)
+  }
+
+  /// A rotation and scaling matrix, such as for a spinning and zooming
+  /// affine background.
+  ///
+  /// The angle is a "binary angle" where 256 is a full circle (so 64 is a
+  /// quarter turn), which is [`sin`] units divided by 256. This is
+  /// `scale(scale_x, scale_y) * rotation(angle)`, the same matrix that the
+  /// BIOS `BgAffineSet` function computes. Like with [`scale`](Mat2::scale), a
+  /// scale of 2 makes the image look half as big.
+  ///
+  /// ```
+  /// # use gba::prelude::*;
+  /// let one = i16fx8::from_bits(1 << 8);
+  /// let two = i16fx8::from_bits(2 << 8);
+  /// assert_eq!(AffineMatrix::from_rotation_scale(0, one, one), Mat2::identity());
+  /// let m = AffineMatrix::from_rotation_scale(64, two, one);
+  /// assert_eq!(m.to_bg_params().map(|x| x.to_bits()), [0, -2 << 8, 1 << 8, 0]);
+  /// ```
+  #[inline]
+  #[must_use]
+  pub const fn from_rotation_scale(
+    angle: u8, scale_x: i16fx8, scale_y: i16fx8,
+  ) -> Self {
+    Self::scale(scale_x, scale_y).mul(&Self::rotation((angle as u16) << 8))
+  }
+
+  /// A scaling matrix.
+  #[inline]
+  #[must_use]
+  pub const fn scale(sx: i16fx8, sy: i16fx8) -> Self {
+    Self { a: sx, b: fx8_from_bits(0), c: fx8_from_bits(0), d: sy }
+  }
+
+  /// A shearing matrix: `x` is shifted by `kx` times `y`, and `y` by `ky`
+  /// times `x`.
+  #[inline]
+  #[must_use]
+  pub const fn shear(kx: i16fx8, ky: i16fx8) -> Self {
+    Self { a: fx8_from_bits(1 << 8), b: kx, c: ky, d: fx8_from_bits(1 << 8) }
+  }
+
+  /// Matrix multiplication, `self * other`.
+  ///
+  /// The result applies `other` first and then `self`.
+  #[inline]
+  #[must_use]
+  pub const fn mul(&self, other: &Self) -> Self {
+    const fn dot(x0: i16fx8, y0: i16fx8, x1: i16fx8, y1: i16fx8) -> i32 {
+      (fx8_bits(x0) * fx8_bits(y0) + fx8_bits(x1) * fx8_bits(y1) + 128) >> 8
+    }
+    Self::from_bits(
+      dot(self.a, other.a, self.b, other.c),
+      dot(self.a, other.b, self.b, other.d),
+      dot(self.c, other.a, self.d, other.c),
+      dot(self.c, other.b, self.d, other.d),
+    )
+  }
+
+  /// The matrix as `[a, b, c, d]`, the order of the `BG2PA..BG2PD` (and
+  /// `BG3PA..BG3PD`) registers.
+  #[inline]
+  #[must_use]
+  pub const fn to_bg_params(&self) -> [i16fx8; 4] {
+    [self.a, self.b, self.c, self.d]
+  }
+
+  #[inline]
+  const fn from_bits(a: i32, b: i32, c: i32, d: i32) -> Self {
+    Self {
+      a: fx8_from_bits(a),
+      b: fx8_from_bits(b),
+      c: fx8_from_bits(c),
+      d: fx8_from_bits(d),
+    }
+  }
+}
+
+/// A pair of [`Fixed`] values, such as a position or a velocity.
+///
+/// `Vec2<i16, 8>` is an `x` and `y` with the same format as [`i16fx8`].
+///
+/// ```
+/// # use gba::fixed::Vec2;
+/// let v: Vec2<i16, 8> = Vec2::from_bits(3 << 8, -128);
+/// assert_eq!((v.x.to_bits(), v.y.to_bits()), (3 << 8, -128));
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Vec2<I, const B: u32> {
+  #[allow(missing_docs)]
+  pub x: Fixed<I, B>,
+  #[allow(missing_docs)]
+  pub y: Fixed<I, B>,
+}
+impl<I, const B: u32> Vec2<I, B> {
+  /// Makes a vector from its two parts.
+  #[inline]
+  #[must_use]
+  pub const fn new(x: Fixed<I, B>, y: Fixed<I, B>) -> Self {
+    Self { x, y }
+  }
+
+  /// Makes a vector from the raw inner value of each part (no shift).
+  #[inline]
+  #[must_use]
+  pub const fn from_bits(x: I, y: I) -> Self {
+    Self { x: Fixed(x), y: Fixed(y) }
+  }
+}
+impl<I, const B: u32> core::fmt::Debug for Vec2<I, B>
+where
+  Fixed<I, B>: core::fmt::Debug,
+{
+  #[inline]
+  fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+    f.debug_struct("Vec2").field("x", &self.x).field("y", &self.y).finish()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rotations_compose() {
+    let angles = [0_u16, 0x0800, 0x1555, 0x4000, 0x7000, 0xC123, 0xFFFF];
+    for a in angles {
+      for b in angles {
+        let composed = Mat2::rotation(a).mul(&Mat2::rotation(b));
+        let direct = Mat2::rotation(a.wrapping_add(b));
+        for (x, y) in composed.to_bg_params().iter().zip(direct.to_bg_params())
+        {
+          let diff = fx8_bits(*x) - fx8_bits(y);
+          assert!(diff.abs() <= 2, "{a:#X} + {b:#X}: {composed:?} {direct:?}");
+        }
+      }
+    }
+    let quarter = Mat2::rotation(0x4000);
+    assert_eq!(quarter, Mat2::from_bits(0, -256, 256, 0));
+    assert_eq!(Mat2::rotation(0), Mat2::identity());
+    assert_eq!(quarter.mul(&Mat2::identity()), quarter);
+  }
+
+  #[test]
+  fn sin_cos_quadrants() {
+    let bits = |v: i16fx14| v.to_bits();
+    assert_eq!(bits(sin(0)), 0);
+    assert_eq!(bits(sin(0x2000)), 11585);
+    assert_eq!(bits(sin(0x4000)), 16384);
+    assert_eq!(bits(sin(0x8000)), 0);
+    assert_eq!(bits(sin(0xC000)), -16384);
+    assert_eq!(bits(cos(0)), 16384);
+    assert_eq!(bits(cos(0x8000)), -16384);
+    // interpolated between steps, 1/512th of a turn is about 0.01227
+    assert_eq!(bits(sin(0x80)), 201);
+  }
+
+  #[test]
+  fn parse_fixed() {
+    type I = Fixed<i16, 8>;
+    let bits = |s: &str| s.parse::<I>().map(|x| x.to_bits());
+    assert_eq!(bits("1.5"), Ok(0x180));
+    assert_eq!(bits("-0.25"), Ok(-0x40));
+    assert_eq!(bits("3.25"), Ok(0x340));
+    assert_eq!(bits("+2"), Ok(0x200));
+    assert_eq!(bits(".5"), Ok(0x80));
+    assert_eq!(bits("5."), Ok(0x500));
+    assert_eq!(bits("-128"), Ok(i16::MIN));
+    // fractions round to the nearest 1/256, halves away from zero
+    assert_eq!(bits("1.00390625"), Ok(0x101));
+    assert_eq!(bits("0.001953125"), Ok(1));
+    assert_eq!(bits("-0.001953125"), Ok(-1));
+    assert_eq!(bits("0.0019"), Ok(0));
+    assert_eq!(bits("0.0000000000009"), Ok(0));
+    assert_eq!(bits("abc"), Err(ParseFixedError::InvalidDigit));
+    assert_eq!(bits("1.5x"), Err(ParseFixedError::InvalidDigit));
+    assert_eq!(bits("1.2.3"), Err(ParseFixedError::InvalidDigit));
+    assert_eq!(bits(""), Err(ParseFixedError::Empty));
+    assert_eq!(bits("-."), Err(ParseFixedError::Empty));
+    assert_eq!(bits("128"), Err(ParseFixedError::Overflow));
+    assert_eq!(bits("99999999999999999999"), Err(ParseFixedError::Overflow));
+    assert_eq!("-1".parse::<Fixed<u16, 8>>(), Err(ParseFixedError::Overflow));
+    assert_eq!(
+      "255.5".parse::<Fixed<u16, 8>>().map(|x| x.to_bits()),
+      Ok(0xFF80)
+    );
+  }
+
+  #[test]
+  fn signum_min_max() {
+    type Fx = Fixed<i16, 8>;
+    let one = Fx::from_bits(256);
+    for (bits, sign) in [(-32768, -256), (-300, -256), (-1, -256), (0, 0)] {
+      assert_eq!(Fx::from_bits(bits).signum(), Fx::from_bits(sign), "{bits}");
+    }
+    for bits in [1, 255, 256, 32767] {
+      assert_eq!(Fx::from_bits(bits).signum(), one, "{bits}");
+    }
+    // the wider and more precise formats use their own 1.0
+    assert_eq!(Fixed::<i32, 8>::from_bits(-9).signum().to_bits(), -256);
+    assert_eq!(Fixed::<i16, 14>::from_bits(3).signum().to_bits(), 1 << 14);
+    let (a, b) = (Fx::from_bits(-20), Fx::from_bits(7));
+    assert_eq!((a.min(b), a.max(b)), (a, b));
+    assert_eq!((b.min(a), b.max(a)), (a, b));
+  }
+}
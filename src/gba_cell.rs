@@ -25,6 +25,7 @@ use core::{
   mem::{align_of, size_of},
   num::{NonZeroI16, NonZeroI32, NonZeroI8, NonZeroU16, NonZeroU32, NonZeroU8},
   panic::RefUnwindSafe,
+  ptr::NonNull,
 };
 
 use crate::{
@@ -59,6 +60,8 @@ use crate::{
 ///   inline assembly as an atomic sync point.
 /// * However, inside the inline asm block we actually just use the single load
 ///   or store op that we wanted.
+/// * Without `on_gba` (such as when running tests on the host) the inline
+///   assembly isn't available, so plain volatile reads and writes are used.
 #[repr(transparent)]
 pub struct GbaCell<T>(UnsafeCell<T>);
 impl<T> Debug for GbaCell<T>
@@ -117,6 +120,11 @@ where
   #[inline]
   #[must_use]
   pub fn read(&self) -> T {
+    #[cfg(not(feature = "on_gba"))]
+    {
+      unsafe { self.get_ptr().read_volatile() }
+    }
+    #[cfg(feature = "on_gba")]
     match (size_of::<T>(), align_of::<T>()) {
       (4, 4) => unsafe {
         let val: u32;
@@ -157,6 +165,11 @@ where
   /// Writes a new value.
   #[inline]
   pub fn write(&self, val: T) {
+    #[cfg(not(feature = "on_gba"))]
+    {
+      unsafe { self.get_ptr().write_volatile(val) }
+    }
+    #[cfg(feature = "on_gba")]
     match (size_of::<T>(), align_of::<T>()) {
       (4, 4) => unsafe {
         let u: u32 = core::mem::transmute_copy(&val);
@@ -246,3 +259,4 @@ unsafe impl GbaCellSafe for u16 {}
 unsafe impl GbaCellSafe for u32 {}
 unsafe impl GbaCellSafe for u8 {}
 unsafe impl<I: GbaCellSafe, const B: u32> GbaCellSafe for Fixed<I, B> {}
+unsafe impl<T> GbaCellSafe for Option<NonNull<T>> {}
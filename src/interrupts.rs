@@ -1,4 +1,14 @@
-use crate::macros::{pub_const_fn_new_zeroed, u16_bool_field};
+use crate::{
+  macros::{pub_const_fn_new_zeroed, u16_bool_field},
+  mem::MemoryRegion,
+};
+
+#[cfg(any(feature = "on_gba", test))]
+use crate::gba_cell::GbaCell;
+#[cfg(feature = "on_gba")]
+use crate::mmio::{IE, IF, IME};
+#[cfg(any(feature = "on_gba", test))]
+use core::ptr::NonNull;
 
 /// A function you want called during an interrupt.
 pub type IrqFn = unsafe extern "C" fn(IrqBits);
@@ -47,3 +57,1049 @@ impl IrqBits {
 
 // TODO: might want to support bit ops. But it's not super important right now
 // since they can't be implented as const traits yet anyway.
+
+/// The number of interrupt sources, one per bit of [`IrqBits`].
+#[cfg(any(feature = "on_gba", test))]
+const IRQ_SOURCES: usize = 14;
+
+/// The handler for each interrupt source, used by [`irq_dispatcher`].
+#[cfg(feature = "on_gba")]
+static IRQ_HANDLERS: [GbaCell<Option<IrqFn>>; IRQ_SOURCES] =
+  [const { GbaCell::new(None) }; IRQ_SOURCES];
+
+/// Sets the function that [`irq_dispatcher`] calls for each interrupt in
+/// `irqs`, or clears it when `handler` is `None`.
+///
+/// When called, the handler is passed just the one bit it was called for.
+///
+/// This doesn't change `IE` or any of the other interrupt controls, you still
+/// need to enable the interrupts you want to receive.
+///
+/// With `debug_assertions` enabled, a handler that isn't an ARM function in
+/// IWRAM (see [`IrqHandlerPlacement`]) is reported in the mGBA log as a
+/// warning.
+///
+/// ```no_run
+/// # use gba::prelude::*;
+/// unsafe extern "C" fn on_vblank(_: IrqBits) {}
+///
+/// set_irq_handler(IrqBits::VBLANK, Some(on_vblank));
+/// RUST_IRQ_HANDLER.write(Some(irq_dispatcher));
+/// ```
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn set_irq_handler(irqs: IrqBits, handler: Option<IrqFn>) {
+  if let (true, Some(f)) = (cfg!(debug_assertions), handler) {
+    let placement = IrqHandlerPlacement::of_addr(f as usize);
+    if !placement.is_ideal() {
+      use crate::mgba::{mgba_log_fmt, MgbaMessageLevel};
+      mgba_log_fmt(
+        MgbaMessageLevel::Warning,
+        format_args!("slow interrupt handler at {:p}: {placement:?}", f),
+      )
+      .ok();
+    }
+  }
+  for (i, cell) in IRQ_HANDLERS.iter().enumerate() {
+    if irqs.0 & (1 << i) != 0 {
+      cell.write(handler);
+    }
+  }
+}
+
+/// Where an interrupt handler's code is, and how it's encoded.
+///
+/// Interrupt handlers should be fast, so ideally they're ARM code placed in
+/// IWRAM. Code in ROM runs with the ROM's wait states, and Thumb code in ROM
+/// is only somewhat better. A slow handler can make the program miss
+/// interrupts, or finish them too late for timing sensitive effects.
+///
+/// By default Rust code for the GBA is Thumb code in ROM. To move a handler,
+/// use `#[instruction_set(arm::a32)]` and
+/// `#[link_section = ".iwram.handler_name"]` on it.
+///
+/// ```
+/// # use gba::interrupts::IrqHandlerPlacement;
+/// # use gba::mem::MemoryRegion;
+/// let p = IrqHandlerPlacement::of_addr(0x0300_0100);
+/// assert_eq!(p.region, MemoryRegion::Iwram);
+/// assert!(!p.thumb);
+/// assert!(p.is_ideal());
+/// // a Thumb function in ROM has the low bit of its address set
+/// let p = IrqHandlerPlacement::of_addr(0x0800_1235);
+/// assert_eq!(p.region, MemoryRegion::Rom);
+/// assert!(p.thumb);
+/// assert!(!p.is_ideal());
+/// assert!(!IrqHandlerPlacement::of_addr(0x0300_0101).is_ideal());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IrqHandlerPlacement {
+  /// The memory region the code is in.
+  pub region: MemoryRegion,
+  /// If the code is Thumb code (the low bit of the address is set).
+  pub thumb: bool,
+}
+impl IrqHandlerPlacement {
+  /// Classifies a function address.
+  #[inline]
+  #[must_use]
+  pub const fn of_addr(addr: usize) -> Self {
+    Self { region: MemoryRegion::of_addr(addr), thumb: addr & 1 != 0 }
+  }
+
+  /// If the code is ARM code in IWRAM.
+  #[inline]
+  #[must_use]
+  pub const fn is_ideal(&self) -> bool {
+    matches!(self.region, MemoryRegion::Iwram) && !self.thumb
+  }
+}
+
+/// The interrupts that currently have a handler set with [`set_irq_handler`].
+#[inline]
+#[must_use]
+#[cfg(feature = "on_gba")]
+pub fn handled_irqs() -> IrqBits {
+  let mut bits = 0;
+  for (i, cell) in IRQ_HANDLERS.iter().enumerate() {
+    if cell.read().is_some() {
+      bits |= 1 << i;
+    }
+  }
+  IrqBits(bits)
+}
+
+/// The interrupts within `fired` that aren't part of `handled`.
+#[inline]
+#[must_use]
+pub const fn unhandled_irqs(fired: IrqBits, handled: IrqBits) -> IrqBits {
+  IrqBits(fired.0 & !handled.0)
+}
+
+/// Calls `handler(i)` (if any) for each bit `i` of `bits`, in bit order,
+/// returning the bits that had no handler.
+///
+/// ## Safety
+/// * Each handler is called, so this has the same safety requirements as
+///   calling them.
+#[inline]
+#[cfg(any(feature = "on_gba", test))]
+unsafe fn dispatch_irqs(
+  bits: IrqBits, mut handler: impl FnMut(usize) -> Option<IrqFn>,
+) -> IrqBits {
+  let mut handled = 0;
+  for i in 0..IRQ_SOURCES {
+    let bit = 1 << i;
+    if bits.0 & bit == 0 {
+      continue;
+    }
+    if let Some(handler) = handler(i) {
+      handler(IrqBits(bit));
+      handled |= bit;
+    }
+  }
+  unhandled_irqs(bits, IrqBits(handled))
+}
+
+/// An interrupt handler that calls the handler set for each interrupt that
+/// fired.
+///
+/// Assign this to [`RUST_IRQ_HANDLER`](crate::RUST_IRQ_HANDLER), and then use
+/// [`set_irq_handler`] to set what should happen for each interrupt. Handlers
+/// are called in bit order (vblank first).
+///
+/// With `debug_assertions` enabled, any interrupt that fires without a handler
+/// is reported in the mGBA log as a warning. That's almost always a setup
+/// mistake, such as enabling an interrupt in `IE` and then forgetting to set a
+/// handler for it.
+///
+/// The dispatcher can also count how often each interrupt fires, see
+/// [`set_irq_counting`].
+///
+/// ## Safety
+/// * This is meant to be called by the assembly runtime, not directly.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub unsafe extern "C" fn irq_dispatcher(bits: IrqBits) {
+  let unhandled = dispatch_irqs(bits, |i| IRQ_HANDLERS[i].read());
+  if IRQ_COUNTING.read() {
+    let mut counts = IrqCounts::from_counts(irq_counts());
+    counts.record(bits);
+    for (cell, count) in IRQ_COUNTS.iter().zip(counts.counts()) {
+      cell.write(count);
+    }
+  }
+  if cfg!(debug_assertions) && unhandled.0 != 0 {
+    use crate::mgba::{mgba_log_fmt, MgbaMessageLevel};
+    mgba_log_fmt(
+      MgbaMessageLevel::Warning,
+      format_args!("unhandled interrupt, IrqBits: {:#06X}", unhandled.0),
+    )
+    .ok();
+  }
+}
+
+/// The most handlers that [`install_chained`] can chain together (including
+/// the handler that was installed before the first chained one).
+pub const IRQ_CHAIN_LEN: usize = 4;
+
+/// The handlers used by [`chained_irq_handler`], oldest first.
+#[cfg(feature = "on_gba")]
+static IRQ_CHAIN: [GbaCell<Option<IrqFn>>; IRQ_CHAIN_LEN] =
+  [const { GbaCell::new(None) }; IRQ_CHAIN_LEN];
+
+/// Calls each handler in `chain` with `bits`, starting from the *end* of the
+/// slice.
+///
+/// The chain is stored oldest first, so this calls the most recently
+/// installed handler first, and the original handler last.
+///
+/// ```
+/// # use gba::interrupts::*;
+/// # use std::sync::Mutex;
+/// static ORDER: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+/// unsafe extern "C" fn app(_: IrqBits) {
+///   ORDER.lock().unwrap().push("app");
+/// }
+/// unsafe extern "C" fn library(_: IrqBits) {
+///   ORDER.lock().unwrap().push("library");
+/// }
+/// unsafe { call_irq_chain(&[Some(app), None, Some(library)], IrqBits::VBLANK) };
+/// assert_eq!(*ORDER.lock().unwrap(), ["library", "app"]);
+/// ```
+///
+/// ## Safety
+/// * Each handler is called, so this has the same safety requirements as
+///   calling them.
+#[inline]
+pub unsafe fn call_irq_chain(chain: &[Option<IrqFn>], bits: IrqBits) {
+  for handler in chain.iter().rev().flatten() {
+    handler(bits);
+  }
+}
+
+/// The [`RUST_IRQ_HANDLER`](crate::RUST_IRQ_HANDLER) set by
+/// [`install_chained`].
+///
+/// Calls each chained handler, newest first, see [`call_irq_chain`].
+///
+/// ## Safety
+/// * This is meant to be called by the assembly runtime, not directly.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub unsafe extern "C" fn chained_irq_handler(bits: IrqBits) {
+  let chain: [Option<IrqFn>; IRQ_CHAIN_LEN] =
+    core::array::from_fn(|i| IRQ_CHAIN[i].read());
+  call_irq_chain(&chain, bits);
+}
+
+/// [`install_chained`] already has [`IRQ_CHAIN_LEN`] handlers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IrqChainFull;
+
+/// Installs an interrupt handler without replacing the existing one.
+///
+/// This is meant for libraries that need their own interrupt handler. Rather
+/// than overwriting [`RUST_IRQ_HANDLER`](crate::RUST_IRQ_HANDLER) (and
+/// silently breaking the program's handler), the current handler is kept and
+/// [`chained_irq_handler`] is installed in its place.
+///
+/// * When an interrupt fires, `handler` runs first and then the handler that
+///   was installed before it. With more than one chained handler, the most
+///   recently installed one runs first and the original one runs last.
+/// * Every handler is passed the same [`IrqBits`].
+/// * Handlers run one after the other, not nested. If a handler allows nested
+///   interrupts (see [`enter_nestable`]), an interrupt that fires during it
+///   will run the *whole* chain again from the start, so every handler in the
+///   chain must be able to cope with being re-entered in that case.
+/// * Writing `RUST_IRQ_HANDLER` directly after this will remove the whole
+///   chain. A later `install_chained` call will then start a new chain with
+///   whatever handler was written.
+///
+/// `IME` is turned off while the handlers are changed, and then set back to
+/// what it was.
+///
+/// ## Failures
+/// * If there are already [`IRQ_CHAIN_LEN`] handlers in the chain.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn install_chained(handler: IrqFn) -> Result<(), IrqChainFull> {
+  use crate::RUST_IRQ_HANDLER;
+  let ime = IME.read();
+  IME.write(false);
+  let current = RUST_IRQ_HANDLER.read();
+  let chained = chained_irq_handler as IrqFn as usize;
+  let is_chained = current.map(|f| f as usize) == Some(chained);
+  let mut chain: [Option<IrqFn>; IRQ_CHAIN_LEN] =
+    core::array::from_fn(|i| IRQ_CHAIN[i].read());
+  let result = push_chained(&mut chain, current, is_chained, handler);
+  if result.is_ok() {
+    for (cell, h) in IRQ_CHAIN.iter().zip(chain) {
+      cell.write(h);
+    }
+    RUST_IRQ_HANDLER.write(Some(chained_irq_handler));
+  }
+  IME.write(ime);
+  result
+}
+
+/// Adds `handler` to the newest end of `chain`, the work of
+/// [`install_chained`].
+///
+/// `current` is the installed handler, and `is_chained` is if that's already
+/// [`chained_irq_handler`]. If it isn't, the chain is started over with
+/// `current` as its oldest handler.
+#[inline]
+#[cfg(any(feature = "on_gba", test))]
+fn push_chained(
+  chain: &mut [Option<IrqFn>; IRQ_CHAIN_LEN], current: Option<IrqFn>,
+  is_chained: bool, handler: IrqFn,
+) -> Result<(), IrqChainFull> {
+  if !is_chained {
+    *chain = [None; IRQ_CHAIN_LEN];
+    chain[0] = current;
+  }
+  let slot = chain[1..].iter_mut().find(|h| h.is_none()).ok_or(IrqChainFull)?;
+  *slot = Some(handler);
+  Ok(())
+}
+
+/// Counts of how many times each interrupt has fired.
+///
+/// Each count is for one bit of [`IrqBits`], in bit order (vblank first).
+/// Counts stop at `u16::MAX` rather than wrapping.
+///
+/// This is the bookkeeping used by the counting mode of [`irq_dispatcher`]
+/// (see [`set_irq_counting`]), but it can be used on its own too.
+///
+/// ```
+/// # use gba::interrupts::{IrqBits, IrqCounts};
+/// let mut counts = IrqCounts::new();
+/// counts.record(IrqBits::VBLANK.with_timer0(true));
+/// counts.record(IrqBits::TIMER0);
+/// assert_eq!(counts.vblanks(), 1);
+/// assert_eq!(counts.counts()[3], 2);
+/// // a frame where vblank fired twice is the bug we want to spot.
+/// counts.record(IrqBits::VBLANK);
+/// assert!(counts.has_extra_vblanks());
+/// counts.reset();
+/// assert_eq!(counts.counts(), [0; 14]);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct IrqCounts([u16; 14]);
+impl IrqCounts {
+  /// All counts at zero.
+  #[inline]
+  #[must_use]
+  pub const fn new() -> Self {
+    Self([0; 14])
+  }
+
+  /// Wraps existing counts.
+  #[inline]
+  #[must_use]
+  pub const fn from_counts(counts: [u16; 14]) -> Self {
+    Self(counts)
+  }
+
+  /// The count for each interrupt.
+  #[inline]
+  #[must_use]
+  pub const fn counts(&self) -> [u16; 14] {
+    self.0
+  }
+
+  /// The number of vblank interrupts.
+  #[inline]
+  #[must_use]
+  pub const fn vblanks(&self) -> u16 {
+    self.0[0]
+  }
+
+  /// If vblank fired more than once.
+  ///
+  /// When the counts are reset once per frame, this means either the
+  /// program missed a frame, or the vblank interrupt is being handled more
+  /// than once per frame (eg: `IF` isn't being acknowledged properly).
+  #[inline]
+  #[must_use]
+  pub const fn has_extra_vblanks(&self) -> bool {
+    self.vblanks() > 1
+  }
+
+  /// Adds one to the count of each interrupt in `bits`.
+  #[inline]
+  pub fn record(&mut self, bits: IrqBits) {
+    for (i, count) in self.0.iter_mut().enumerate() {
+      if bits.0 & (1 << i) != 0 {
+        *count = count.saturating_add(1);
+      }
+    }
+  }
+
+  /// Sets all counts back to zero.
+  #[inline]
+  pub fn reset(&mut self) {
+    self.0 = [0; 14];
+  }
+}
+
+/// If [`irq_dispatcher`] is counting interrupts.
+#[cfg(feature = "on_gba")]
+static IRQ_COUNTING: GbaCell<bool> = GbaCell::new(false);
+
+/// The counts kept by [`irq_dispatcher`] when counting is on.
+#[cfg(feature = "on_gba")]
+static IRQ_COUNTS: [GbaCell<u16>; IRQ_SOURCES] =
+  [const { GbaCell::new(0) }; IRQ_SOURCES];
+
+/// Turns the counting mode of [`irq_dispatcher`] on or off.
+///
+/// While counting is on, the dispatcher adds up how many times each interrupt
+/// fires (see [`irq_counts`]). This is off by default, since it adds a little
+/// time to every interrupt. Turning it on or off doesn't reset the counts.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn set_irq_counting(enabled: bool) {
+  IRQ_COUNTING.write(enabled);
+}
+
+/// How many times each interrupt has fired since the counts were last reset.
+///
+/// See [`IrqCounts`] for the order of the counts.
+#[inline]
+#[must_use]
+#[cfg(feature = "on_gba")]
+pub fn irq_counts() -> [u16; 14] {
+  let mut counts = [0; 14];
+  for (count, cell) in counts.iter_mut().zip(IRQ_COUNTS.iter()) {
+    *count = cell.read();
+  }
+  counts
+}
+
+/// Sets all of the interrupt counts back to zero.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn reset_irq_counts() {
+  for cell in IRQ_COUNTS.iter() {
+    cell.write(0);
+  }
+}
+
+/// Checks that vblank fired only once since the last call, and then resets
+/// the interrupt counts for the next frame.
+///
+/// Call this once per frame, just after your main loop has waited for vblank,
+/// with counting turned on (see [`set_irq_counting`]). If vblank fired more
+/// than once, a warning with the count is sent to the mGBA log. Either the
+/// main loop took longer than a frame, or the vblank interrupt is being
+/// handled more than once per frame.
+///
+/// Returns the number of vblanks that were counted.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn assert_one_vblank_per_frame() -> u16 {
+  let counts = IrqCounts::from_counts(irq_counts());
+  reset_irq_counts();
+  if counts.has_extra_vblanks() {
+    use crate::mgba::{mgba_log_fmt, MgbaMessageLevel};
+    mgba_log_fmt(
+      MgbaMessageLevel::Warning,
+      format_args!("vblank fired {} times in one frame", counts.vblanks()),
+    )
+    .ok();
+  }
+  counts.vblanks()
+}
+
+/// A snapshot of the interrupt controls, for checking interrupt setup.
+///
+/// When an interrupt handler never runs, one of these is usually why: the
+/// interrupt isn't enabled in `IE`, `IME` is off, or (for a handler that runs
+/// over and over) the interrupt is never acknowledged and stays pending in
+/// `IF`.
+///
+/// ```
+/// # use gba::interrupts::*;
+/// let d = IrqDiagnostics::from_registers(0x0009, 0x0001, 1);
+/// assert_eq!(d.enabled, IrqBits::VBLANK.with_timer0(true));
+/// assert_eq!(d.pending, IrqBits::VBLANK);
+/// assert!(d.master);
+/// assert_eq!(d.deliverable(), IrqBits::VBLANK);
+/// // with IME off, nothing gets through
+/// let d = IrqDiagnostics::from_registers(0x0009, 0x0001, 0);
+/// assert!(!d.master);
+/// assert_eq!(d.deliverable(), IrqBits::new());
+/// assert_eq!(format!("{d}"), "IE=0x0009 IF=0x0001 IME=false");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct IrqDiagnostics {
+  /// The interrupts enabled in `IE`.
+  pub enabled: IrqBits,
+  /// The interrupts pending in `IF`.
+  pub pending: IrqBits,
+  /// If `IME` is on.
+  pub master: bool,
+}
+impl IrqDiagnostics {
+  /// Decodes raw `IE`, `IF`, and `IME` values.
+  ///
+  /// Only the bits that are used by each register are kept.
+  #[inline]
+  #[must_use]
+  pub const fn from_registers(ie: u16, if_: u16, ime: u16) -> Self {
+    Self {
+      enabled: IrqBits(ie & 0x3FFF),
+      pending: IrqBits(if_ & 0x3FFF),
+      master: ime & 1 != 0,
+    }
+  }
+
+  /// The pending interrupts that the CPU will be interrupted for, given these
+  /// settings.
+  #[inline]
+  #[must_use]
+  pub const fn deliverable(&self) -> IrqBits {
+    if self.master {
+      IrqBits(self.enabled.0 & self.pending.0)
+    } else {
+      IrqBits::new()
+    }
+  }
+}
+impl core::fmt::Display for IrqDiagnostics {
+  #[inline]
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(
+      f,
+      "IE={:#06X} IF={:#06X} IME={}",
+      self.enabled.0, self.pending.0, self.master
+    )
+  }
+}
+
+/// Reads `IE`, `IF`, and `IME`.
+#[inline]
+#[must_use]
+#[cfg(feature = "on_gba")]
+pub fn diagnostics() -> IrqDiagnostics {
+  IrqDiagnostics { enabled: IE.read(), pending: IF.read(), master: IME.read() }
+}
+
+/// Reads the interrupt controls (see [`diagnostics`]) and sends them to the
+/// mGBA log as a debug message.
+///
+/// Returns the values that were read.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn log_diagnostics() -> IrqDiagnostics {
+  use crate::mgba::{mgba_log_fmt, MgbaMessageLevel};
+  let d = diagnostics();
+  mgba_log_fmt(MgbaMessageLevel::Debug, format_args!("{d}")).ok();
+  d
+}
+
+/// The interrupt state saved by [`enter_nestable`].
+///
+/// Pass this to [`exit_nestable`] to restore the state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[must_use]
+pub struct NestedIrqState {
+  ie: IrqBits,
+  ime: bool,
+}
+
+/// Allows interrupts to occur again while an interrupt handler is running.
+///
+/// Normally, the CPU masks interrupts for the entire time that the
+/// [`RUST_IRQ_HANDLER`](crate::RUST_IRQ_HANDLER) is running. Calling this
+/// within the handler lets a higher priority interrupt preempt the rest of the
+/// handler:
+/// * `IE` is limited to only the `allowed` interrupts (the old value is kept in
+///   the returned state). Usually you'd only allow interrupts that are "more
+///   important" than the one being handled.
+/// * The CPU's IRQ mask bit is cleared.
+///
+/// `IME` is turned off while the changes are made, and then set back to what
+/// it was.
+///
+/// Once the nestable part of your handler is done, call [`exit_nestable`] with
+/// the returned state before the handler returns.
+///
+/// ## Caveats
+/// * The assembly runtime has already acknowledged the interrupt in both `IF`
+///   and the BIOS interrupt flags before your handler is called, so
+///   [`IntrWait`](crate::bios::IntrWait) works normally even when nesting.
+/// * If you allow the interrupt you're currently handling, it can fire again
+///   and your handler will be re-entered. Only do that if the handler can cope
+///   with being re-entered.
+/// * Each nested interrupt uses 24 bytes of the (very small) IRQ mode stack for
+///   the BIOS, and then runs your handler on the user stack. Deep nesting can
+///   overflow either stack, so keep the number of `allowed` interrupts small
+///   and your handler's stack frame modest.
+///
+/// ## Safety
+/// * This must only be called from within the `RUST_IRQ_HANDLER`.
+/// * Every call must be paired with a call to [`exit_nestable`], using the
+///   returned state, before the handler returns.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub unsafe fn enter_nestable(allowed: IrqBits) -> NestedIrqState {
+  enter_nestable_with(&mut HwIrqControls, allowed, || {
+    set_cpsr_irq_masked(false)
+  })
+}
+
+/// Undoes [`enter_nestable`], restoring the saved interrupt state.
+///
+/// After this call interrupts are masked within the CPU again, so the rest of
+/// the handler can't be preempted.
+///
+/// ## Safety
+/// * This must only be called from within the `RUST_IRQ_HANDLER`, using the
+///   state returned by the matching call to [`enter_nestable`].
+#[inline]
+#[cfg(feature = "on_gba")]
+pub unsafe fn exit_nestable(state: NestedIrqState) {
+  exit_nestable_with(&mut HwIrqControls, state, || set_cpsr_irq_masked(true))
+}
+
+/// Access to `IE` and `IME`, so that the order of the register accesses done
+/// by [`enter_nestable`] and [`exit_nestable`] can be checked off-hardware.
+#[cfg(any(feature = "on_gba", test))]
+trait IrqControls {
+  fn ie(&mut self) -> IrqBits;
+  fn set_ie(&mut self, ie: IrqBits);
+  fn ime(&mut self) -> bool;
+  fn set_ime(&mut self, ime: bool);
+}
+
+/// The real `IE` and `IME` registers.
+#[cfg(feature = "on_gba")]
+struct HwIrqControls;
+#[cfg(feature = "on_gba")]
+impl IrqControls for HwIrqControls {
+  #[inline]
+  fn ie(&mut self) -> IrqBits {
+    IE.read()
+  }
+  #[inline]
+  fn set_ie(&mut self, ie: IrqBits) {
+    IE.write(ie)
+  }
+  #[inline]
+  fn ime(&mut self) -> bool {
+    IME.read()
+  }
+  #[inline]
+  fn set_ime(&mut self, ime: bool) {
+    IME.write(ime)
+  }
+}
+
+/// The register work of [`enter_nestable`], with `unmask` clearing the CPU's
+/// IRQ mask bit.
+#[inline]
+#[cfg(any(feature = "on_gba", test))]
+fn enter_nestable_with(
+  regs: &mut impl IrqControls, allowed: IrqBits, unmask: impl FnOnce(),
+) -> NestedIrqState {
+  let ime = regs.ime();
+  regs.set_ime(false);
+  let ie = regs.ie();
+  regs.set_ie(IrqBits(ie.0 & allowed.0));
+  unmask();
+  regs.set_ime(ime);
+  NestedIrqState { ie, ime }
+}
+
+/// The register work of [`exit_nestable`], with `mask` setting the CPU's IRQ
+/// mask bit.
+#[inline]
+#[cfg(any(feature = "on_gba", test))]
+fn exit_nestable_with(
+  regs: &mut impl IrqControls, state: NestedIrqState, mask: impl FnOnce(),
+) {
+  regs.set_ime(false);
+  mask();
+  regs.set_ie(state.ie);
+  regs.set_ime(state.ime);
+}
+
+/// Sets or clears the IRQ mask bit of the CPSR.
+///
+/// The `msr` instruction is only available in a32 code, so this always uses
+/// a32 even when the caller is t32 code.
+#[inline(never)]
+#[cfg(feature = "on_gba")]
+#[instruction_set(arm::a32)]
+unsafe fn set_cpsr_irq_masked(masked: bool) {
+  if masked {
+    core::arch::asm!(
+      "mrs {tmp}, cpsr",
+      "orr {tmp}, {tmp}, #0x80",
+      "msr cpsr_c, {tmp}",
+      tmp = out(reg) _,
+      options(nostack, preserves_flags)
+    );
+  } else {
+    core::arch::asm!(
+      "mrs {tmp}, cpsr",
+      "bic {tmp}, {tmp}, #0x80",
+      "msr cpsr_c, {tmp}",
+      tmp = out(reg) _,
+      options(nostack, preserves_flags)
+    );
+  }
+}
+
+/// Shares a value between the main program and an interrupt handler.
+///
+/// This holds a pointer to the shared value. The main program sets the
+/// pointer with [`set`](IrqShared::set), and then either side accesses the
+/// value with [`with`](IrqShared::with). `IME` is turned off for the length
+/// of each `with` call, so an interrupt can't fire and access the value while
+/// the main program is using it.
+///
+/// ```no_run
+/// # use gba::prelude::*;
+/// # use gba::interrupts::IrqShared;
+/// struct Stats {
+///   vblanks: u32,
+/// }
+/// static STATS: IrqShared<Stats> = IrqShared::new();
+///
+/// unsafe extern "C" fn on_vblank(_: IrqBits) {
+///   STATS.with(|stats| stats.vblanks += 1);
+/// }
+///
+/// let mut stats = Stats { vblanks: 0 };
+/// STATS.set(&mut stats);
+/// set_irq_handler(IrqBits::VBLANK, Some(on_vblank));
+/// // later, in the main loop
+/// let count = unsafe { STATS.with(|stats| stats.vblanks) };
+/// ```
+#[derive(Debug)]
+#[cfg(any(feature = "on_gba", test))]
+pub struct IrqShared<T>(GbaCell<Option<NonNull<T>>>);
+#[cfg(any(feature = "on_gba", test))]
+impl<T> IrqShared<T> {
+  /// Makes a new, unset, shared value.
+  #[inline]
+  #[must_use]
+  pub const fn new() -> Self {
+    Self(GbaCell::new(None))
+  }
+
+  /// Sets the value to share.
+  ///
+  /// The value must outlive its use through this wrapper: either keep it alive
+  /// for the rest of the program, or [`clear`](IrqShared::clear) this before
+  /// the value goes away.
+  #[inline]
+  pub fn set(&self, value: &mut T) {
+    self.0.write(Some(NonNull::from(value)));
+  }
+
+  /// Stops sharing the value, if any.
+  #[inline]
+  pub fn clear(&self) {
+    self.0.write(None);
+  }
+
+  /// A pointer to the shared value, if one is set.
+  #[inline]
+  #[must_use]
+  pub fn get(&self) -> Option<NonNull<T>> {
+    self.0.read()
+  }
+
+  /// If a value is currently set.
+  #[inline]
+  #[must_use]
+  pub fn is_set(&self) -> bool {
+    self.get().is_some()
+  }
+
+  /// Runs `f` on the shared value, with `IME` off for the length of the call.
+  ///
+  /// Returns `None` without calling `f` if no value is set. `IME` is set back
+  /// to what it was afterwards, so this also works within an interrupt
+  /// handler (which already can't be interrupted, unless you've used
+  /// [`enter_nestable`]).
+  ///
+  /// ## Safety
+  /// * The value passed to [`set`](IrqShared::set) must still be alive.
+  /// * Nothing else can be holding a reference to the value, including a
+  ///   reference from an outer call to `with` (so don't call `with` from within
+  ///   `f`).
+  #[inline]
+  #[cfg(feature = "on_gba")]
+  pub unsafe fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+    unsafe { self.with_controls(&mut HwIrqControls, f) }
+  }
+
+  /// The work of [`with`](IrqShared::with), using `regs` for `IME`.
+  ///
+  /// ## Safety
+  /// * Same as [`with`](IrqShared::with).
+  #[inline]
+  unsafe fn with_controls<R>(
+    &self, regs: &mut impl IrqControls, f: impl FnOnce(&mut T) -> R,
+  ) -> Option<R> {
+    let ime = regs.ime();
+    regs.set_ime(false);
+    let out = self.0.read().map(|mut value| f(unsafe { value.as_mut() }));
+    regs.set_ime(ime);
+    out
+  }
+}
+#[cfg(any(feature = "on_gba", test))]
+impl<T> Default for IrqShared<T> {
+  #[inline]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use core::cell::RefCell;
+
+  /// A register access, in the order they happen.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  enum Access {
+    SetIe(u16),
+    SetIme(bool),
+    CpsrMasked(bool),
+  }
+
+  /// Pretend registers that log every write.
+  struct MockControls<'a> {
+    ie: IrqBits,
+    ime: bool,
+    log: &'a RefCell<[Option<Access>; 8]>,
+  }
+  impl MockControls<'_> {
+    fn push(&self, access: Access) {
+      let mut log = self.log.borrow_mut();
+      let slot = log.iter_mut().find(|a| a.is_none()).unwrap();
+      *slot = Some(access);
+    }
+  }
+  impl IrqControls for MockControls<'_> {
+    fn ie(&mut self) -> IrqBits {
+      self.ie
+    }
+    fn set_ie(&mut self, ie: IrqBits) {
+      self.ie = ie;
+      self.push(Access::SetIe(ie.0));
+    }
+    fn ime(&mut self) -> bool {
+      self.ime
+    }
+    fn set_ime(&mut self, ime: bool) {
+      self.ime = ime;
+      self.push(Access::SetIme(ime));
+    }
+  }
+
+  #[test]
+  fn nestable_saves_and_restores_ime() {
+    use Access::*;
+    let log = RefCell::new([None; 8]);
+    let start = IrqBits::VBLANK.with_timer0(true).with_keypad(true);
+    let mut regs = MockControls { ie: start, ime: true, log: &log };
+    let cpsr = |masked| {
+      let mut log = log.borrow_mut();
+      *log.iter_mut().find(|a| a.is_none()).unwrap() = Some(CpsrMasked(masked));
+    };
+    let state = enter_nestable_with(&mut regs, IrqBits::TIMER0, || cpsr(false));
+    assert_eq!(regs.ie, IrqBits::TIMER0);
+    assert!(regs.ime);
+    exit_nestable_with(&mut regs, state, || cpsr(true));
+    assert_eq!(regs.ie, start);
+    assert!(regs.ime);
+    assert_eq!(
+      *log.borrow(),
+      [
+        Some(SetIme(false)),
+        Some(SetIe(IrqBits::TIMER0.0)),
+        Some(CpsrMasked(false)),
+        Some(SetIme(true)),
+        Some(SetIme(false)),
+        Some(CpsrMasked(true)),
+        Some(SetIe(start.0)),
+        Some(SetIme(true)),
+      ]
+    );
+  }
+
+  #[test]
+  fn nestable_keeps_ime_off() {
+    let log = RefCell::new([None; 8]);
+    let mut regs = MockControls { ie: IrqBits::VBLANK, ime: false, log: &log };
+    let state = enter_nestable_with(&mut regs, IrqBits::VBLANK, || ());
+    assert!(!regs.ime);
+    exit_nestable_with(&mut regs, state, || ());
+    assert!(!regs.ime);
+    assert_eq!(regs.ie, IrqBits::VBLANK);
+  }
+
+  #[test]
+  fn dispatch_reports_unhandled_irqs() {
+    use core::sync::atomic::{AtomicU16, Ordering};
+    static CALLED_WITH: AtomicU16 = AtomicU16::new(0);
+    unsafe extern "C" fn on_vblank(bits: IrqBits) {
+      CALLED_WITH.fetch_or(bits.0, Ordering::Relaxed);
+    }
+    let handler = |i: usize| (i == 0).then_some(on_vblank as IrqFn);
+    let fired = IrqBits::VBLANK.with_timer0(true).with_keypad(true);
+    let unhandled = unsafe { dispatch_irqs(fired, handler) };
+    assert_eq!(unhandled, IrqBits::TIMER0.with_keypad(true));
+    assert_eq!(CALLED_WITH.load(Ordering::Relaxed), IrqBits::VBLANK.0);
+    // with every fired bit handled there's nothing to report
+    let unhandled = unsafe { dispatch_irqs(IrqBits::VBLANK, handler) };
+    assert_eq!(unhandled, IrqBits::new());
+    assert_eq!(unhandled_irqs(fired, fired), IrqBits::new());
+  }
+
+  #[test]
+  fn shared_value_round_trip() {
+    use Access::*;
+    let log = RefCell::new([None; 8]);
+    let mut regs = MockControls { ie: IrqBits::new(), ime: true, log: &log };
+    let shared = IrqShared::<u32>::new();
+    assert!(!shared.is_set());
+    let mut vblanks = 5_u32;
+    shared.set(&mut vblanks);
+    assert!(shared.is_set());
+    let out = unsafe {
+      shared.with_controls(&mut regs, |v| {
+        *v += 1;
+        *v
+      })
+    };
+    assert_eq!(out, Some(6));
+    assert_eq!(vblanks, 6);
+    // with no value set, `f` isn't called, and IME is still put back
+    shared.clear();
+    let out = unsafe { shared.with_controls(&mut regs, |_| unreachable!()) };
+    assert_eq!(out, None::<()>);
+    assert!(regs.ime);
+    assert_eq!(
+      log.borrow()[..4],
+      [
+        Some(SetIme(false)),
+        Some(SetIme(true)),
+        Some(SetIme(false)),
+        Some(SetIme(true))
+      ]
+    );
+  }
+
+  #[test]
+  fn irq_counts_per_frame() {
+    let mut counts = IrqCounts::new();
+    // a frame with some hblanks and a timer, then its vblank
+    for _ in 0..3 {
+      counts.record(IrqBits::HBLANK);
+    }
+    counts.record(IrqBits::TIMER0.with_hblank(true));
+    counts.record(IrqBits::VBLANK);
+    let mut expected = [0; 14];
+    expected[0] = 1;
+    expected[1] = 4;
+    expected[3] = 1;
+    assert_eq!(counts.counts(), expected);
+    assert!(!counts.has_extra_vblanks());
+    // reset at the start of the next frame, which then gets two vblanks
+    counts.reset();
+    assert_eq!(counts, IrqCounts::new());
+    counts.record(IrqBits::VBLANK);
+    counts.record(IrqBits::VBLANK.with_gamepak(true));
+    assert_eq!(counts.vblanks(), 2);
+    assert_eq!(counts.counts()[13], 1);
+    assert!(counts.has_extra_vblanks());
+    // counts saturate rather than wrapping
+    let mut counts = IrqCounts::from_counts([u16::MAX; 14]);
+    counts.record(IrqBits::KEYPAD);
+    assert_eq!(counts.counts()[12], u16::MAX);
+  }
+
+  #[test]
+  fn chained_handlers_run_newest_first() {
+    use core::sync::atomic::{AtomicU32, Ordering};
+    // each handler adds its id as the next hex digit
+    static ORDER: AtomicU32 = AtomicU32::new(0);
+    fn log(id: u32, bits: IrqBits) {
+      assert_eq!(bits, IrqBits::VBLANK);
+      ORDER.store(ORDER.load(Ordering::Relaxed) << 4 | id, Ordering::Relaxed);
+    }
+    unsafe extern "C" fn app(bits: IrqBits) {
+      log(1, bits)
+    }
+    unsafe extern "C" fn lib_a(bits: IrqBits) {
+      log(2, bits)
+    }
+    unsafe extern "C" fn lib_b(bits: IrqBits) {
+      log(3, bits)
+    }
+    unsafe extern "C" fn lib_c(bits: IrqBits) {
+      log(4, bits)
+    }
+    let mut chain = [None; IRQ_CHAIN_LEN];
+    assert_eq!(push_chained(&mut chain, Some(app), false, lib_a), Ok(()));
+    assert_eq!(push_chained(&mut chain, None, true, lib_b), Ok(()));
+    assert_eq!(push_chained(&mut chain, None, true, lib_c), Ok(()));
+    assert_eq!(push_chained(&mut chain, None, true, app), Err(IrqChainFull));
+    unsafe { call_irq_chain(&chain, IrqBits::VBLANK) };
+    assert_eq!(ORDER.load(Ordering::Relaxed), 0x4321);
+    // replacing the chained handler starts a new chain, without an app handler
+    assert_eq!(push_chained(&mut chain, None, false, lib_b), Ok(()));
+    ORDER.store(0, Ordering::Relaxed);
+    unsafe { call_irq_chain(&chain, IrqBits::VBLANK) };
+    assert_eq!(ORDER.load(Ordering::Relaxed), 0x3);
+  }
+
+  #[test]
+  fn handler_placement_classification() {
+    let cases = [
+      // ARM in IWRAM, including the last word of it
+      (0x0300_0000, MemoryRegion::Iwram, false, true),
+      (0x0300_7FFC, MemoryRegion::Iwram, false, true),
+      // Thumb in IWRAM is still slower than it could be
+      (0x0300_0001, MemoryRegion::Iwram, true, false),
+      // ARM or Thumb code anywhere else
+      (0x0200_0400, MemoryRegion::Ewram, false, false),
+      (0x0800_0000, MemoryRegion::Rom, false, false),
+      (0x0800_1235, MemoryRegion::Rom, true, false),
+    ];
+    for (addr, region, thumb, ideal) in cases {
+      let p = IrqHandlerPlacement::of_addr(addr);
+      assert_eq!((p.region, p.thumb), (region, thumb), "{addr:#X}");
+      assert_eq!(p.is_ideal(), ideal, "{addr:#X}");
+    }
+  }
+
+  #[test]
+  fn diagnostics_decode() {
+    extern crate std;
+    use std::string::ToString;
+    // unused high bits (and IME bits past bit 0) are dropped
+    let d = IrqDiagnostics::from_registers(0xC000 | 0x1005, 0xFFFF, 0xFFFE);
+    assert_eq!(
+      d.enabled,
+      IrqBits::VBLANK.with_vcounter(true).with_keypad(true)
+    );
+    assert_eq!(d.pending.0, 0x3FFF);
+    assert!(!d.master);
+    assert_eq!(d.deliverable(), IrqBits::new());
+    // a vblank that's pending but not enabled isn't delivered
+    let d = IrqDiagnostics::from_registers(0x0008, 0x0009, 1);
+    assert_eq!(d.deliverable(), IrqBits::TIMER0);
+    assert_eq!(d.to_string(), "IE=0x0008 IF=0x0009 IME=true");
+  }
+}
@@ -22,6 +22,8 @@
 //! of the normal game simulation when the flag is set.
 
 use crate::macros::{pub_const_fn_new_zeroed, u16_bool_field};
+#[cfg(feature = "on_gba")]
+use crate::{gba_cell::GbaCell, interrupts::IrqBits, mmio::KEYINPUT};
 use core::ops;
 
 /// [`KEYINPUT`](crate::prelude::KEYINPUT): Key input data.
@@ -161,3 +163,142 @@ impl KeyControl {
   u16_bool_field!(14, irq_enabled, with_irq_enabled);
   u16_bool_field!(15, irq_all, with_irq_all);
 }
+
+/// The key input as of the most recent [`capture_keys`] call.
+///
+/// Starts with all keys released.
+#[cfg(feature = "on_gba")]
+pub static LATEST_INPUT: GbaCell<KeyInput> = GbaCell::new(KeyInput::new());
+
+/// An interrupt handler that reads [`KEYINPUT`] into [`LATEST_INPUT`].
+///
+/// Set this as the handler for the keypad interrupt (or call it from your own
+/// handler) to get the exact key state at the moment the interrupt fired,
+/// rather than whatever the keys are by the time the main program looks.
+///
+/// ```no_run
+/// # use gba::prelude::*;
+/// KEYCNT.write(KeyControl::new().with_start(true).with_irq_enabled(true));
+/// RUST_IRQ_HANDLER.write(Some(capture_keys));
+/// IE.write(IrqBits::new().with_keypad(true));
+/// IME.write(true);
+/// // later, in the main loop
+/// let keys = LATEST_INPUT.read();
+/// ```
+#[cfg(feature = "on_gba")]
+#[inline]
+pub extern "C" fn capture_keys(_: IrqBits) {
+  LATEST_INPUT.write(KEYINPUT.read());
+}
+
+/// Filters out key "bounce" by only reporting a change in a key once the new
+/// state has lasted for a number of frames in a row.
+///
+/// Call [`update`](Debounced::update) once per frame with that frame's raw
+/// `KEYINPUT` value. A key that reads as pressed for a single frame (or
+/// released for a single frame, in the middle of being held) won't show up as
+/// a change unless it lasts long enough.
+///
+/// The cost is latency: with a setting of `frames`, every press and release is
+/// reported `frames - 1` frames later than it happened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Debounced {
+  frames: u8,
+  stable: KeyInput,
+  /// For each key, how many frames in a row the raw state has differed from
+  /// the stable state.
+  counts: [u8; 10],
+}
+impl Debounced {
+  /// Makes a filter that needs `frames` frames in a row to accept a change.
+  ///
+  /// Values of 0 and 1 both mean "accept changes immediately". All keys start
+  /// as released.
+  #[inline]
+  #[must_use]
+  pub const fn new(frames: u8) -> Self {
+    Self { frames, stable: KeyInput::new(), counts: [0; 10] }
+  }
+
+  /// The current filtered key state.
+  #[inline]
+  #[must_use]
+  pub const fn keys(&self) -> KeyInput {
+    self.stable
+  }
+
+  /// Adds a new frame of raw input, returning the filtered key state.
+  #[inline]
+  pub fn update(&mut self, raw: KeyInput) -> KeyInput {
+    let mut stable = self.stable.to_u16();
+    let differs = raw.to_u16() ^ stable;
+    for (i, count) in self.counts.iter_mut().enumerate() {
+      let bit = 1 << i;
+      if differs & bit == 0 {
+        *count = 0;
+        continue;
+      }
+      *count = count.saturating_add(1);
+      if *count >= self.frames {
+        stable ^= bit;
+        *count = 0;
+      }
+    }
+    self.stable = KeyInput::from(stable);
+    self.stable
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn debounce_filters_glitches() {
+    let up = KeyInput::new();
+    let a = KeyInput::new().with_a(true);
+    let mut keys = Debounced::new(3);
+    // a one frame press is ignored
+    assert_eq!(keys.update(a), up);
+    assert_eq!(keys.update(up), up);
+    // a sustained press shows up on the third frame
+    assert_eq!(keys.update(a), up);
+    assert_eq!(keys.update(a), up);
+    assert_eq!(keys.update(a), a);
+    // a one frame release in the middle of the hold is ignored too
+    assert_eq!(keys.update(up), a);
+    assert_eq!(keys.update(a), a);
+    assert_eq!(keys.update(up), a);
+    assert_eq!(keys.update(up), a);
+    assert_eq!(keys.update(up), up);
+    assert_eq!(keys.keys(), up);
+  }
+
+  #[test]
+  fn debounce_keys_are_independent() {
+    let mut keys = Debounced::new(2);
+    let b = KeyInput::new().with_b(true);
+    let both = b.with_l(true);
+    assert_eq!(keys.update(b), KeyInput::new());
+    assert_eq!(keys.update(both), b);
+    assert_eq!(keys.update(both), both);
+    // a setting of 1 (or 0) accepts changes immediately
+    assert_eq!(Debounced::new(1).update(both), both);
+    assert_eq!(Debounced::new(0).update(both), both);
+  }
+
+  #[test]
+  fn key_input_cell_round_trip() {
+    // `capture_keys` itself needs the hardware, but what it stores has to
+    // come back out of a `GbaCell` unchanged, like `LATEST_INPUT` does
+    use crate::gba_cell::GbaCell;
+    let latest = GbaCell::new(KeyInput::new());
+    // a `KEYINPUT` value with A and Up held
+    let keys = KeyInput::from(0x03FF & !(1 << 0) & !(1 << 6));
+    latest.write(keys);
+    let read = latest.read();
+    assert_eq!(read, keys);
+    assert!(read.a() && read.up());
+    assert!(!read.b() && !read.down());
+  }
+}
@@ -101,22 +101,22 @@ pub mod bios;
 pub mod builtin_art;
 #[cfg(feature = "critical-section")]
 mod critical_section;
-#[cfg(feature = "on_gba")]
 pub mod dma;
 pub mod fixed;
-#[cfg(feature = "on_gba")]
+#[cfg(any(feature = "on_gba", test))]
 pub mod gba_cell;
 pub mod interrupts;
 pub mod keys;
 pub mod mem;
-#[cfg(feature = "on_gba")]
 pub mod mgba;
 #[cfg(feature = "on_gba")]
 pub mod mmio;
 pub mod prelude;
 pub mod random;
+pub mod save;
 pub mod sound;
 pub mod timers;
+pub mod util;
 pub mod video;
 
 /// The function pointer that the assembly runtime calls when an interrupt
@@ -154,3 +154,60 @@ macro_rules! u8_int_field {
   };
 }
 pub(crate) use u8_int_field;
+
+/// Logs the value of an expression to mGBA and then returns the value.
+///
+/// This works like the standard library's `dbg!` macro: the message has the
+/// form `file:line: expr = value` (see [`DbgMessage`]), and is sent at
+/// [`MgbaMessageLevel::Debug`]. Passing several expressions logs each of them
+/// and returns a tuple of the values.
+///
+/// Logging goes through [`mgba_log_fmt`], so it's safe to use within an
+/// interrupt handler. When the program isn't running in mGBA the message is
+/// silently discarded, and the only cost is formatting the value.
+///
+/// [`MgbaMessageLevel::Debug`]: crate::mgba::MgbaMessageLevel::Debug
+/// [`mgba_log_fmt`]: crate::mgba::mgba_log_fmt
+/// [`DbgMessage`]: crate::mgba::DbgMessage
+///
+/// ```no_run
+/// # use gba::prelude::*;
+/// let x = gba::dbg!(2 + 3);
+/// assert_eq!(x, 5);
+/// ```
+#[macro_export]
+#[cfg(feature = "on_gba")]
+macro_rules! dbg {
+  () => {{
+    $crate::mgba::mgba_log_fmt(
+      $crate::mgba::MgbaMessageLevel::Debug,
+      format_args!("{}:{}", file!(), line!()),
+    )
+    .ok();
+  }};
+  ($val:expr $(,)?) => {
+    // The `match` keeps temporaries alive for the whole
+    // expression, the same as `std::dbg!` does.
+    match $val {
+      tmp => {
+        $crate::mgba::mgba_log_fmt(
+          $crate::mgba::MgbaMessageLevel::Debug,
+          format_args!(
+            "{}",
+            $crate::mgba::DbgMessage {
+              file: file!(),
+              line: line!(),
+              expr: stringify!($val),
+              value: &tmp,
+            }
+          ),
+        )
+        .ok();
+        tmp
+      }
+    }
+  };
+  ($($val:expr),+ $(,)?) => {
+    ($($crate::dbg!($val)),+,)
+  };
+}
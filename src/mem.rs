@@ -52,6 +52,8 @@ pub unsafe extern "C" fn copy_u8_unchecked(
 ///   specify, and the destination must be writable for the size you specify.
 /// * Both pointers must be aligned to 4.
 /// * The regions must not overlap.
+/// * `dest` can't be in SRAM. This isn't checked here, since the panic code
+///   would have to go in IWRAM too, but [`copy_u32x8`] checks it.
 #[cfg_attr(feature = "on_gba", instruction_set(arm::a32))]
 #[cfg_attr(feature = "on_gba", link_section = ".iwram.copy_u32x8_unchecked")]
 pub unsafe extern "C" fn copy_u32x8_unchecked(
@@ -87,6 +89,20 @@ pub unsafe extern "C" fn copy_u32x8_unchecked(
   });
 }
 
+/// Calls [`copy_u32x8_unchecked`], first checking that `dest` isn't in SRAM.
+///
+/// ## Safety
+/// * The same as [`copy_u32x8_unchecked`]. With `debug_assertions` on, a `dest`
+///   in SRAM is checked, and will panic.
+#[inline]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub unsafe fn copy_u32x8(
+  dest: *mut [u32; 8], src: *const [u32; 8], count: usize,
+) {
+  debug_assert_not_sram(dest as usize);
+  unsafe { copy_u32x8_unchecked(dest, src, count) }
+}
+
 /// Sets `word` in blocks of 80 per loop.
 ///
 /// This is intended for clearing VRAM to a particular color when using
@@ -94,6 +110,9 @@ pub unsafe extern "C" fn copy_u32x8_unchecked(
 /// * To clear the Mode 3 bitmap, pass `240` as the count.
 /// * To clear a Mode 4 frame pass `120`.
 /// * To clear a Mode 5 frame pass `128`.
+///
+/// `dest` can't be in SRAM. This isn't checked here, but [`set_u32x80`] checks
+/// it.
 #[cfg_attr(feature = "on_gba", instruction_set(arm::a32))]
 #[cfg_attr(feature = "on_gba", link_section = ".iwram.set_u32x80_unchecked")]
 pub unsafe extern "C" fn set_u32x80_unchecked(
@@ -137,6 +156,259 @@ pub unsafe extern "C" fn set_u32x80_unchecked(
   });
 }
 
+/// Calls [`set_u32x80_unchecked`], first checking that `dest` isn't in SRAM.
+///
+/// ## Safety
+/// * `dest` must be aligned to 4 and writable for `count * 80` words.
+/// * With `debug_assertions` on, a `dest` in SRAM is checked, and will panic.
+#[inline]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub unsafe fn set_u32x80(dest: *mut [u32; 80], word: u32, count: usize) {
+  debug_assert_not_sram(dest as usize);
+  unsafe { set_u32x80_unchecked(dest, word, count) }
+}
+
+/// A region of the GBA's memory map.
+///
+/// Several regions are mirrored through the rest of their address range (eg:
+/// IWRAM repeats every 32K up to `0x0400_0000`), and mirrors count as part of
+/// the region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MemoryRegion {
+  /// The system BIOS, `0x0000_0000`.
+  Bios,
+  /// External work RAM, `0x0200_0000`.
+  Ewram,
+  /// Internal work RAM, `0x0300_0000`.
+  Iwram,
+  /// Memory mapped IO, `0x0400_0000`.
+  Mmio,
+  /// Palette RAM, `0x0500_0000`.
+  Palram,
+  /// Video RAM, `0x0600_0000`.
+  Vram,
+  /// Object attribute memory, `0x0700_0000`.
+  Oam,
+  /// The gamepak ROM (all three wait state mirrors), `0x0800_0000`.
+  Rom,
+  /// The gamepak SRAM, `0x0E00_0000`.
+  Sram,
+  /// Any address that isn't in one of the other regions.
+  Unmapped,
+}
+impl MemoryRegion {
+  /// Determines which region an address is in.
+  #[inline]
+  #[must_use]
+  pub const fn of_addr(addr: usize) -> Self {
+    if addr >= 0x1000_0000 {
+      return Self::Unmapped;
+    }
+    match addr >> 24 {
+      0x0 if addr < 0x4000 => Self::Bios,
+      0x2 => Self::Ewram,
+      0x3 => Self::Iwram,
+      0x4 => Self::Mmio,
+      0x5 => Self::Palram,
+      0x6 => Self::Vram,
+      0x7 => Self::Oam,
+      0x8..=0xD => Self::Rom,
+      0xE | 0xF => Self::Sram,
+      _ => Self::Unmapped,
+    }
+  }
+
+  /// If the region can be accessed with 16 and 32 bit loads and stores,
+  /// including by DMA.
+  ///
+  /// This is only false for SRAM, which has an 8-bit bus. A wider access to
+  /// SRAM doesn't fault, it just reads or writes the wrong data.
+  ///
+  /// ```
+  /// # use gba::mem::MemoryRegion;
+  /// assert!(MemoryRegion::of_addr(0x0600_0000).supports_wide_access());
+  /// assert!(!MemoryRegion::of_addr(0x0E00_0010).supports_wide_access());
+  /// ```
+  #[inline]
+  #[must_use]
+  pub const fn supports_wide_access(self) -> bool {
+    !matches!(self, Self::Sram)
+  }
+}
+
+/// With `debug_assertions` on, panics if `addr` is within SRAM.
+///
+/// This is for the DMA copies and [`copy_u32x8`]/[`set_u32x80`], which use 16
+/// or 32 bit writes that would corrupt SRAM rather than write to it.
+#[inline]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub(crate) fn debug_assert_not_sram(addr: usize) {
+  debug_assert!(
+    MemoryRegion::of_addr(addr).supports_wide_access(),
+    "destination {addr:#010X} is in SRAM, which only allows 8-bit access"
+  );
+}
+
+/// The smallest copy, in bytes, that [`fast_copy`] will use DMA for.
+///
+/// Starting a DMA transfer takes several register writes plus a short startup
+/// delay, so for small copies a normal CPU copy finishes first.
+pub const FAST_COPY_DMA_THRESHOLD: usize = 128;
+
+/// How [`fast_copy`] will perform a particular copy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CopyStrategy {
+  /// DMA3, using 32-bit transfers.
+  Dma32,
+  /// DMA3, using 16-bit transfers.
+  Dma16,
+  /// A normal CPU copy.
+  Cpu,
+}
+
+/// Determines how [`fast_copy`] would copy `bytes` bytes from `src` to
+/// `dest`.
+///
+/// DMA is used when all of the following are true:
+/// * The copy is at least [`FAST_COPY_DMA_THRESHOLD`] bytes.
+/// * Both addresses and the byte count are aligned to 4 (for `Dma32`) or to 2
+///   (for `Dma16`).
+/// * Neither address is within SRAM, which DMA can't access.
+#[inline]
+#[must_use]
+pub const fn fast_copy_strategy(
+  dest: usize, src: usize, bytes: usize,
+) -> CopyStrategy {
+  const fn is_sram(addr: usize) -> bool {
+    matches!(MemoryRegion::of_addr(addr), MemoryRegion::Sram)
+  }
+  if bytes < FAST_COPY_DMA_THRESHOLD || is_sram(dest) || is_sram(src) {
+    return CopyStrategy::Cpu;
+  }
+  let all = dest | src | bytes;
+  if all & 0b11 == 0 {
+    CopyStrategy::Dma32
+  } else if all & 0b1 == 0 {
+    CopyStrategy::Dma16
+  } else {
+    CopyStrategy::Cpu
+  }
+}
+
+/// Copies `bytes` bytes from `src` to `dest`, using DMA3 when it's worth it.
+///
+/// See [`fast_copy_strategy`] for how the copy method is picked. When DMA
+/// isn't used, this is a normal `memcpy`.
+///
+/// While DMA runs the CPU is completely halted, including interrupt handling,
+/// so a very large copy can delay an interrupt. If you need interrupts handled
+/// on time, split large copies up.
+///
+/// ## Safety
+/// * `src` must be readable for `bytes` bytes, and `dest` must be writable for
+///   `bytes` bytes.
+/// * The regions must not overlap.
+/// * Because DMA might be used, see the [DMA safety notes](crate::dma): this is
+///   intended for copying into VRAM, PALRAM, and OAM.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub unsafe fn fast_copy(dest: *mut u8, src: *const u8, bytes: usize) {
+  use crate::dma::{dma3_copy_u16, dma3_copy_u32};
+  match fast_copy_strategy(dest as usize, src as usize, bytes) {
+    CopyStrategy::Dma32 => dma3_copy_u32(src.cast(), dest.cast(), bytes / 4),
+    CopyStrategy::Dma16 => dma3_copy_u16(src.cast(), dest.cast(), bytes / 2),
+    CopyStrategy::Cpu => core::ptr::copy_nonoverlapping(src, dest, bytes),
+  }
+}
+
+/// Reads a `u32` from an address that might not be aligned.
+///
+/// The GBA's CPU can't do a misaligned word load: it reads the aligned word
+/// and rotates it, giving you a scrambled value instead of the data at that
+/// address. This reads the value one byte at a time instead, which is what you
+/// need for packed data formats (eg: a `u32` field at an odd offset within a
+/// ROM asset).
+///
+/// The bytes are combined as little-endian, the GBA's native byte order.
+///
+/// ## Safety
+/// * `ptr` must be readable for 4 bytes. It has no alignment requirement.
+#[inline]
+#[must_use]
+pub unsafe fn read_unaligned_u32(ptr: *const u8) -> u32 {
+  u32::from_le_bytes(ptr.cast::<[u8; 4]>().read())
+}
+
+/// Writes a `u32` to an address that might not be aligned.
+///
+/// See [`read_unaligned_u32`] for why this is needed.
+///
+/// ## Safety
+/// * `ptr` must be writable for 4 bytes. It has no alignment requirement.
+#[inline]
+pub unsafe fn write_unaligned_u32(ptr: *mut u8, value: u32) {
+  ptr.cast::<[u8; 4]>().write(value.to_le_bytes())
+}
+
+/// Reads a `u64` from an address that might not be aligned.
+///
+/// See [`read_unaligned_u32`] for why this is needed.
+///
+/// ## Safety
+/// * `ptr` must be readable for 8 bytes. It has no alignment requirement.
+#[inline]
+#[must_use]
+pub unsafe fn read_unaligned_u64(ptr: *const u8) -> u64 {
+  u64::from_le_bytes(ptr.cast::<[u8; 8]>().read())
+}
+
+/// Writes a `u64` to an address that might not be aligned.
+///
+/// See [`read_unaligned_u32`] for why this is needed.
+///
+/// ## Safety
+/// * `ptr` must be writable for 8 bytes. It has no alignment requirement.
+#[inline]
+pub unsafe fn write_unaligned_u64(ptr: *mut u8, value: u64) {
+  ptr.cast::<[u8; 8]>().write(value.to_le_bytes())
+}
+
+/// The value of the `MEMCNT` register when the GBA boots.
+///
+/// This gives EWRAM 2 wait states (3 cycles per access).
+pub const MEMCNT_DEFAULT: u32 = 0x0D00_0020;
+
+/// A `MEMCNT` value that gives EWRAM 1 wait state (2 cycles per access).
+pub const MEMCNT_FAST_EWRAM: u32 = 0x0E00_0020;
+
+/// Lowers the EWRAM wait states from 2 to 1, making EWRAM accesses about a
+/// third faster.
+///
+/// This uses the `MEMCNT` register at `0x0400_0800`, which is **not officially
+/// documented**. The setting works on the GBA, the GBA SP, and most emulators,
+/// but not every unit supports it: the GBA mode of the Nintendo DS (and
+/// reportedly the GBA Micro) will lock up when EWRAM is run at this speed. Use
+/// [`set_ewram_wait_default`] to go back to the normal setting.
+///
+/// ## Safety
+/// * The program must only be run on hardware that supports the faster
+///   setting. There's no way to detect this from software.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub unsafe fn set_ewram_wait_fast() {
+  crate::mmio::MEMCNT.write(MEMCNT_FAST_EWRAM)
+}
+
+/// Restores the EWRAM wait states to the boot setting.
+///
+/// This undoes [`set_ewram_wait_fast`].
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn set_ewram_wait_default() {
+  // Safety: this is the value the hardware starts with.
+  unsafe { crate::mmio::MEMCNT.write(MEMCNT_DEFAULT) }
+}
+
 #[cfg(feature = "aeabi_mem_fns")]
 pub use aeabi_mem_fns::*;
 #[cfg(feature = "aeabi_mem_fns")]
@@ -826,3 +1098,109 @@ mod aeabi_mem_fns {
     )
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn not_sram_allows_ewram() {
+    debug_assert_not_sram(0x0200_0000);
+    debug_assert_not_sram(0x0600_0000);
+  }
+
+  #[test]
+  #[cfg(debug_assertions)]
+  #[should_panic = "is in SRAM"]
+  fn not_sram_rejects_sram() {
+    debug_assert_not_sram(0x0E00_0000);
+  }
+
+  #[test]
+  #[cfg(debug_assertions)]
+  #[should_panic = "is in SRAM"]
+  fn copy_u32x8_rejects_sram() {
+    unsafe { copy_u32x8(0x0E00_0000 as *mut _, [[0; 8]].as_ptr(), 1) };
+  }
+
+  #[test]
+  #[cfg(debug_assertions)]
+  #[should_panic = "is in SRAM"]
+  fn set_u32x80_rejects_sram() {
+    unsafe { set_u32x80(0x0E00_0100 as *mut _, 0, 1) };
+  }
+
+  #[test]
+  fn fast_copy_strategy_selection() {
+    use CopyStrategy::*;
+    let ewram = 0x0200_0000;
+    let vram = 0x0600_0000;
+    let t = FAST_COPY_DMA_THRESHOLD;
+    assert_eq!(fast_copy_strategy(vram, ewram, t), Dma32);
+    assert_eq!(fast_copy_strategy(vram, ewram, t - 4), Cpu);
+    assert_eq!(fast_copy_strategy(vram, ewram, t + 2), Dma16);
+    assert_eq!(fast_copy_strategy(vram + 2, ewram, t), Dma16);
+    assert_eq!(fast_copy_strategy(vram, ewram + 1, t), Cpu);
+    assert_eq!(fast_copy_strategy(vram, ewram, t + 1), Cpu);
+    assert_eq!(fast_copy_strategy(0x0E00_0000, ewram, t), Cpu);
+    assert_eq!(fast_copy_strategy(vram, 0x0E00_0000, t), Cpu);
+  }
+
+  #[test]
+  fn memory_region_classification() {
+    use MemoryRegion::*;
+    let cases = [
+      (0x0000_0000, Bios),
+      (0x0000_3FFF, Bios),
+      (0x0000_4000, Unmapped),
+      (0x0100_0000, Unmapped),
+      (0x0200_0000, Ewram),
+      (0x02FF_FFFF, Ewram),
+      (0x0300_7FFC, Iwram),
+      // IWRAM mirrors are still IWRAM
+      (0x0301_0000, Iwram),
+      (0x0400_0000, Mmio),
+      (0x0500_01FE, Palram),
+      (0x0601_7FFF, Vram),
+      (0x0700_03FF, Oam),
+      (0x0800_0000, Rom),
+      (0x0DFF_FFFF, Rom),
+      (0x0E00_0000, Sram),
+      (0x0FFF_FFFF, Sram),
+      (0x1000_0000, Unmapped),
+      (usize::MAX, Unmapped),
+    ];
+    for (addr, region) in cases {
+      assert_eq!(MemoryRegion::of_addr(addr), region, "{addr:#X}");
+    }
+  }
+
+  #[test]
+  fn unaligned_access_is_little_endian() {
+    let mut bytes = [0_u8; 12];
+    let base = bytes.as_mut_ptr();
+    unsafe {
+      write_unaligned_u32(base.add(1), 0x1234_5678);
+      assert_eq!(bytes[..6], [0, 0x78, 0x56, 0x34, 0x12, 0]);
+      assert_eq!(read_unaligned_u32(bytes.as_ptr().add(1)), 0x1234_5678);
+      assert_eq!(read_unaligned_u32(bytes.as_ptr().add(2)), 0x0012_3456);
+      let base = bytes.as_mut_ptr();
+      write_unaligned_u64(base.add(3), 0x0102_0304_0506_0708);
+      assert_eq!(bytes[3..11], [8, 7, 6, 5, 4, 3, 2, 1]);
+      assert_eq!(
+        read_unaligned_u64(bytes.as_ptr().add(3)),
+        0x0102_0304_0506_0708
+      );
+    }
+  }
+
+  #[test]
+  fn memcnt_ewram_wait_values() {
+    // bits 24-27 are 15 minus the EWRAM wait states, the rest must not change
+    let wait_states = |memcnt: u32| 15 - ((memcnt >> 24) & 0xF);
+    assert_eq!(wait_states(MEMCNT_DEFAULT), 2);
+    assert_eq!(wait_states(MEMCNT_FAST_EWRAM), 1);
+    assert_eq!(MEMCNT_DEFAULT & !0x0F00_0000, MEMCNT_FAST_EWRAM & !0x0F00_0000);
+    assert_eq!(MEMCNT_FAST_EWRAM, 0x0E00_0020);
+  }
+}
@@ -15,12 +15,14 @@
 //! check if mGBA logging is possible.
 //!
 //! ```no_run
+//! # #[cfg(feature = "on_gba")] {
 //! # use gba::prelude::*;
 //! use core::fmt::Write;
 //! let log_level = MgbaMessageLevel::Debug;
 //! if let Ok(logger) = MgbaBufferedLogger::try_new(log_level) {
 //!   writeln!(logger, "hello").ok();
 //! }
+//! # }
 //! ```
 //!
 //! ## Fine Details
@@ -41,7 +43,8 @@
 //! logs at that message level and also implicitly zeroes the message buffer so
 //! that it's ready for the next message.
 
-use crate::mmio::{MGBA_LOG_BUFFER, MGBA_LOG_ENABLE, MGBA_LOG_SEND};
+#[cfg(feature = "on_gba")]
+use crate::mmio::{IME, MGBA_LOG_BUFFER, MGBA_LOG_ENABLE, MGBA_LOG_SEND};
 
 pub const MGBA_LOGGING_ENABLE_REQUEST: u16 = 0xC0DE;
 
@@ -61,15 +64,96 @@ pub enum MgbaMessageLevel {
 
 /// Returns if mGBA logging is possible.
 #[inline]
+#[cfg(feature = "on_gba")]
 pub fn mgba_logging_available() -> bool {
   // the `__start` function writes the request, so here we just check success.
   MGBA_LOG_ENABLE.read() == MGBA_LOGGING_ENABLE_RESPONSE
 }
 
+/// Does the mGBA detection handshake using the given register accessors.
+///
+/// This is the handshake that mGBA documents: write
+/// [`MGBA_LOGGING_ENABLE_REQUEST`] to the enable register, then read it back.
+/// mGBA answers with [`MGBA_LOGGING_ENABLE_RESPONSE`], while on hardware (or
+/// another emulator) the address isn't connected to anything and reads back
+/// some other value.
+///
+/// [`is_mgba`] calls this with [`MGBA_LOG_ENABLE`]. Taking the accesses as
+/// closures lets the logic be checked against a pretend register.
+///
+/// ```
+/// # use gba::mgba::*;
+/// use core::cell::Cell;
+/// let reg = Cell::new(0_u16);
+/// // a register that answers like mGBA does
+/// let found = mgba_handshake(
+///   |v| reg.set(if v == MGBA_LOGGING_ENABLE_REQUEST { 0x1DEA } else { 0 }),
+///   || reg.get(),
+/// );
+/// assert!(found);
+/// // open bus, which just reads back junk
+/// assert!(!mgba_handshake(|_| (), || 0x0000));
+/// ```
+#[inline]
+pub fn mgba_handshake(
+  write: impl FnOnce(u16), read: impl FnOnce() -> u16,
+) -> bool {
+  write(MGBA_LOGGING_ENABLE_REQUEST);
+  read() == MGBA_LOGGING_ENABLE_RESPONSE
+}
+
+/// Checks if the program is running in mGBA.
+///
+/// This does the detection handshake (see [`mgba_handshake`]) again, rather
+/// than relying on the one done at boot by the assembly runtime. Outside of
+/// mGBA the write to the enable register does no harm.
+#[inline]
+#[must_use]
+#[cfg(feature = "on_gba")]
+pub fn is_mgba() -> bool {
+  mgba_handshake(|v| MGBA_LOG_ENABLE.write(v), || MGBA_LOG_ENABLE.read())
+}
+
+/// Logs a formatted message at the given level.
+///
+/// Interrupts are disabled (via [`IME`]) while the message is written and
+/// sent, so an interrupt handler that also logs can't interleave its bytes
+/// with this message. The previous `IME` setting is restored afterward.
+///
+/// Fails if mGBA logging isn't available.
+///
+/// ```no_run
+/// # use gba::prelude::*;
+/// let frame = 7;
+/// mgba_log_fmt(MgbaMessageLevel::Info, format_args!("frame {frame}")).ok();
+/// ```
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn mgba_log_fmt(
+  level: MgbaMessageLevel, args: core::fmt::Arguments,
+) -> Result<(), ()> {
+  use core::fmt::Write;
+  let ime = IME.read();
+  IME.write(false);
+  let out = match MgbaBufferedLogger::try_new(level) {
+    Ok(mut logger) => {
+      logger.write_fmt(args).ok();
+      // the logger flushes any final partial line when it drops.
+      drop(logger);
+      Ok(())
+    }
+    Err(()) => Err(()),
+  };
+  IME.write(ime);
+  out
+}
+
+#[cfg(feature = "on_gba")]
 pub struct MgbaBufferedLogger {
   byte_count: u8,
   pub message_level: MgbaMessageLevel,
 }
+#[cfg(feature = "on_gba")]
 impl MgbaBufferedLogger {
   #[inline]
   pub fn try_new(message_level: MgbaMessageLevel) -> Result<Self, ()> {
@@ -84,6 +168,7 @@ impl MgbaBufferedLogger {
     self.byte_count = 0;
   }
 }
+#[cfg(feature = "on_gba")]
 impl Drop for MgbaBufferedLogger {
   #[inline]
   fn drop(&mut self) {
@@ -92,6 +177,7 @@ impl Drop for MgbaBufferedLogger {
     }
   }
 }
+#[cfg(feature = "on_gba")]
 impl core::fmt::Write for MgbaBufferedLogger {
   #[inline]
   fn write_str(&mut self, s: &str) -> core::fmt::Result {
@@ -110,3 +196,181 @@ impl core::fmt::Write for MgbaBufferedLogger {
     Ok(())
   }
 }
+
+/// One message logged by the [`dbg!`](crate::dbg) macro.
+///
+/// This displays as `file:line: expr = value`, with the value in its
+/// [`Debug`](core::fmt::Debug) format.
+#[derive(Debug)]
+pub struct DbgMessage<'a, T: ?Sized> {
+  /// The file the macro was used in.
+  pub file: &'static str,
+  /// The line the macro was used on.
+  pub line: u32,
+  /// The source text of the expression.
+  pub expr: &'static str,
+  /// The value of the expression.
+  pub value: &'a T,
+}
+impl<T: core::fmt::Debug + ?Sized> core::fmt::Display for DbgMessage<'_, T> {
+  #[inline]
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{}:{}: {} = {:?}", self.file, self.line, self.expr, self.value)
+  }
+}
+
+/// The number of bytes shown on each line of a hex dump.
+pub const HEX_DUMP_WIDTH: usize = 16;
+
+/// One line of a hex dump: the address, then the bytes in hex, then the bytes
+/// as ASCII.
+///
+/// Bytes that aren't printable ASCII are shown as `.` in the ASCII column. A
+/// line with fewer than [`HEX_DUMP_WIDTH`] bytes is padded so that the ASCII
+/// column still lines up. A full line is 76 characters, well within the mGBA
+/// message buffer.
+#[derive(Debug, Clone, Copy)]
+pub struct HexDumpLine<'a> {
+  /// The address of the first byte.
+  pub addr: usize,
+  /// The bytes of the line, at most [`HEX_DUMP_WIDTH`] are shown.
+  pub bytes: &'a [u8],
+}
+impl core::fmt::Display for HexDumpLine<'_> {
+  #[inline]
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let bytes = &self.bytes[..self.bytes.len().min(HEX_DUMP_WIDTH)];
+    write!(f, "{:08X}: ", self.addr)?;
+    for i in 0..HEX_DUMP_WIDTH {
+      match bytes.get(i) {
+        Some(b) => write!(f, "{b:02X} ")?,
+        None => f.write_str("   ")?,
+      }
+    }
+    f.write_str("|")?;
+    for b in bytes {
+      let c = if b.is_ascii_graphic() || *b == b' ' { *b as char } else { '.' };
+      write!(f, "{c}")?;
+    }
+    f.write_str("|")
+  }
+}
+
+/// Writes a hex dump of `bytes` to `out`, one [`HexDumpLine`] per
+/// [`HEX_DUMP_WIDTH`] bytes, each ending with a newline.
+///
+/// The addresses shown start at `addr`.
+///
+/// ```
+/// # use gba::mgba::write_hex_dump;
+/// let mut out = String::new();
+/// write_hex_dump(&mut out, 0x0200_0000, b"Hello, GBA!\0\x01\x02\xFF\n").unwrap();
+/// assert_eq!(
+///   out,
+///   "02000000: 48 65 6C 6C 6F 2C 20 47 42 41 21 00 01 02 FF 0A |Hello, GBA!.....|\n"
+/// );
+/// ```
+#[inline]
+pub fn write_hex_dump(
+  out: &mut impl core::fmt::Write, addr: usize, bytes: &[u8],
+) -> core::fmt::Result {
+  for (i, line) in bytes.chunks(HEX_DUMP_WIDTH).enumerate() {
+    let line = HexDumpLine { addr: addr + i * HEX_DUMP_WIDTH, bytes: line };
+    writeln!(out, "{line}")?;
+  }
+  Ok(())
+}
+
+/// Logs a hex dump of `len` bytes starting at `addr`, such as to check what's
+/// in VRAM, OAM, or a save buffer.
+///
+/// Each line (see [`HexDumpLine`]) is sent as its own message with
+/// [`mgba_log_fmt`], so an interrupt handler that logs can't break up a line.
+///
+/// Fails if mGBA logging isn't available.
+///
+/// ```no_run
+/// # use gba::prelude::*;
+/// // the first object entry
+/// unsafe { hex_dump(0x0700_0000 as *const u8, 8, MgbaMessageLevel::Debug) }
+///   .ok();
+/// ```
+///
+/// ## Safety
+/// * `addr` must be readable for `len` bytes, with byte sized reads.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub unsafe fn hex_dump(
+  addr: *const u8, len: usize, level: MgbaMessageLevel,
+) -> Result<(), ()> {
+  if !mgba_logging_available() {
+    return Err(());
+  }
+  for start in (0..len).step_by(HEX_DUMP_WIDTH) {
+    let mut line = [0_u8; HEX_DUMP_WIDTH];
+    let n = (len - start).min(HEX_DUMP_WIDTH);
+    for (i, b) in line[..n].iter_mut().enumerate() {
+      *b = unsafe { addr.add(start + i).read_volatile() };
+    }
+    let line = HexDumpLine { addr: addr as usize + start, bytes: &line[..n] };
+    mgba_log_fmt(level, format_args!("{line}"))?;
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  extern crate std;
+  use super::*;
+  use std::string::String;
+
+  #[test]
+  fn dbg_message_format() {
+    let value = (3, "hi");
+    let msg =
+      DbgMessage { file: "src/main.rs", line: 12, expr: "pair", value: &value };
+    // a `String` stands in for the mGBA logger
+    let mut out = String::new();
+    core::fmt::Write::write_fmt(&mut out, format_args!("{msg}")).unwrap();
+    assert_eq!(out, "src/main.rs:12: pair = (3, \"hi\")");
+  }
+
+  #[test]
+  fn handshake_needs_the_right_readback() {
+    use core::cell::Cell;
+    let written = Cell::new(0_u16);
+    // the exact enable value is written, and the response is checked
+    assert!(mgba_handshake(
+      |v| written.set(v),
+      || MGBA_LOGGING_ENABLE_RESPONSE
+    ));
+    assert_eq!(written.get(), MGBA_LOGGING_ENABLE_REQUEST);
+    // hardware open bus often reads back whatever was last written
+    let reg = Cell::new(0_u16);
+    assert!(!mgba_handshake(|v| reg.set(v), || reg.get()));
+    for junk in [0x0000, 0xFFFF, MGBA_LOGGING_ENABLE_RESPONSE ^ 1] {
+      assert!(!mgba_handshake(|_| (), || junk), "{junk:#06X}");
+    }
+  }
+
+  #[test]
+  fn hex_dump_lines() {
+    let bytes: [u8; 20] =
+      core::array::from_fn(|i| (i as u8).wrapping_mul(13).wrapping_add(30));
+    // a `String` stands in for the mGBA logger
+    let mut out = String::new();
+    write_hex_dump(&mut out, 0x0600_FFF0, &bytes).unwrap();
+    let lines: std::vec::Vec<&str> = out.lines().collect();
+    assert_eq!(
+      lines,
+      [
+        "0600FFF0: 1E 2B 38 45 52 5F 6C 79 86 93 A0 AD BA C7 D4 E1 \
+         |.+8ER_ly........|",
+        "06010000: EE FB 08 15                                     |....|",
+      ]
+    );
+    // a full line fits easily in one mGBA message
+    assert_eq!(lines[0].len(), 76);
+    assert!(lines[0].len() < 256);
+  }
+}
@@ -54,6 +54,7 @@ def_mmio!(0x0400_0008 = BG0CNT: VolAddress<BackgroundControl, Safe, Safe>; "Back
 def_mmio!(0x0400_000A = BG1CNT: VolAddress<BackgroundControl, Safe, Safe>; "Background 1 Control");
 def_mmio!(0x0400_000C = BG2CNT: VolAddress<BackgroundControl, Safe, Safe>; "Background 2 Control");
 def_mmio!(0x0400_000E = BG3CNT: VolAddress<BackgroundControl, Safe, Safe>; "Background 3 Control");
+def_mmio!(0x0400_0008 = BGCNT/["BG0CNT","BG1CNT","BG2CNT","BG3CNT"]: VolBlock<BackgroundControl, Safe, Safe, 4>; "All four Background Control registers, indexed by background");
 
 def_mmio!(0x0400_0010 = BG0HOFS: VolAddress<u16, (), Safe>; "Background 0 Horizontal Offset (9-bit, text mode)");
 def_mmio!(0x0400_0012 = BG0VOFS: VolAddress<u16, (), Safe>; "Background 0 Vertical Offset (9-bit, text mode)");
@@ -189,6 +190,10 @@ def_mmio!(0x0400_0202 = IF: VolAddress<IrqBits, Safe, Safe>; "Interrupts Flagged
 def_mmio!(0x0400_0204 = WAITCNT: VolAddress<u16, Safe, Unsafe>; "Wait state control for interfacing with the ROM.\n\nThis can make reading the ROM give garbage when it's mis-configured!");
 def_mmio!(0x0400_0208 = IME: VolAddress<bool, Safe, Safe>; "Interrupt Master Enable: Allows turning on/off all interrupts with a single access.");
 
+// Memory Control
+
+def_mmio!(0x0400_0800 = MEMCNT: VolAddress<u32, Safe, Unsafe>; "Internal Memory Control (undocumented): EWRAM wait states and more.\n\nSee [`set_ewram_wait_fast`](crate::mem::set_ewram_wait_fast). A bad value can crash the system!");
+
 // mGBA Logging
 
 def_mmio!(0x04FF_F600 = MGBA_LOG_BUFFER: VolBlock<u8, Safe, Safe, 256>; "The buffer to put logging messages into.\n\nThe first 0 in the buffer is the end of each message.");
@@ -2,18 +2,22 @@
 
 #[cfg(feature = "on_gba")]
 pub use crate::{
-  asm_runtime::*, bios::*, dma::*, gba_cell::*, mgba::*, mmio::*,
+  asm_runtime::*, bios::*, gba_cell::*, mmio::*, save::sram::*,
   RUST_IRQ_HANDLER,
 };
 
 pub use crate::{
   builtin_art::*,
+  dma::*,
   fixed::*,
   include_aligned_bytes,
   interrupts::*,
   keys::*,
+  mgba::*,
+  save::{compress::*, *},
   sound::*,
   timers::*,
+  util::*,
   video::{obj::*, *},
   Align4,
 };
@@ -0,0 +1,271 @@
+//! Compressing save data before it's written.
+//!
+//! Save data is often very compressible (lots of zeroes, flags, and repeated
+//! values), so compressing it can let a game store more than the media's raw
+//! capacity. [`CompressedSave`] runs a [`SaveCodec`] over the data on every
+//! load and store. You can use the built in [`Rle`] codec, or provide your own.
+//!
+//! Keep in mind that how well data compresses depends entirely on the data.
+//! A store that fits today may not fit tomorrow if the data becomes less
+//! compressible, so always handle the error from
+//! [`store`](CompressedSave::store).
+
+use super::{check_bounds, prepare_range, RawSaveAccess, SaveError};
+
+/// The size of the header before the compressed data.
+///
+/// The header is the compressed length as a little-endian `u32`.
+const HEADER_LEN: usize = 4;
+
+/// A way to compress and decompress save data.
+///
+/// Both methods write into `output` and return how many bytes were written,
+/// or `None` if the output doesn't fit or the input is malformed.
+///
+/// This is also implemented for a pair of closures `(encode, decode)`, so you
+/// can plug in your own compression without making a new type.
+pub trait SaveCodec {
+  /// Compresses `input` into `output`.
+  fn encode(&mut self, input: &[u8], output: &mut [u8]) -> Option<usize>;
+
+  /// Decompresses `input` into `output`.
+  fn decode(&mut self, input: &[u8], output: &mut [u8]) -> Option<usize>;
+}
+
+impl<E, D> SaveCodec for (E, D)
+where
+  E: FnMut(&[u8], &mut [u8]) -> Option<usize>,
+  D: FnMut(&[u8], &mut [u8]) -> Option<usize>,
+{
+  #[inline]
+  fn encode(&mut self, input: &[u8], output: &mut [u8]) -> Option<usize> {
+    (self.0)(input, output)
+  }
+
+  #[inline]
+  fn decode(&mut self, input: &[u8], output: &mut [u8]) -> Option<usize> {
+    (self.1)(input, output)
+  }
+}
+
+/// A simple run-length encoding.
+///
+/// The data is a series of packets, each starting with a header byte `h`:
+/// * `0 ..= 127`: the next `h + 1` bytes are copied as-is.
+/// * `128 ..= 255`: the next byte is repeated `h - 126` times.
+///
+/// Only runs of 3 or more bytes are encoded as repeats, so data without any
+/// runs grows by just one byte per 128 bytes.
+///
+/// ```
+/// # use gba::save::compress::*;
+/// let data = [0_u8; 100];
+/// let mut packed = [0_u8; 8];
+/// let n = Rle.encode(&data, &mut packed).unwrap();
+/// assert_eq!(n, 2);
+/// let mut unpacked = [1_u8; 100];
+/// assert_eq!(Rle.decode(&packed[..n], &mut unpacked), Some(100));
+/// assert_eq!(data, unpacked);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Rle;
+impl Rle {
+  /// The largest encoded size that `len` bytes of input can produce.
+  #[inline]
+  #[must_use]
+  pub const fn max_encoded_len(len: usize) -> usize {
+    len + len.div_ceil(128)
+  }
+}
+impl SaveCodec for Rle {
+  #[inline]
+  fn encode(&mut self, input: &[u8], output: &mut [u8]) -> Option<usize> {
+    let mut out = 0;
+    let mut literal_start = 0;
+    let mut i = 0;
+    while i < input.len() {
+      let b = input[i];
+      let mut run = 1;
+      while run < 129 && input.get(i + run) == Some(&b) {
+        run += 1;
+      }
+      if run >= 3 {
+        out = rle_literals(&input[literal_start..i], output, out)?;
+        *output.get_mut(out)? = (run + 126) as u8;
+        *output.get_mut(out + 1)? = b;
+        out += 2;
+        literal_start = i + run;
+      }
+      i += run;
+    }
+    rle_literals(&input[literal_start..], output, out)
+  }
+
+  #[inline]
+  fn decode(&mut self, input: &[u8], output: &mut [u8]) -> Option<usize> {
+    let mut out = 0;
+    let mut i = 0;
+    while i < input.len() {
+      let h = usize::from(input[i]);
+      i += 1;
+      if h < 128 {
+        let n = h + 1;
+        output.get_mut(out..out + n)?.copy_from_slice(input.get(i..i + n)?);
+        i += n;
+        out += n;
+      } else {
+        let n = h - 126;
+        output.get_mut(out..out + n)?.fill(*input.get(i)?);
+        i += 1;
+        out += n;
+      }
+    }
+    Some(out)
+  }
+}
+
+/// Writes literal packets for all of `literals`, starting at `output[out]`.
+///
+/// Returns the new output position.
+#[inline]
+fn rle_literals(
+  literals: &[u8], output: &mut [u8], mut out: usize,
+) -> Option<usize> {
+  for chunk in literals.chunks(128) {
+    *output.get_mut(out)? = (chunk.len() - 1) as u8;
+    output.get_mut(out + 1..out + 1 + chunk.len())?.copy_from_slice(chunk);
+    out += 1 + chunk.len();
+  }
+  Some(out)
+}
+
+/// Save data that's compressed with a [`SaveCodec`] as it's stored.
+///
+/// This uses `capacity` bytes of media starting at `offset`: a small header
+/// holding the compressed length, and then the compressed data.
+///
+/// Since there's no allocator, both [`store`](CompressedSave::store) and
+/// [`load`](CompressedSave::load) need a `scratch` buffer to hold the
+/// compressed bytes. It should be at least as large as the largest compressed
+/// size you expect.
+///
+/// Unlike [`AbBuffer`](super::AbBuffer), this writes in place, so a power
+/// loss during a store can lose the save.
+#[derive(Debug)]
+pub struct CompressedSave<M, C> {
+  media: M,
+  codec: C,
+  offset: usize,
+  capacity: usize,
+}
+impl<M: RawSaveAccess, C: SaveCodec> CompressedSave<M, C> {
+  /// Wraps some media, using `capacity` bytes starting at `offset`.
+  #[inline]
+  #[must_use]
+  pub const fn new(media: M, codec: C, offset: usize, capacity: usize) -> Self {
+    Self { media, codec, offset, capacity }
+  }
+
+  /// Gives back the wrapped media.
+  #[inline]
+  #[must_use]
+  pub fn into_inner(self) -> M {
+    self.media
+  }
+
+  /// Compresses and stores `data`.
+  ///
+  /// On success, returns the compressed length. If the compressed data
+  /// doesn't fit in `scratch` or in the capacity you'll get
+  /// [`SaveError::OutOfBounds`], and nothing is written.
+  #[inline]
+  pub fn store(
+    &mut self, data: &[u8], scratch: &mut [u8],
+  ) -> Result<usize, SaveError> {
+    check_bounds(self.media.info(), self.offset, self.capacity)?;
+    let room = self.capacity.saturating_sub(HEADER_LEN);
+    let limit = scratch.len().min(room);
+    let len = self
+      .codec
+      .encode(data, &mut scratch[..limit])
+      .ok_or(SaveError::OutOfBounds)?;
+    prepare_range(&mut self.media, self.offset, HEADER_LEN + len)?;
+    self.media.write(self.offset + HEADER_LEN, &scratch[..len])?;
+    self.media.write(self.offset, &(len as u32).to_le_bytes())?;
+    Ok(len)
+  }
+
+  /// Loads and decompresses the data into `out`.
+  ///
+  /// On success, returns the decompressed length. If the stored length is
+  /// impossible, or the data can't be decompressed into `out`, you'll get
+  /// [`SaveError::NoValidData`].
+  #[inline]
+  pub fn load(
+    &mut self, out: &mut [u8], scratch: &mut [u8],
+  ) -> Result<usize, SaveError> {
+    check_bounds(self.media.info(), self.offset, self.capacity)?;
+    let mut header = [0_u8; HEADER_LEN];
+    self.media.read(self.offset, &mut header)?;
+    let len = u32::from_le_bytes(header) as usize;
+    if len > self.capacity.saturating_sub(HEADER_LEN) || len > scratch.len() {
+      return Err(SaveError::NoValidData);
+    }
+    self.media.read(self.offset + HEADER_LEN, &mut scratch[..len])?;
+    self.codec.decode(&scratch[..len], out).ok_or(SaveError::NoValidData)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    super::{tests::Ram, ERASED_BYTE},
+    *,
+  };
+
+  #[test]
+  fn rle_round_trip() {
+    // literals, short and long runs, and a literal stretch over 128 bytes
+    let mut data = [0_u8; 600];
+    data[..5].copy_from_slice(&[1, 2, 3, 3, 4]);
+    data[5..140].fill(7);
+    for (i, b) in data[140..300].iter_mut().enumerate() {
+      *b = i as u8;
+    }
+    data[300..302].fill(9);
+    let mut packed = [0_u8; Rle::max_encoded_len(600)];
+    let n = Rle.encode(&data, &mut packed).unwrap();
+    assert!(n < data.len());
+    let mut unpacked = [0xAA_u8; 600];
+    assert_eq!(Rle.decode(&packed[..n], &mut unpacked), Some(600));
+    assert_eq!(data, unpacked);
+    // data without runs grows by at most the documented amount
+    let mut packed = [0_u8; 256];
+    assert_eq!(Rle.encode(&[], &mut packed), Some(0));
+    let ramp: [u8; 200] = core::array::from_fn(|i| i as u8);
+    let n = Rle.encode(&ramp, &mut packed).unwrap();
+    assert_eq!(n, Rle::max_encoded_len(200));
+    // output that doesn't fit is an error, not a panic
+    assert_eq!(Rle.encode(&ramp, &mut packed[..100]), None);
+    assert_eq!(Rle.decode(&[0x81], &mut unpacked), None);
+  }
+
+  #[test]
+  fn compressed_save_round_trip() {
+    let mut save = CompressedSave::new(Ram([ERASED_BYTE; 256]), Rle, 64, 32);
+    let mut data = [0_u8; 200];
+    data[..4].copy_from_slice(b"SAVE");
+    let mut scratch = [0_u8; 64];
+    let n = save.store(&data, &mut scratch).unwrap();
+    // "SAVE" as a literal, then 196 zeroes as two runs
+    assert_eq!(n, 9);
+    let mut out = [0xFF_u8; 200];
+    assert_eq!(save.load(&mut out, &mut scratch), Ok(200));
+    assert_eq!(out, data);
+    // incompressible data doesn't fit, and the old save is left alone
+    let ramp: [u8; 200] = core::array::from_fn(|i| i as u8);
+    assert_eq!(save.store(&ramp, &mut scratch), Err(SaveError::OutOfBounds));
+    assert_eq!(save.load(&mut out, &mut scratch), Ok(200));
+    assert_eq!(out, data);
+  }
+}
@@ -0,0 +1,1353 @@
+//! Module for storing data in the cartridge's save media.
+//!
+//! GBA cartridges can have one of several kinds of save media (SRAM, Flash, or
+//! EEPROM), each with its own quirks. The [`RawSaveAccess`] trait gives a
+//! common byte-level interface over all of them, and then the rest of this
+//! module builds safer save formats on top of that trait.
+//!
+//! ## Sectors
+//!
+//! Some save media can't simply be overwritten. Flash memory must have a
+//! sector erased before it can be written again. Because of this, before
+//! writing you must call [`RawSaveAccess::prepare_write`] on every sector that
+//! you're about to write. For media that don't need it (such as SRAM) that
+//! call does nothing, but portable code should always make it.
+//!
+//! ## Power Loss
+//!
+//! The player can turn off the GBA at any moment, including while a save is
+//! being written. Writing a save "in place" means that a badly timed power
+//! loss destroys both the old save and the new one. [`AbBuffer`] avoids this
+//! by keeping two copies of the data and only ever overwriting the older copy.
+//! [`Mirrored`] also keeps two copies, but always writes both, so that damage
+//! to either copy can be recovered from.
+
+pub mod compress;
+#[cfg(feature = "on_gba")]
+pub mod sram;
+
+use crate::timers::{CPU_FREQUENCY, CYCLES_PER_FRAME};
+
+/// Information about a save media's size and layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MediaInfo {
+  /// The size of a sector is `1 << sector_shift` bytes.
+  pub sector_shift: u8,
+  /// The number of sectors in the media.
+  pub sector_count: usize,
+}
+impl MediaInfo {
+  /// The size of one sector, in bytes.
+  #[inline]
+  #[must_use]
+  pub const fn sector_size(&self) -> usize {
+    1 << self.sector_shift
+  }
+
+  /// The total size of the media, in bytes.
+  #[inline]
+  #[must_use]
+  pub const fn total_len(&self) -> usize {
+    self.sector_count << self.sector_shift
+  }
+}
+
+/// An error that can occur while accessing save media.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SaveError {
+  /// The access would go past the end of the media.
+  OutOfBounds,
+  /// The media didn't respond properly to an operation.
+  OperationFailed,
+  /// No valid saved data was found.
+  NoValidData,
+}
+
+/// Raw, byte level access to a save media.
+///
+/// Offsets are in bytes from the start of the media. Before writing to any
+/// part of the media, the sectors being written must be prepared with
+/// [`prepare_write`](RawSaveAccess::prepare_write).
+pub trait RawSaveAccess {
+  /// Gets the size and layout of the media.
+  fn info(&self) -> MediaInfo;
+
+  /// Reads bytes starting at `offset` to fill `buffer`.
+  fn read(&mut self, offset: usize, buffer: &mut [u8])
+    -> Result<(), SaveError>;
+
+  /// Prepares `count` sectors, starting at sector index `sector`, to be
+  /// written.
+  ///
+  /// Depending on the media, this may erase the sectors.
+  fn prepare_write(
+    &mut self, sector: usize, count: usize,
+  ) -> Result<(), SaveError>;
+
+  /// Writes all of `buffer` starting at `offset`.
+  fn write(&mut self, offset: usize, buffer: &[u8]) -> Result<(), SaveError>;
+
+  /// The value that [`format`] leaves in every byte of the media.
+  ///
+  /// SRAM and EEPROM are zero filled, so this defaults to 0. Flash should
+  /// return [`ERASED_BYTE`], since that's what a chip erase leaves behind.
+  #[inline]
+  fn erased_byte(&self) -> u8 {
+    0
+  }
+}
+
+/// Sets the SRAM wait state bits of a `WAITCNT` value to 8 cycles.
+///
+/// Battery backed SRAM needs the slowest SRAM wait setting, bits 0-1 of
+/// `WAITCNT` both set, or reads can give garbage. All other bits are kept.
+#[inline]
+#[must_use]
+pub const fn waitcnt_with_sram_wait(waitcnt: u16) -> u16 {
+  waitcnt | 0b11
+}
+
+/// Checks that `len` bytes starting at `offset` are within the media.
+#[inline]
+pub(crate) fn check_bounds(
+  info: MediaInfo, offset: usize, len: usize,
+) -> Result<(), SaveError> {
+  match offset.checked_add(len) {
+    Some(end) if end <= info.total_len() => Ok(()),
+    _ => Err(SaveError::OutOfBounds),
+  }
+}
+
+/// Prepares all sectors that touch `len` bytes starting at `offset`.
+#[inline]
+fn prepare_range<M: RawSaveAccess + ?Sized>(
+  media: &mut M, offset: usize, len: usize,
+) -> Result<(), SaveError> {
+  let info = media.info();
+  check_bounds(info, offset, len)?;
+  if len == 0 {
+    return Ok(());
+  }
+  let first = offset >> info.sector_shift;
+  let last = (offset + len - 1) >> info.sector_shift;
+  media.prepare_write(first, last - first + 1)
+}
+
+/// The value that erased Flash reads as.
+///
+/// Flash media return this from
+/// [`erased_byte`](RawSaveAccess::erased_byte).
+pub const ERASED_BYTE: u8 = 0xFF;
+
+/// Erases all data on the media, such as for a "delete all save data" menu
+/// option.
+///
+/// Every sector is prepared with a single
+/// [`prepare_write`](RawSaveAccess::prepare_write) call, which on Flash is a
+/// chip erase. Then any part of the media that doesn't already read as
+/// [`erased_byte`](RawSaveAccess::erased_byte) is written over with that
+/// value, which zero fills SRAM and EEPROM since preparing them does nothing.
+/// Finally the whole media is read back to check that it worked.
+///
+/// This is slow: a Flash chip erase can take over a second, and writing all of
+/// a 64K SRAM or Flash byte by byte takes a good fraction of a second. Show a
+/// "please wait" message first, and don't call it from an interrupt handler.
+///
+/// ```
+/// # use gba::save::*;
+/// # struct Ram([u8; 256], Vec<(usize, usize)>);
+/// # impl RawSaveAccess for Ram {
+/// #   fn info(&self) -> MediaInfo {
+/// #     MediaInfo { sector_shift: 6, sector_count: 4 }
+/// #   }
+/// #   fn read(&mut self, o: usize, b: &mut [u8]) -> Result<(), SaveError> {
+/// #     Ok(b.copy_from_slice(&self.0[o..o + b.len()]))
+/// #   }
+/// #   fn prepare_write(&mut self, s: usize, c: usize) -> Result<(), SaveError> {
+/// #     Ok(self.1.push((s, c)))
+/// #   }
+/// #   fn write(&mut self, o: usize, b: &[u8]) -> Result<(), SaveError> {
+/// #     Ok(self.0[o..o + b.len()].copy_from_slice(b))
+/// #   }
+/// # }
+/// let mut media = Ram([0x12; 256], Vec::new());
+/// format(&mut media).unwrap();
+/// // one prepare, covering all 4 sectors
+/// assert_eq!(media.1, [(0, 4)]);
+/// let mut out = [0_u8; 256];
+/// media.read(0, &mut out).unwrap();
+/// assert!(out.iter().all(|&b| b == 0));
+/// ```
+///
+/// ## Failures
+/// * Any error from the media is returned.
+/// * If the media doesn't read back as erased, you get
+///   [`SaveError::OperationFailed`].
+#[inline]
+pub fn format<M: RawSaveAccess + ?Sized>(
+  media: &mut M,
+) -> Result<(), SaveError> {
+  let info = media.info();
+  media.prepare_write(0, info.sector_count)?;
+  let erased = [media.erased_byte(); 32];
+  let mut chunk = [0_u8; 32];
+  let mut pos = 0;
+  while pos < info.total_len() {
+    let n = (info.total_len() - pos).min(chunk.len());
+    media.read(pos, &mut chunk[..n])?;
+    if chunk[..n] != erased[..n] {
+      media.write(pos, &erased[..n])?;
+    }
+    pos += n;
+  }
+  pos = 0;
+  while pos < info.total_len() {
+    let n = (info.total_len() - pos).min(chunk.len());
+    media.read(pos, &mut chunk[..n])?;
+    if chunk[..n] != erased[..n] {
+      return Err(SaveError::OperationFailed);
+    }
+    pos += n;
+  }
+  Ok(())
+}
+
+/// Runs a save operation, running it again up to `retries` more times if it
+/// fails.
+///
+/// `between` is called before each retry, such as to wait for the save chip
+/// to settle. On success the operation's value is returned, and once all
+/// attempts have failed the last error is returned. [`with_retries`] is this
+/// with a wait of some number of frames.
+///
+/// ```
+/// # use gba::save::*;
+/// let mut attempts = 0;
+/// let mut waits = 0;
+/// let flaky = |attempts: &mut u32| {
+///   *attempts += 1;
+///   if *attempts < 4 {
+///     Err(SaveError::OperationFailed)
+///   } else {
+///     Ok(*attempts)
+///   }
+/// };
+/// // fails 3 times, then works on the last of 4 attempts
+/// let out = retry_with(3, || waits += 1, || flaky(&mut attempts));
+/// assert_eq!(out, Ok(4));
+/// assert_eq!(waits, 3);
+///
+/// // with only 2 retries, the last error comes back
+/// attempts = 0;
+/// let out = retry_with(2, || (), || flaky(&mut attempts));
+/// assert_eq!(out, Err(SaveError::OperationFailed));
+/// assert_eq!(attempts, 3);
+/// ```
+#[inline]
+pub fn retry_with<T>(
+  retries: u32, mut between: impl FnMut(),
+  mut op: impl FnMut() -> Result<T, SaveError>,
+) -> Result<T, SaveError> {
+  let mut result = op();
+  for _ in 0..retries {
+    if result.is_ok() {
+      break;
+    }
+    between();
+    result = op();
+  }
+  result
+}
+
+/// Runs a save operation, running it again up to `retries` more times if it
+/// fails, waiting `delay_frames` frames before each retry.
+///
+/// Flash chips can occasionally fail a write and then work fine a moment
+/// later, so this gives a failed operation a few more chances. See
+/// [`retry_with`].
+///
+/// The wait is done with [`VBlankIntrWait`](crate::bios::VBlankIntrWait), so
+/// the vertical blank interrupt must be enabled (and `IME` on).
+///
+/// ```no_run
+/// # use gba::prelude::*;
+/// # let data = [0_u8; 16];
+/// let mut sram = SramAccess::new();
+/// with_retries(3, 2, || sram.write(0, &data)).ok();
+/// ```
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn with_retries<T>(
+  retries: u32, delay_frames: u32, op: impl FnMut() -> Result<T, SaveError>,
+) -> Result<T, SaveError> {
+  retry_with(
+    retries,
+    || {
+      for _ in 0..delay_frames {
+        crate::bios::VBlankIntrWait();
+      }
+    },
+    op,
+  )
+}
+
+/// An incremental CRC-32 (the common IEEE 802.3 variant).
+///
+/// ```
+/// # use gba::save::Crc32;
+/// let mut crc = Crc32::new();
+/// crc.update(b"1234");
+/// crc.update(b"56789");
+/// assert_eq!(crc.finish(), 0xCBF4_3926);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Crc32(u32);
+impl Crc32 {
+  // Using a nibble table keeps the table tiny while still being a fair bit
+  // faster than going one bit at a time.
+  const TABLE: [u32; 16] = {
+    let mut table = [0; 16];
+    let mut i = 0;
+    while i < 16 {
+      let mut c = i as u32;
+      let mut k = 0;
+      while k < 4 {
+        c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+        k += 1;
+      }
+      table[i] = c;
+      i += 1;
+    }
+    table
+  };
+
+  /// Starts a new CRC.
+  #[inline]
+  #[must_use]
+  pub const fn new() -> Self {
+    Self(u32::MAX)
+  }
+
+  /// Adds more bytes to the CRC.
+  #[inline]
+  pub fn update(&mut self, bytes: &[u8]) {
+    let mut c = self.0;
+    for b in bytes.iter().copied() {
+      c ^= u32::from(b);
+      c = Self::TABLE[(c & 0xF) as usize] ^ (c >> 4);
+      c = Self::TABLE[(c & 0xF) as usize] ^ (c >> 4);
+    }
+    self.0 = c;
+  }
+
+  /// Gets the final CRC value.
+  #[inline]
+  #[must_use]
+  pub const fn finish(self) -> u32 {
+    !self.0
+  }
+}
+impl Default for Crc32 {
+  #[inline]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Computes the CRC-32 of some bytes in one step.
+#[inline]
+#[must_use]
+pub fn crc32(bytes: &[u8]) -> u32 {
+  let mut crc = Crc32::new();
+  crc.update(bytes);
+  crc.finish()
+}
+
+/// The size of the header that each save slot begins with.
+///
+/// The header is the slot's sequence number and then the CRC-32 of the
+/// sequence number and the payload, both stored as little-endian `u32`.
+pub const SLOT_HEADER_LEN: usize = 8;
+
+/// If sequence number `a` is newer than `b`, allowing for wrap around.
+#[inline]
+#[must_use]
+const fn seq_is_newer(a: u32, b: u32) -> bool {
+  (a.wrapping_sub(b) as i32) > 0
+}
+
+/// Checks a slot that holds `len` bytes of payload.
+///
+/// Returns the sequence number if the slot's CRC is valid.
+fn scan_slot<M: RawSaveAccess + ?Sized>(
+  media: &mut M, offset: usize, len: usize,
+) -> Result<Option<u32>, SaveError> {
+  let mut header = [0_u8; SLOT_HEADER_LEN];
+  media.read(offset, &mut header)?;
+  let seq = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+  let crc = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+  let mut check = Crc32::new();
+  check.update(&header[..4]);
+  let mut chunk = [0_u8; 32];
+  let mut pos = 0;
+  while pos < len {
+    let n = (len - pos).min(chunk.len());
+    media.read(offset + SLOT_HEADER_LEN + pos, &mut chunk[..n])?;
+    check.update(&chunk[..n]);
+    pos += n;
+  }
+  Ok(if check.finish() == crc { Some(seq) } else { None })
+}
+
+/// Writes a slot: first the payload, and then the header.
+///
+/// The payload is all of `parts`, one after the other. Writing the header last
+/// means that if the write is interrupted the slot's CRC won't match, and the
+/// slot is treated as invalid.
+fn write_slot<M: RawSaveAccess + ?Sized>(
+  media: &mut M, offset: usize, seq: u32, parts: &[&[u8]],
+) -> Result<(), SaveError> {
+  let len: usize = parts.iter().map(|part| part.len()).sum();
+  prepare_range(media, offset, SLOT_HEADER_LEN + len)?;
+  let mut crc = Crc32::new();
+  crc.update(&seq.to_le_bytes());
+  let mut pos = offset + SLOT_HEADER_LEN;
+  for part in parts {
+    crc.update(part);
+    media.write(pos, part)?;
+    pos += part.len();
+  }
+  let mut header = [0_u8; SLOT_HEADER_LEN];
+  header[..4].copy_from_slice(&seq.to_le_bytes());
+  header[4..].copy_from_slice(&crc.finish().to_le_bytes());
+  media.write(offset, &header)
+}
+
+/// Finds the newest valid slot out of `count` slots spaced `stride` bytes
+/// apart, returning the slot index and sequence number.
+fn find_newest_slot<M: RawSaveAccess + ?Sized>(
+  media: &mut M, offset: usize, stride: usize, count: usize, len: usize,
+) -> Result<Option<(usize, u32)>, SaveError> {
+  let mut newest: Option<(usize, u32)> = None;
+  for i in 0..count {
+    if let Some(seq) = scan_slot(media, offset + i * stride, len)? {
+      match newest {
+        Some((_, best)) if !seq_is_newer(seq, best) => (),
+        _ => newest = Some((i, seq)),
+      }
+    }
+  }
+  Ok(newest)
+}
+
+/// Double buffered save data, which survives power loss during a write.
+///
+/// This keeps two slots of `SIZE` bytes each (plus a [`SLOT_HEADER_LEN`]
+/// header), one after the other starting at `offset`. Each slot has a sequence
+/// number and a CRC.
+/// * [`store`](AbBuffer::store) always writes to the slot that *isn't* the
+///   newest valid one, using the next sequence number.
+/// * [`load`](AbBuffer::load) reads the newest slot with a valid CRC.
+///
+/// If the power is lost in the middle of a store, the slot being written won't
+/// have a valid CRC, and loading will fall back to the previous save.
+///
+/// On media with large sectors (eg: Flash), `offset` and
+/// [`SLOT_LEN`](AbBuffer::SLOT_LEN) should both be multiples of the sector
+/// size, otherwise preparing one slot for writing can erase part of the
+/// other slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AbBuffer<const SIZE: usize> {
+  offset: usize,
+}
+impl<const SIZE: usize> AbBuffer<SIZE> {
+  /// The number of bytes each slot uses, including the header.
+  pub const SLOT_LEN: usize = SLOT_HEADER_LEN + SIZE;
+
+  /// The total number of bytes of media used.
+  pub const TOTAL_LEN: usize = 2 * Self::SLOT_LEN;
+
+  /// Makes a buffer that uses the media starting at `offset`.
+  #[inline]
+  #[must_use]
+  pub const fn new(offset: usize) -> Self {
+    Self { offset }
+  }
+
+  /// Loads the newest valid save into `out`.
+  ///
+  /// On success, the save's sequence number is returned. If neither slot is
+  /// valid you'll get [`SaveError::NoValidData`].
+  #[inline]
+  pub fn load<M: RawSaveAccess + ?Sized>(
+    &self, media: &mut M, out: &mut [u8; SIZE],
+  ) -> Result<u32, SaveError> {
+    check_bounds(media.info(), self.offset, Self::TOTAL_LEN)?;
+    match find_newest_slot(media, self.offset, Self::SLOT_LEN, 2, SIZE)? {
+      Some((i, seq)) => {
+        let slot = self.offset + i * Self::SLOT_LEN;
+        media.read(slot + SLOT_HEADER_LEN, out)?;
+        Ok(seq)
+      }
+      None => Err(SaveError::NoValidData),
+    }
+  }
+
+  /// Stores `data` into the older slot.
+  ///
+  /// On success, the new save's sequence number is returned.
+  #[inline]
+  pub fn store<M: RawSaveAccess + ?Sized>(
+    &self, media: &mut M, data: &[u8; SIZE],
+  ) -> Result<u32, SaveError> {
+    check_bounds(media.info(), self.offset, Self::TOTAL_LEN)?;
+    let (target, seq) =
+      match find_newest_slot(media, self.offset, Self::SLOT_LEN, 2, SIZE)? {
+        Some((i, seq)) => (1 - i, seq.wrapping_add(1)),
+        None => (0, 1),
+      };
+    write_slot(media, self.offset + target * Self::SLOT_LEN, seq, &[data])?;
+    Ok(seq)
+  }
+}
+
+/// The size of the metadata that [`SaveSlots`] stores before each slot's data.
+///
+/// This is the version and then the timestamp, as little-endian `u32` values.
+pub const SLOT_META_LEN: usize = 8;
+
+/// A summary of one slot of a [`SaveSlots`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SlotInfo {
+  /// The slot's index.
+  pub index: usize,
+  /// If the slot holds valid data. When it doesn't, the other fields are 0.
+  pub valid: bool,
+  /// The version number that was stored with the data.
+  pub version: u32,
+  /// The timestamp that was stored with the data.
+  ///
+  /// This is whatever value the game passes in, such as total play time.
+  pub timestamp: u32,
+}
+
+/// A row of numbered save slots, such as for a save/load menu.
+///
+/// Each slot holds `SIZE` bytes of data plus a version number and a timestamp.
+/// Slots are checked with a CRC (see [`SLOT_HEADER_LEN`]), so an empty or
+/// partly written slot shows up as not valid.
+///
+/// Unlike [`AbBuffer`], each slot is written in place, so a power loss while
+/// storing to a slot can lose that slot (other slots are unaffected).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SaveSlots<const SIZE: usize> {
+  offset: usize,
+  count: usize,
+}
+impl<const SIZE: usize> SaveSlots<SIZE> {
+  /// The number of bytes each slot uses, including the header and metadata.
+  pub const SLOT_LEN: usize = SLOT_HEADER_LEN + SLOT_META_LEN + SIZE;
+
+  /// Makes `count` slots, one after the other starting at `offset`.
+  #[inline]
+  #[must_use]
+  pub const fn new(offset: usize, count: usize) -> Self {
+    Self { offset, count }
+  }
+
+  /// The number of slots.
+  #[inline]
+  #[must_use]
+  pub const fn count(&self) -> usize {
+    self.count
+  }
+
+  /// The total number of bytes of media used.
+  #[inline]
+  #[must_use]
+  pub const fn total_len(&self) -> usize {
+    self.count * Self::SLOT_LEN
+  }
+
+  #[inline]
+  fn slot_offset(&self, index: usize) -> Result<usize, SaveError> {
+    if index < self.count {
+      Ok(self.offset + index * Self::SLOT_LEN)
+    } else {
+      Err(SaveError::OutOfBounds)
+    }
+  }
+
+  /// Reads a slot's summary, without reading its data into memory.
+  ///
+  /// The whole slot is still read from the media to check the CRC.
+  #[inline]
+  pub fn info<M: RawSaveAccess + ?Sized>(
+    &self, media: &mut M, index: usize,
+  ) -> Result<SlotInfo, SaveError> {
+    let offset = self.slot_offset(index)?;
+    check_bounds(media.info(), offset, Self::SLOT_LEN)?;
+    let mut info = SlotInfo { index, valid: false, version: 0, timestamp: 0 };
+    if scan_slot(media, offset, SLOT_META_LEN + SIZE)?.is_some() {
+      let mut meta = [0_u8; SLOT_META_LEN];
+      media.read(offset + SLOT_HEADER_LEN, &mut meta)?;
+      info.valid = true;
+      info.version = u32::from_le_bytes([meta[0], meta[1], meta[2], meta[3]]);
+      info.timestamp = u32::from_le_bytes([meta[4], meta[5], meta[6], meta[7]]);
+    }
+    Ok(info)
+  }
+
+  /// Iterates over the summary of every slot.
+  ///
+  /// A slot that can't be read from the media is listed as not valid.
+  #[inline]
+  pub fn list_slots<'a, M: RawSaveAccess + ?Sized>(
+    &'a self, media: &'a mut M,
+  ) -> impl Iterator<Item = SlotInfo> + 'a {
+    (0..self.count).map(move |index| {
+      self.info(media, index).unwrap_or(SlotInfo {
+        index,
+        valid: false,
+        version: 0,
+        timestamp: 0,
+      })
+    })
+  }
+
+  /// Stores data into a slot.
+  #[inline]
+  pub fn store<M: RawSaveAccess + ?Sized>(
+    &self, media: &mut M, index: usize, version: u32, timestamp: u32,
+    data: &[u8; SIZE],
+  ) -> Result<(), SaveError> {
+    let offset = self.slot_offset(index)?;
+    check_bounds(media.info(), offset, Self::SLOT_LEN)?;
+    let mut meta = [0_u8; SLOT_META_LEN];
+    meta[..4].copy_from_slice(&version.to_le_bytes());
+    meta[4..].copy_from_slice(&timestamp.to_le_bytes());
+    write_slot(media, offset, 0, &[&meta, data])
+  }
+
+  /// Loads a slot's data into `out`, returning the slot's summary.
+  ///
+  /// If the slot isn't valid you'll get [`SaveError::NoValidData`].
+  #[inline]
+  pub fn load<M: RawSaveAccess + ?Sized>(
+    &self, media: &mut M, index: usize, out: &mut [u8; SIZE],
+  ) -> Result<SlotInfo, SaveError> {
+    let info = self.info(media, index)?;
+    if !info.valid {
+      return Err(SaveError::NoValidData);
+    }
+    let offset = self.slot_offset(index)?;
+    media.read(offset + SLOT_HEADER_LEN + SLOT_META_LEN, out)?;
+    Ok(info)
+  }
+}
+
+/// The size of the length field that [`WearLeveled`] and [`Mirrored`] store
+/// before the data.
+const WEAR_LEN_FIELD: usize = 4;
+
+/// Save data that's written to a different place every time, to spread out
+/// the wear on Flash media.
+///
+/// Each Flash sector can only be erased a limited number of times before it
+/// stops working reliably. A game that autosaves often can use up a single
+/// sector surprisingly quickly, so this rotates through `slot_count` slots of
+/// `sectors_per_slot` whole sectors each, starting at sector `first_sector`.
+/// Each [`write`](WearLeveled::write) goes to the slot after the newest one,
+/// and so every sector is erased only once per `slot_count` writes.
+///
+/// Each slot has a sequence number and a CRC (see [`SLOT_HEADER_LEN`]), then
+/// the data's length as a little-endian `u32`, then the data.
+/// [`read_latest`](WearLeveled::read_latest) reads the valid slot with the
+/// newest sequence number. Like with [`AbBuffer`], a write interrupted by
+/// power loss leaves the previous save in place.
+///
+/// ```
+/// # use gba::save::*;
+/// # struct Ram([u8; 256]);
+/// # impl RawSaveAccess for Ram {
+/// #   fn info(&self) -> MediaInfo {
+/// #     MediaInfo { sector_shift: 6, sector_count: 4 }
+/// #   }
+/// #   fn read(&mut self, o: usize, b: &mut [u8]) -> Result<(), SaveError> {
+/// #     Ok(b.copy_from_slice(&self.0[o..o + b.len()]))
+/// #   }
+/// #   fn prepare_write(&mut self, _: usize, _: usize) -> Result<(), SaveError> {
+/// #     Ok(())
+/// #   }
+/// #   fn write(&mut self, o: usize, b: &[u8]) -> Result<(), SaveError> {
+/// #     Ok(self.0[o..o + b.len()].copy_from_slice(b))
+/// #   }
+/// # }
+/// // 3 slots of 1 sector each, starting at sector 1.
+/// let mut save = WearLeveled::new(Ram([0xFF; 256]), 1, 3, 1);
+/// let mut out = [0_u8; 8];
+/// assert_eq!(save.read_latest(&mut out), Err(SaveError::NoValidData));
+/// save.write(b"first").unwrap();
+/// assert_eq!(save.active_sector(), Ok(Some(1)));
+/// save.write(b"second").unwrap();
+/// assert_eq!(save.active_sector(), Ok(Some(2)));
+/// assert_eq!(save.read_latest(&mut out), Ok(6));
+/// assert_eq!(&out[..6], b"second");
+/// save.write(b"third").unwrap();
+/// save.write(b"fourth").unwrap();
+/// assert_eq!(save.active_sector(), Ok(Some(1)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WearLeveled<M> {
+  media: M,
+  first_sector: usize,
+  slot_count: usize,
+  sectors_per_slot: usize,
+}
+impl<M: RawSaveAccess> WearLeveled<M> {
+  /// Wraps some media, rotating through `slot_count` slots of
+  /// `sectors_per_slot` sectors each, starting at sector `first_sector`.
+  #[inline]
+  #[must_use]
+  pub const fn new(
+    media: M, first_sector: usize, slot_count: usize, sectors_per_slot: usize,
+  ) -> Self {
+    Self { media, first_sector, slot_count, sectors_per_slot }
+  }
+
+  /// Gives back the wrapped media.
+  #[inline]
+  #[must_use]
+  pub fn into_inner(self) -> M {
+    self.media
+  }
+
+  /// The most data that can be written at once, in bytes.
+  #[inline]
+  #[must_use]
+  pub fn max_len(&self) -> usize {
+    self.slot_len().saturating_sub(SLOT_HEADER_LEN + WEAR_LEN_FIELD)
+  }
+
+  #[inline]
+  fn slot_len(&self) -> usize {
+    self.sectors_per_slot << self.media.info().sector_shift
+  }
+
+  #[inline]
+  fn slot_offset(&self, index: usize) -> usize {
+    (self.first_sector + index * self.sectors_per_slot)
+      << self.media.info().sector_shift
+  }
+
+  /// Checks a slot, returning its sequence number and data length if valid.
+  #[inline]
+  fn scan(&mut self, index: usize) -> Result<Option<(u32, usize)>, SaveError> {
+    let offset = self.slot_offset(index);
+    let mut len = [0_u8; WEAR_LEN_FIELD];
+    self.media.read(offset + SLOT_HEADER_LEN, &mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > self.max_len() {
+      return Ok(None);
+    }
+    let seq = scan_slot(&mut self.media, offset, WEAR_LEN_FIELD + len)?;
+    Ok(seq.map(|seq| (seq, len)))
+  }
+
+  /// Finds the newest valid slot, returning its index, sequence number, and
+  /// data length.
+  #[inline]
+  fn newest(&mut self) -> Result<Option<(usize, u32, usize)>, SaveError> {
+    let total = self.slot_count * self.slot_len();
+    check_bounds(self.media.info(), self.slot_offset(0), total)?;
+    let mut newest: Option<(usize, u32, usize)> = None;
+    for i in 0..self.slot_count {
+      if let Some((seq, len)) = self.scan(i)? {
+        match newest {
+          Some((_, best, _)) if !seq_is_newer(seq, best) => (),
+          _ => newest = Some((i, seq, len)),
+        }
+      }
+    }
+    Ok(newest)
+  }
+
+  /// The first sector of the slot holding the newest valid data, if any.
+  #[inline]
+  pub fn active_sector(&mut self) -> Result<Option<usize>, SaveError> {
+    Ok(
+      self
+        .newest()?
+        .map(|(i, _, _)| self.first_sector + i * self.sectors_per_slot),
+    )
+  }
+
+  /// Writes `data` into the slot after the newest one.
+  ///
+  /// On success, the new save's sequence number is returned. If `data` is
+  /// longer than [`max_len`](WearLeveled::max_len) you'll get
+  /// [`SaveError::OutOfBounds`], and nothing is written.
+  #[inline]
+  pub fn write(&mut self, data: &[u8]) -> Result<u32, SaveError> {
+    if data.len() > self.max_len() || self.slot_count == 0 {
+      return Err(SaveError::OutOfBounds);
+    }
+    let (target, seq) = match self.newest()? {
+      Some((i, seq, _)) => ((i + 1) % self.slot_count, seq.wrapping_add(1)),
+      None => (0, 1),
+    };
+    let len = (data.len() as u32).to_le_bytes();
+    let offset = self.slot_offset(target);
+    write_slot(&mut self.media, offset, seq, &[&len, data])?;
+    Ok(seq)
+  }
+
+  /// Reads the newest valid data into `out`.
+  ///
+  /// On success, returns the data's length. If there's no valid data you'll
+  /// get [`SaveError::NoValidData`], and if the data doesn't fit in `out`
+  /// you'll get [`SaveError::OutOfBounds`].
+  #[inline]
+  pub fn read_latest(&mut self, out: &mut [u8]) -> Result<usize, SaveError> {
+    let (index, _, len) = self.newest()?.ok_or(SaveError::NoValidData)?;
+    let out = out.get_mut(..len).ok_or(SaveError::OutOfBounds)?;
+    let offset = self.slot_offset(index);
+    self.media.read(offset + SLOT_HEADER_LEN + WEAR_LEN_FIELD, out)?;
+    Ok(len)
+  }
+}
+
+/// Save data that's written to two places, so that a damaged copy can be
+/// recovered from the other.
+///
+/// Every [`write`](Mirrored::write) stores the data into the slot at
+/// `primary`, and then into the slot at `secondary`, each slot being
+/// `slot_len` bytes. Each slot has a CRC (see [`SLOT_HEADER_LEN`]), then the
+/// data's length as a little-endian `u32`, then the data.
+/// [`read`](Mirrored::read) gives the data from the first slot with a valid
+/// CRC, checking the primary slot first.
+///
+/// Since the secondary slot is only written after the primary one is complete,
+/// a write interrupted by power loss always leaves at least one valid copy.
+/// The two slots must not overlap, and on media with large sectors (eg: Flash)
+/// they should not share a sector either.
+///
+/// ```
+/// # use gba::save::*;
+/// # struct Ram([u8; 256]);
+/// # impl RawSaveAccess for Ram {
+/// #   fn info(&self) -> MediaInfo {
+/// #     MediaInfo { sector_shift: 6, sector_count: 4 }
+/// #   }
+/// #   fn read(&mut self, o: usize, b: &mut [u8]) -> Result<(), SaveError> {
+/// #     Ok(b.copy_from_slice(&self.0[o..o + b.len()]))
+/// #   }
+/// #   fn prepare_write(&mut self, _: usize, _: usize) -> Result<(), SaveError> {
+/// #     Ok(())
+/// #   }
+/// #   fn write(&mut self, o: usize, b: &[u8]) -> Result<(), SaveError> {
+/// #     Ok(self.0[o..o + b.len()].copy_from_slice(b))
+/// #   }
+/// # }
+/// // two slots of 64 bytes, one at byte 0 and one at byte 128.
+/// let mut save = Mirrored::new(Ram([0xFF; 256]), 0, 128, 64);
+/// let mut out = [0_u8; 16];
+/// assert_eq!(save.read(&mut out), Err(SaveError::NoValidData));
+/// save.write(b"hello").unwrap();
+/// assert_eq!(save.read(&mut out), Ok(5));
+/// assert_eq!(&out[..5], b"hello");
+///
+/// // corrupt the primary copy, and the secondary copy is used instead.
+/// let mut ram = save.into_inner();
+/// ram.0[14] ^= 0xFF;
+/// let mut save = Mirrored::new(ram, 0, 128, 64);
+/// assert_eq!(save.valid_slots(), Ok((false, true)));
+/// out = [0; 16];
+/// assert_eq!(save.read(&mut out), Ok(5));
+/// assert_eq!(&out[..5], b"hello");
+///
+/// // the next write repairs both copies.
+/// save.write(b"bye").unwrap();
+/// assert_eq!(save.valid_slots(), Ok((true, true)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Mirrored<M> {
+  media: M,
+  primary: usize,
+  secondary: usize,
+  slot_len: usize,
+}
+impl<M: RawSaveAccess> Mirrored<M> {
+  /// Wraps some media, mirroring data into the `slot_len` bytes at `primary`
+  /// and the `slot_len` bytes at `secondary`.
+  #[inline]
+  #[must_use]
+  pub const fn new(
+    media: M, primary: usize, secondary: usize, slot_len: usize,
+  ) -> Self {
+    Self { media, primary, secondary, slot_len }
+  }
+
+  /// Gives back the wrapped media.
+  #[inline]
+  #[must_use]
+  pub fn into_inner(self) -> M {
+    self.media
+  }
+
+  /// The most data that can be written at once, in bytes.
+  #[inline]
+  #[must_use]
+  pub const fn max_len(&self) -> usize {
+    self.slot_len.saturating_sub(SLOT_HEADER_LEN + WEAR_LEN_FIELD)
+  }
+
+  /// Checks the slot at `offset`, returning its data length if valid.
+  #[inline]
+  fn scan(&mut self, offset: usize) -> Result<Option<usize>, SaveError> {
+    check_bounds(self.media.info(), offset, self.slot_len)?;
+    if self.slot_len < SLOT_HEADER_LEN + WEAR_LEN_FIELD {
+      return Ok(None);
+    }
+    let mut len = [0_u8; WEAR_LEN_FIELD];
+    self.media.read(offset + SLOT_HEADER_LEN, &mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > self.max_len() {
+      return Ok(None);
+    }
+    let seq = scan_slot(&mut self.media, offset, WEAR_LEN_FIELD + len)?;
+    Ok(seq.map(|_| len))
+  }
+
+  /// If the primary and secondary slots (in that order) hold valid data.
+  ///
+  /// After a power loss or other damage, one slot can be invalid even though
+  /// [`read`](Mirrored::read) still succeeds. Writing again repairs it.
+  #[inline]
+  pub fn valid_slots(&mut self) -> Result<(bool, bool), SaveError> {
+    let primary = self.scan(self.primary)?.is_some();
+    let secondary = self.scan(self.secondary)?.is_some();
+    Ok((primary, secondary))
+  }
+
+  /// Writes `data` to the primary slot, and then to the secondary slot.
+  ///
+  /// If `data` is longer than [`max_len`](Mirrored::max_len) you'll get
+  /// [`SaveError::OutOfBounds`], and nothing is written.
+  #[inline]
+  pub fn write(&mut self, data: &[u8]) -> Result<(), SaveError> {
+    if data.len() > self.max_len() {
+      return Err(SaveError::OutOfBounds);
+    }
+    let info = self.media.info();
+    check_bounds(info, self.primary, self.slot_len)?;
+    check_bounds(info, self.secondary, self.slot_len)?;
+    let len = (data.len() as u32).to_le_bytes();
+    write_slot(&mut self.media, self.primary, 0, &[&len, data])?;
+    write_slot(&mut self.media, self.secondary, 0, &[&len, data])
+  }
+
+  /// Reads the data from the first valid slot into `out`.
+  ///
+  /// On success, returns the data's length. If neither slot is valid you'll
+  /// get [`SaveError::NoValidData`], and if the data doesn't fit in `out`
+  /// you'll get [`SaveError::OutOfBounds`].
+  #[inline]
+  pub fn read(&mut self, out: &mut [u8]) -> Result<usize, SaveError> {
+    let (offset, len) = match self.scan(self.primary)? {
+      Some(len) => (self.primary, len),
+      None => match self.scan(self.secondary)? {
+        Some(len) => (self.secondary, len),
+        None => return Err(SaveError::NoValidData),
+      },
+    };
+    let out = out.get_mut(..len).ok_or(SaveError::OutOfBounds)?;
+    self.media.read(offset + SLOT_HEADER_LEN + WEAR_LEN_FIELD, out)?;
+    Ok(len)
+  }
+}
+
+/// Total play time, counted in frames.
+///
+/// Call [`tick`](PlayClock::tick) once per frame (such as after waiting for
+/// vblank) while the game is being played. The clock counts exact CPU cycles
+/// internally, so it doesn't drift even though the GBA's frame rate isn't a
+/// whole number (it's about 59.73 frames per second).
+///
+/// To keep the play time across a save and load, store
+/// [`to_le_bytes`](PlayClock::to_le_bytes) in the save and restore it with
+/// [`from_le_bytes`](PlayClock::from_le_bytes). If all you need is whole
+/// seconds (eg: for the timestamp of [`SaveSlots::store`]), use
+/// [`elapsed_seconds`](PlayClock::elapsed_seconds) and
+/// [`from_seconds`](PlayClock::from_seconds) instead.
+///
+/// ```
+/// # use gba::save::*;
+/// let mut clock = PlayClock::new();
+/// for _ in 0..59 {
+///   clock.tick();
+/// }
+/// assert_eq!(clock.elapsed_seconds(), 0);
+/// clock.tick();
+/// assert_eq!(clock.elapsed_seconds(), 1);
+/// // one hour of frames
+/// clock.add_frames(215_040);
+/// assert_eq!(clock.elapsed_seconds(), 3601);
+/// // saving and loading keeps the partial second too
+/// let loaded = PlayClock::from_le_bytes(clock.to_le_bytes());
+/// assert_eq!(loaded, clock);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PlayClock {
+  seconds: u32,
+  /// CPU cycles past the last whole second, always less than
+  /// `CPU_FREQUENCY`.
+  cycles: u32,
+}
+impl PlayClock {
+  /// A clock at zero.
+  #[inline]
+  #[must_use]
+  pub const fn new() -> Self {
+    Self { seconds: 0, cycles: 0 }
+  }
+
+  /// A clock at the given number of whole seconds.
+  #[inline]
+  #[must_use]
+  pub const fn from_seconds(seconds: u32) -> Self {
+    Self { seconds, cycles: 0 }
+  }
+
+  /// The number of whole seconds played.
+  ///
+  /// This stops at `u32::MAX` (over 136 years).
+  #[inline]
+  #[must_use]
+  pub const fn elapsed_seconds(&self) -> u32 {
+    self.seconds
+  }
+
+  /// Adds one frame.
+  #[inline]
+  pub fn tick(&mut self) {
+    self.add_frames(1);
+  }
+
+  /// Adds any number of frames.
+  #[inline]
+  pub fn add_frames(&mut self, frames: u32) {
+    let total =
+      u64::from(self.cycles) + u64::from(frames) * u64::from(CYCLES_PER_FRAME);
+    let seconds = total / u64::from(CPU_FREQUENCY);
+    self.cycles = (total % u64::from(CPU_FREQUENCY)) as u32;
+    self.seconds =
+      u32::try_from(u64::from(self.seconds) + seconds).unwrap_or(u32::MAX);
+  }
+
+  /// The clock as bytes, for storing in a save.
+  ///
+  /// This is the seconds and then the partial second, both as little-endian
+  /// `u32` values.
+  #[inline]
+  #[must_use]
+  pub const fn to_le_bytes(&self) -> [u8; 8] {
+    let s = self.seconds.to_le_bytes();
+    let c = self.cycles.to_le_bytes();
+    [s[0], s[1], s[2], s[3], c[0], c[1], c[2], c[3]]
+  }
+
+  /// Restores a clock from bytes made by
+  /// [`to_le_bytes`](PlayClock::to_le_bytes).
+  ///
+  /// An out of range partial second (from corrupt data) is carried into the
+  /// seconds.
+  #[inline]
+  #[must_use]
+  pub const fn from_le_bytes(bytes: [u8; 8]) -> Self {
+    let seconds = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let cycles = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+    Self {
+      seconds: seconds.saturating_add(cycles / CPU_FREQUENCY),
+      cycles: cycles % CPU_FREQUENCY,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Save media that's just 256 bytes of RAM in 4 sectors.
+  pub(super) struct Ram(pub(super) [u8; 256]);
+  impl RawSaveAccess for Ram {
+    fn info(&self) -> MediaInfo {
+      MediaInfo { sector_shift: 6, sector_count: 4 }
+    }
+    fn read(&mut self, o: usize, b: &mut [u8]) -> Result<(), SaveError> {
+      b.copy_from_slice(&self.0[o..o + b.len()]);
+      Ok(())
+    }
+    fn prepare_write(&mut self, _: usize, _: usize) -> Result<(), SaveError> {
+      Ok(())
+    }
+    fn write(&mut self, o: usize, b: &[u8]) -> Result<(), SaveError> {
+      self.0[o..o + b.len()].copy_from_slice(b);
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn ab_buffer_falls_back_to_older_slot() {
+    let mut media = Ram([ERASED_BYTE; 256]);
+    let buf = AbBuffer::<4>::new(16);
+    let mut out = [0_u8; 4];
+    assert_eq!(buf.load(&mut media, &mut out), Err(SaveError::NoValidData));
+    assert_eq!(buf.store(&mut media, b"old!"), Ok(1));
+    assert_eq!(buf.store(&mut media, b"new!"), Ok(2));
+    assert_eq!(buf.load(&mut media, &mut out), Ok(2));
+    assert_eq!(&out, b"new!");
+    // the second store went to slot 1, corrupt its payload
+    media.0[16 + AbBuffer::<4>::SLOT_LEN + SLOT_HEADER_LEN] ^= 1;
+    assert_eq!(buf.load(&mut media, &mut out), Ok(1));
+    assert_eq!(&out, b"old!");
+    // the next store overwrites the broken slot, not the good one
+    assert_eq!(buf.store(&mut media, b"next"), Ok(2));
+    assert_eq!(buf.load(&mut media, &mut out), Ok(2));
+    assert_eq!(&out, b"next");
+  }
+
+  #[test]
+  fn sram_wait_bits() {
+    assert_eq!(waitcnt_with_sram_wait(0), 0b11);
+    // the crate runtime's boot value, which already has the slowest setting
+    assert_eq!(waitcnt_with_sram_wait(0x4317), 0x4317);
+    // faster SRAM settings are replaced, the ROM and prefetch bits are kept
+    assert_eq!(waitcnt_with_sram_wait(0x4314), 0x4317);
+    assert_eq!(waitcnt_with_sram_wait(0xFFFD), 0xFFFF);
+  }
+
+  #[test]
+  fn save_slots_listing() {
+    let mut media = Ram([ERASED_BYTE; 256]);
+    let slots = SaveSlots::<16>::new(32, 5);
+    slots.store(&mut media, 1, 7, 1000, &[1; 16]).unwrap();
+    slots.store(&mut media, 3, 8, 2000, &[3; 16]).unwrap();
+    slots.store(&mut media, 4, 9, 3000, &[4; 16]).unwrap();
+    // slot 2 is zeroed rather than erased, and slot 4 is damaged
+    let len = SaveSlots::<16>::SLOT_LEN;
+    media.0[32 + 2 * len..32 + 3 * len].fill(0);
+    media.0[32 + 5 * len - 1] ^= 0x80;
+    let empty =
+      |index| SlotInfo { index, valid: false, version: 0, timestamp: 0 };
+    let mut listed = [empty(9); 5];
+    for (out, info) in listed.iter_mut().zip(slots.list_slots(&mut media)) {
+      *out = info;
+    }
+    assert_eq!(
+      listed,
+      [
+        empty(0),
+        SlotInfo { index: 1, valid: true, version: 7, timestamp: 1000 },
+        empty(2),
+        SlotInfo { index: 3, valid: true, version: 8, timestamp: 2000 },
+        empty(4),
+      ]
+    );
+    assert_eq!(slots.list_slots(&mut media).count(), 5);
+    let mut out = [0_u8; 16];
+    assert_eq!(slots.load(&mut media, 3, &mut out).map(|i| i.version), Ok(8));
+    assert_eq!(out, [3; 16]);
+    assert_eq!(
+      slots.load(&mut media, 4, &mut out),
+      Err(SaveError::NoValidData)
+    );
+    assert_eq!(slots.info(&mut media, 5), Err(SaveError::OutOfBounds));
+    // slots past the end of the media are listed as not valid
+    let past_end = SaveSlots::<16>::new(224, 2);
+    let valid = past_end.list_slots(&mut media).filter(|i| i.valid).count();
+    assert_eq!(valid, 0);
+  }
+
+  #[test]
+  fn wear_leveled_rotates_sectors() {
+    // 2 slots of 1 sector each, in sectors 2 and 3
+    let mut save = WearLeveled::new(Ram([ERASED_BYTE; 256]), 2, 2, 1);
+    assert_eq!(save.max_len(), 64 - SLOT_HEADER_LEN - WEAR_LEN_FIELD);
+    assert_eq!(save.active_sector(), Ok(None));
+    let mut sectors = [0; 5];
+    for (n, sector) in sectors.iter_mut().enumerate() {
+      assert_eq!(save.write(&[n as u8; 3]), Ok(n as u32 + 1));
+      *sector = save.active_sector().unwrap().unwrap();
+    }
+    assert_eq!(sectors, [2, 3, 2, 3, 2]);
+    let mut out = [0_u8; 8];
+    assert_eq!(save.read_latest(&mut out), Ok(3));
+    assert_eq!(out[..3], [4; 3]);
+    // sectors before the first one are never touched
+    let mut ram = save.into_inner();
+    assert!(ram.0[..128].iter().all(|&b| b == ERASED_BYTE));
+    // a broken newest slot falls back to the one before it
+    ram.0[128 + SLOT_HEADER_LEN + WEAR_LEN_FIELD] ^= 1;
+    let mut save = WearLeveled::new(ram, 2, 2, 1);
+    assert_eq!(save.read_latest(&mut out), Ok(3));
+    assert_eq!(out[..3], [3; 3]);
+    assert_eq!(save.active_sector(), Ok(Some(3)));
+    let too_long = [0_u8; 64];
+    assert_eq!(save.write(&too_long), Err(SaveError::OutOfBounds));
+  }
+
+  #[test]
+  fn play_clock_accumulates_exact_cycles() {
+    let mut clock = PlayClock::new();
+    for frame in 1..=20_000_u64 {
+      clock.tick();
+      let expected =
+        frame * u64::from(CYCLES_PER_FRAME) / u64::from(CPU_FREQUENCY);
+      assert_eq!(u64::from(clock.elapsed_seconds()), expected, "{frame}");
+    }
+    // adding frames all at once lands on the same time
+    let mut bulk = PlayClock::new();
+    bulk.add_frames(20_000);
+    assert_eq!(bulk, clock);
+    // a save and load part way through doesn't lose the partial second
+    let mut loaded = PlayClock::from_le_bytes(clock.to_le_bytes());
+    for _ in 0..40 {
+      loaded.tick();
+      clock.tick();
+    }
+    assert_eq!(loaded, clock);
+    assert_ne!(PlayClock::from_seconds(clock.elapsed_seconds()), clock);
+    // the seconds stop at the max
+    let mut old = PlayClock::from_seconds(u32::MAX - 1);
+    old.add_frames(200);
+    assert_eq!(old.elapsed_seconds(), u32::MAX);
+  }
+
+  #[test]
+  fn format_prepares_everything_and_verifies() {
+    /// `Ram` that records prepares, and can have a byte stuck at 0x5A.
+    struct Logged {
+      ram: Ram,
+      erased: u8,
+      prepared: [Option<(usize, usize)>; 2],
+      stuck: Option<usize>,
+    }
+    impl RawSaveAccess for Logged {
+      fn info(&self) -> MediaInfo {
+        self.ram.info()
+      }
+      fn read(&mut self, o: usize, b: &mut [u8]) -> Result<(), SaveError> {
+        self.ram.read(o, b)
+      }
+      fn prepare_write(&mut self, s: usize, c: usize) -> Result<(), SaveError> {
+        let slot = self.prepared.iter_mut().find(|p| p.is_none()).unwrap();
+        *slot = Some((s, c));
+        Ok(())
+      }
+      fn write(&mut self, o: usize, b: &[u8]) -> Result<(), SaveError> {
+        self.ram.write(o, b)?;
+        if let Some(i) = self.stuck {
+          self.ram.0[i] = 0x5A;
+        }
+        Ok(())
+      }
+      fn erased_byte(&self) -> u8 {
+        self.erased
+      }
+    }
+    // SRAM and EEPROM get zero filled, Flash gets left erased
+    for erased in [0, ERASED_BYTE] {
+      let mut ram = Ram([ERASED_BYTE; 256]);
+      ram.0[100..110].fill(0x5A);
+      let mut media = Logged { ram, erased, prepared: [None; 2], stuck: None };
+      assert_eq!(format(&mut media), Ok(()));
+      assert_eq!(media.prepared, [Some((0, 4)), None]);
+      assert!(media.ram.0.iter().all(|&b| b == erased));
+      // a byte that won't erase is caught by the check afterward
+      media.ram.0[200] = 0x5A;
+      media.stuck = Some(200);
+      assert_eq!(format(&mut media), Err(SaveError::OperationFailed));
+    }
+  }
+
+  #[test]
+  fn mirrored_falls_back_to_secondary() {
+    let data_at = |slot: usize| slot + SLOT_HEADER_LEN + WEAR_LEN_FIELD;
+    // the primary slot can come after the secondary one
+    let mut save = Mirrored::new(Ram([ERASED_BYTE; 256]), 128, 64, 64);
+    save.write(&[1, 2, 3, 4, 5]).unwrap();
+    let mut out = [0_u8; 8];
+    // a bad primary payload falls back to the secondary copy
+    let mut ram = save.into_inner();
+    ram.0[data_at(128) + 2] ^= 0x10;
+    let mut save = Mirrored::new(ram, 128, 64, 64);
+    assert_eq!(save.valid_slots(), Ok((false, true)));
+    assert_eq!(save.read(&mut out), Ok(5));
+    assert_eq!(out[..5], [1, 2, 3, 4, 5]);
+    // and so does a primary length past the end of the slot
+    save.write(&[9; 3]).unwrap();
+    let mut ram = save.into_inner();
+    ram.0[128 + SLOT_HEADER_LEN] = 200;
+    let mut save = Mirrored::new(ram, 128, 64, 64);
+    assert_eq!(save.read(&mut out), Ok(3));
+    assert_eq!(out[..3], [9; 3]);
+    // with the secondary copy damaged too, there's nothing left to read
+    let mut ram = save.into_inner();
+    ram.0[data_at(64)] ^= 1;
+    let mut save = Mirrored::new(ram, 128, 64, 64);
+    assert_eq!(save.valid_slots(), Ok((false, false)));
+    assert_eq!(save.read(&mut out), Err(SaveError::NoValidData));
+    // a write repairs both, and a short buffer is an error
+    save.write(&[7; 6]).unwrap();
+    assert_eq!(save.valid_slots(), Ok((true, true)));
+    assert_eq!(save.read(&mut out[..5]), Err(SaveError::OutOfBounds));
+  }
+
+  #[test]
+  fn retry_with_flaky_media() {
+    /// `Ram` where the first `failures` writes don't work.
+    struct Flaky {
+      ram: Ram,
+      failures: u32,
+      writes: u32,
+    }
+    impl Flaky {
+      fn write(&mut self, o: usize, b: &[u8]) -> Result<(), SaveError> {
+        self.writes += 1;
+        if self.writes <= self.failures {
+          return Err(SaveError::OperationFailed);
+        }
+        self.ram.write(o, b)
+      }
+    }
+    let mut media =
+      Flaky { ram: Ram([ERASED_BYTE; 256]), failures: 3, writes: 0 };
+    // the waits only happen between attempts
+    let waits = core::cell::Cell::new(0);
+    let result = retry_with(
+      3,
+      || waits.set(waits.get() + 1),
+      || {
+        assert_eq!(waits.get(), media.writes, "no wait before an attempt");
+        media.write(8, b"ok")
+      },
+    );
+    assert_eq!(result, Ok(()));
+    assert_eq!((media.writes, waits.get()), (4, 3));
+    assert_eq!(&media.ram.0[8..10], b"ok");
+    // one retry too few gives back the last error
+    media.writes = 0;
+    let result = retry_with(2, || (), || media.write(0, b"no"));
+    assert_eq!(result, Err(SaveError::OperationFailed));
+    assert_eq!(media.writes, 3);
+    // a working operation runs just once
+    media.failures = 0;
+    media.writes = 0;
+    assert_eq!(
+      retry_with(5, || panic!("waited"), || media.write(0, b"x")),
+      Ok(())
+    );
+    assert_eq!(media.writes, 1);
+  }
+}
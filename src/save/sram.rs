@@ -0,0 +1,100 @@
+//! Battery backed SRAM save media.
+//!
+//! SRAM is the simplest kind of save media. It's 32K of memory mapped at
+//! `0x0E00_0000`, and it can be read or written at any time. It's only
+//! connected with an 8-bit bus, so it must always be accessed one byte at a
+//! time.
+
+use super::{
+  check_bounds, waitcnt_with_sram_wait, MediaInfo, RawSaveAccess, SaveError,
+};
+use crate::{mem::copy_u8_unchecked, mmio::WAITCNT};
+
+/// The address of the start of SRAM.
+const SRAM_BASE: usize = 0x0E00_0000;
+
+/// The size of SRAM, in bytes.
+const SRAM_LEN: usize = 32 * 1024;
+
+/// Sets the SRAM wait state in `WAITCNT` to 8 cycles.
+///
+/// The crate's runtime already picks this setting at boot, but if anything
+/// has written `WAITCNT` since then (eg: to tune the ROM wait states) the SRAM
+/// setting might have been changed too. SRAM only works with the 8 cycle
+/// setting, any faster setting can make reads return garbage and corrupt
+/// saves. The other wait state bits are left as they are.
+///
+/// [`SramAccess::new`] calls this for you.
+#[inline]
+pub fn configure_wait_state() {
+  let waitcnt = WAITCNT.read();
+  // Safety: only the SRAM bits change, and to the slowest setting, which
+  // can't make any memory access less reliable.
+  unsafe { WAITCNT.write(waitcnt_with_sram_wait(waitcnt)) };
+}
+
+/// Access to battery backed SRAM.
+///
+/// SRAM doesn't have sectors that need erasing, so each "sector" is a single
+/// byte and [`prepare_write`](RawSaveAccess::prepare_write) does nothing.
+#[derive(Debug)]
+pub struct SramAccess(());
+impl SramAccess {
+  /// Gets access to SRAM.
+  ///
+  /// This also sets the SRAM wait state (see [`configure_wait_state`]). It
+  /// doesn't check that the cartridge actually has SRAM.
+  #[inline]
+  #[must_use]
+  pub fn new() -> Self {
+    configure_wait_state();
+    Self(())
+  }
+}
+impl Default for SramAccess {
+  #[inline]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+impl RawSaveAccess for SramAccess {
+  #[inline]
+  fn info(&self) -> MediaInfo {
+    MediaInfo { sector_shift: 0, sector_count: SRAM_LEN }
+  }
+
+  #[inline]
+  fn read(
+    &mut self, offset: usize, buffer: &mut [u8],
+  ) -> Result<(), SaveError> {
+    check_bounds(self.info(), offset, buffer.len())?;
+    unsafe {
+      copy_u8_unchecked(
+        buffer.as_mut_ptr(),
+        (SRAM_BASE + offset) as *const u8,
+        buffer.len(),
+      )
+    };
+    Ok(())
+  }
+
+  #[inline]
+  fn prepare_write(
+    &mut self, sector: usize, count: usize,
+  ) -> Result<(), SaveError> {
+    check_bounds(self.info(), sector, count)
+  }
+
+  #[inline]
+  fn write(&mut self, offset: usize, buffer: &[u8]) -> Result<(), SaveError> {
+    check_bounds(self.info(), offset, buffer.len())?;
+    unsafe {
+      copy_u8_unchecked(
+        (SRAM_BASE + offset) as *mut u8,
+        buffer.as_ptr(),
+        buffer.len(),
+      )
+    };
+    Ok(())
+  }
+}
@@ -3,6 +3,19 @@ use crate::macros::{
   u8_bool_field, u8_int_field,
 };
 
+use crate::timers::{CPU_FREQUENCY, CYCLES_PER_FRAME};
+#[cfg(feature = "on_gba")]
+use crate::{
+  dma::{configure_fifo_dma, DmaControl, SoundFifo},
+  mmio::{
+    DMA1_CONTROL, LEFT_RIGHT_VOLUME, NOISE_FREQ, NOISE_LEN_ENV, SOUND_ENABLED,
+    SOUND_MIX, TIMER0_CONTROL, TIMER0_RELOAD, TONE1_FREQUENCY, TONE1_PATTERN,
+    TONE2_FREQUENCY, TONE2_PATTERN, WAVE_BANK, WAVE_FREQ, WAVE_LEN_VOLUME,
+    WAVE_RAM,
+  },
+  timers::TimerControl,
+};
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(transparent)]
 pub struct SweepControl(u8);
@@ -167,3 +180,692 @@ impl SoundBias {
   u16_int_field!(1 - 9, bias_level, with_bias_level);
   u16_enum_field!(14 - 15: SampleCycle, sample_cycle, with_sample_cycle);
 }
+
+/// A PSG channel that has a volume envelope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PsgChannel {
+  /// Tone 1 (the one with frequency sweep).
+  Tone1,
+  /// Tone 2.
+  Tone2,
+  /// Noise.
+  Noise,
+}
+
+/// The stages of an [`Adsr`] envelope.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AdsrStage {
+  /// No note is playing.
+  #[default]
+  Idle,
+  /// Rising from silence up to full volume.
+  Attack,
+  /// Falling from full volume to the sustain level.
+  Decay,
+  /// Holding at the sustain level until [`Adsr::release`].
+  Sustain,
+  /// Falling from the current level to silence.
+  Release,
+}
+
+/// A software attack/decay/sustain/release volume envelope for a PSG channel.
+///
+/// The PSG hardware envelope can only step the volume in one direction, so
+/// this instead picks a volume once per frame and reprograms the channel.
+/// * `attack`: frames to rise from 0 to full volume (15).
+/// * `decay`: frames to fall from full volume to the sustain level.
+/// * `sustain`: the volume level (`0..=15`) held until the note is released.
+/// * `release`: frames to fall from the current level to 0.
+///
+/// A stage with 0 frames is skipped.
+///
+/// ## Approximation
+/// The channel volume is only 4 bits, and it only changes once per
+/// [`tick`](Adsr::tick), so the envelope is a staircase rather than a smooth
+/// curve. Also, the hardware only takes a new volume when the channel is
+/// restarted, so each volume change restarts the channel. This resets the
+/// channel's length counter and sweep, and can cause a faint click. For short
+/// sound effects that's usually not noticeable.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Adsr {
+  attack: u8,
+  decay: u8,
+  sustain: u8,
+  release: u8,
+  stage: AdsrStage,
+  frame: u8,
+  level: u8,
+  release_from: u8,
+  retrigger: u16,
+}
+impl Adsr {
+  /// Makes a new envelope. The `sustain` level is clamped to `0..=15`.
+  #[inline]
+  #[must_use]
+  pub const fn new(attack: u8, decay: u8, sustain: u8, release: u8) -> Self {
+    Self {
+      attack,
+      decay,
+      sustain: if sustain > 15 { 15 } else { sustain },
+      release,
+      stage: AdsrStage::Idle,
+      frame: 0,
+      level: 0,
+      release_from: 0,
+      retrigger: 0,
+    }
+  }
+
+  /// The current stage.
+  #[inline]
+  #[must_use]
+  pub const fn stage(&self) -> AdsrStage {
+    self.stage
+  }
+
+  /// The current volume level (`0..=15`).
+  #[inline]
+  #[must_use]
+  pub const fn level(&self) -> u8 {
+    self.level
+  }
+
+  /// Starts a note, beginning at the attack stage.
+  ///
+  /// The `frequency` is what gets written when the channel is restarted:
+  /// * For tone channels it's the 11-bit frequency value (see
+  ///   [`ToneFrequency`]).
+  /// * For the noise channel it's the low 8 bits of [`NoiseFrequency`].
+  #[inline]
+  pub fn note_on(&mut self, frequency: u16) {
+    self.retrigger = frequency;
+    self.stage = AdsrStage::Attack;
+    self.frame = 0;
+    self.level = 0;
+  }
+
+  /// Moves the note to the release stage.
+  #[inline]
+  pub fn release(&mut self) {
+    if self.stage != AdsrStage::Idle {
+      self.stage = AdsrStage::Release;
+      self.frame = 0;
+      self.release_from = self.level;
+    }
+  }
+
+  /// Advances the envelope by one frame, returning the new volume level.
+  ///
+  /// This doesn't touch the hardware, [`tick`](Adsr::tick) calls this and then
+  /// programs the channel.
+  #[inline]
+  pub fn advance(&mut self) -> u8 {
+    loop {
+      let (len, from, to, next) = match self.stage {
+        AdsrStage::Idle => return self.level,
+        AdsrStage::Sustain => {
+          self.level = self.sustain;
+          return self.level;
+        }
+        AdsrStage::Attack => (self.attack, 0, 15, AdsrStage::Decay),
+        AdsrStage::Decay => (self.decay, 15, self.sustain, AdsrStage::Sustain),
+        AdsrStage::Release => {
+          (self.release, self.release_from, 0, AdsrStage::Idle)
+        }
+      };
+      if len == 0 {
+        // zero length stages are skipped without taking up a frame.
+        self.level = to;
+        self.stage = next;
+        continue;
+      }
+      self.frame += 1;
+      self.level = lerp_level(from, to, self.frame, len);
+      if self.frame == len {
+        self.stage = next;
+        self.frame = 0;
+      }
+      return self.level;
+    }
+  }
+
+  /// Advances the envelope one frame and programs the channel's volume.
+  ///
+  /// The channel is only touched when the volume level actually changes.
+  #[inline]
+  #[cfg(feature = "on_gba")]
+  pub fn tick(&mut self, channel: PsgChannel) {
+    let old = self.level;
+    let new = self.advance();
+    if old == new {
+      return;
+    }
+    let volume = u16::from(new);
+    match channel {
+      PsgChannel::Tone1 => {
+        TONE1_PATTERN
+          .write(TONE1_PATTERN.read().with_volume(volume).with_step_time(0));
+        TONE1_FREQUENCY.write(
+          ToneFrequency::new()
+            .with_frequency(self.retrigger)
+            .with_enabled(true),
+        );
+      }
+      PsgChannel::Tone2 => {
+        TONE2_PATTERN
+          .write(TONE2_PATTERN.read().with_volume(volume).with_step_time(0));
+        TONE2_FREQUENCY.write(
+          ToneFrequency::new()
+            .with_frequency(self.retrigger)
+            .with_enabled(true),
+        );
+      }
+      PsgChannel::Noise => {
+        NOISE_LEN_ENV
+          .write(NOISE_LEN_ENV.read().with_volume(volume).with_step_time(0));
+        NOISE_FREQ
+          .write(NoiseFrequency(self.retrigger & 0xFF).with_enabled(true));
+      }
+    }
+  }
+}
+
+/// Linear interpolation of a volume level, `frame` out of `len` from `from`
+/// to `to`.
+const fn lerp_level(from: u8, to: u8, frame: u8, len: u8) -> u8 {
+  let from = from as i32;
+  let to = to as i32;
+  (from + (to - from) * frame as i32 / len as i32) as u8
+}
+
+/// The number of CPU cycles between samples when playing at `rate_hz`.
+///
+/// This is rounded to the nearest cycle, so the actual playback rate can be
+/// very slightly different from `rate_hz`.
+///
+/// ## Panics
+/// * If `rate_hz` is less than 256 (the timer can't count that slowly without a
+///   prescaler) or more than [`CPU_FREQUENCY`].
+#[inline]
+#[must_use]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub const fn sample_period(rate_hz: u32) -> u32 {
+  assert!(rate_hz >= 256 && rate_hz <= CPU_FREQUENCY);
+  let period = (CPU_FREQUENCY + rate_hz / 2) / rate_hz;
+  if period > 0x1_0000 {
+    0x1_0000
+  } else {
+    period
+  }
+}
+
+/// The timer reload value that overflows once per sample at `rate_hz`.
+///
+/// ## Panics
+/// * Same as [`sample_period`].
+#[inline]
+#[must_use]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub const fn sample_timer_reload(rate_hz: u32) -> u16 {
+  (0x1_0000 - sample_period(rate_hz)) as u16
+}
+
+/// How many frames it takes to play `len` samples at `rate_hz`.
+///
+/// This is rounded up to a whole frame, and it's the teardown time that
+/// [`play_sample_once`] uses.
+///
+/// ```
+/// # use gba::sound::sample_play_frames;
+/// // One second of 16KHz audio is just under 60 frames.
+/// assert_eq!(sample_play_frames(16_384, 16_384), 60);
+/// // Even a single sample takes a frame to stop.
+/// assert_eq!(sample_play_frames(1, 16_384), 1);
+/// assert_eq!(sample_play_frames(0, 16_384), 0);
+/// ```
+///
+/// ## Panics
+/// * Same as [`sample_period`].
+#[inline]
+#[must_use]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub const fn sample_play_frames(len: usize, rate_hz: u32) -> u32 {
+  let cycles = len as u64 * sample_period(rate_hz) as u64;
+  cycles.div_ceil(CYCLES_PER_FRAME as u64) as u32
+}
+
+/// A sample started by [`play_sample_once`].
+///
+/// Call [`tick`](OneShotSample::tick) once per frame, and it will stop the
+/// playback once the sample is done.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct OneShotSample {
+  frames_left: u32,
+}
+impl OneShotSample {
+  /// The number of frames until playback is stopped.
+  #[inline]
+  #[must_use]
+  pub const fn frames_left(&self) -> u32 {
+    self.frames_left
+  }
+
+  /// If the sample is still playing.
+  #[inline]
+  #[must_use]
+  pub const fn is_playing(&self) -> bool {
+    self.frames_left > 0
+  }
+
+  /// Counts off one frame, stopping playback when the sample has ended.
+  ///
+  /// Returns if the sample is still playing.
+  #[inline]
+  #[cfg(feature = "on_gba")]
+  pub fn tick(&mut self) -> bool {
+    if self.frames_left > 0 {
+      self.frames_left -= 1;
+      if self.frames_left == 0 {
+        stop_sample();
+      }
+    }
+    self.is_playing()
+  }
+}
+
+/// Plays a signed 8-bit sample one time through Direct Sound A.
+///
+/// This uses:
+/// * Direct Sound A (the FIFO A buffer), output to both speakers at full
+///   volume.
+/// * DMA1, to refill FIFO A.
+/// * Timer 0, to set the sample rate.
+///
+/// Any other use of those is overwritten. The sound hardware is also switched
+/// on if it wasn't already.
+///
+/// The FIFO DMA has no "end of data" event, so the playback is stopped after a
+/// number of frames based on the sample's length and rate (see
+/// [`sample_play_frames`]). Call [`OneShotSample::tick`] on the returned value
+/// once per frame, or call [`stop_sample`] yourself.
+///
+/// ## Safety
+/// * `samples` must stay valid until playback is stopped.
+/// * The DMA keeps reading past the end of `samples` until the playback is
+///   stopped, up to one frame's worth of samples plus 16 bytes. That memory
+///   must be readable, and it's what will be heard during that time, so it's
+///   best to pad the end of the sample with silence.
+/// * `samples` must be aligned to 4.
+///
+/// ## Panics
+/// * Same as [`sample_period`].
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub unsafe fn play_sample_once(samples: &[i8], rate_hz: u32) -> OneShotSample {
+  let reload = sample_timer_reload(rate_hz);
+  stop_sample();
+  SOUND_ENABLED.write(SoundEnable::new().with_enabled(true));
+  SOUND_MIX.write(
+    SOUND_MIX
+      .read()
+      .with_sound_a_full(true)
+      .with_sound_a_left(true)
+      .with_sound_a_right(true)
+      .with_sound_a_timer(false)
+      .with_sound_a_reset(true),
+  );
+  // DMA1 is always a valid FIFO channel, so this can't fail.
+  configure_fifo_dma(1, SoundFifo::A, samples.as_ptr()).ok();
+  TIMER0_RELOAD.write(reload);
+  TIMER0_CONTROL.write(TimerControl::new().with_enabled(true));
+  OneShotSample { frames_left: sample_play_frames(samples.len(), rate_hz) }
+}
+
+/// Stops Direct Sound A playback started by [`play_sample_once`].
+///
+/// This disables DMA1 and Timer 0, clears FIFO A, and takes Direct Sound A
+/// off of both speakers.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn stop_sample() {
+  TIMER0_CONTROL.write(TimerControl::new());
+  // Safety: disabling the DMA unit can't cause any transfers.
+  unsafe { DMA1_CONTROL.write(DmaControl::new()) };
+  SOUND_MIX.write(
+    SOUND_MIX
+      .read()
+      .with_sound_a_left(false)
+      .with_sound_a_right(false)
+      .with_sound_a_reset(true),
+  );
+}
+
+/// A built-in waveform for [`Wavetable`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Waveform {
+  /// A sine wave.
+  #[default]
+  Sine,
+  /// A sawtooth wave, rising from low to high.
+  Saw,
+  /// A square wave with a 50% duty cycle.
+  Square,
+  /// A triangle wave.
+  Triangle,
+}
+impl Waveform {
+  /// The 32 four-bit samples of the wave, in play order.
+  #[inline]
+  #[must_use]
+  pub const fn samples(self) -> [u8; 32] {
+    const SINE: [u8; 32] = [
+      8, 9, 10, 12, 13, 14, 14, 15, 15, 15, 14, 14, 13, 12, 10, 9, 8, 6, 5, 3,
+      2, 1, 1, 0, 0, 0, 1, 1, 2, 3, 5, 6,
+    ];
+    let mut out = [0_u8; 32];
+    let mut i = 0;
+    while i < 32 {
+      out[i] = match self {
+        Waveform::Sine => SINE[i],
+        Waveform::Saw => (i / 2) as u8,
+        Waveform::Square => {
+          if i < 16 {
+            15
+          } else {
+            0
+          }
+        }
+        Waveform::Triangle => {
+          if i < 16 {
+            i as u8
+          } else {
+            (31 - i) as u8
+          }
+        }
+      };
+      i += 1;
+    }
+    out
+  }
+
+  /// The wave packed into the 16 bytes of one wave RAM bank.
+  ///
+  /// Each byte holds two samples, with the first one in the high bits.
+  ///
+  /// ```
+  /// # use gba::sound::*;
+  /// let bytes = Waveform::Square.wave_ram_bytes();
+  /// assert_eq!(bytes[..8], [0xFF; 8]);
+  /// assert_eq!(bytes[8..], [0x00; 8]);
+  /// let bytes = Waveform::Saw.wave_ram_bytes();
+  /// assert_eq!(bytes[..3], [0x00, 0x11, 0x22]);
+  /// ```
+  #[inline]
+  #[must_use]
+  pub const fn wave_ram_bytes(self) -> [u8; 16] {
+    let samples = self.samples();
+    let mut out = [0_u8; 16];
+    let mut i = 0;
+    while i < 16 {
+      out[i] = (samples[i * 2] << 4) | samples[i * 2 + 1];
+      i += 1;
+    }
+    out
+  }
+
+  /// The wave packed into the four words of
+  /// [`WAVE_RAM`](crate::mmio::WAVE_RAM).
+  #[inline]
+  #[must_use]
+  pub const fn wave_ram_words(self) -> [u32; 4] {
+    let b = self.wave_ram_bytes();
+    let mut out = [0_u32; 4];
+    let mut i = 0;
+    while i < 4 {
+      out[i] = u32::from_le_bytes([
+        b[i * 4],
+        b[i * 4 + 1],
+        b[i * 4 + 2],
+        b[i * 4 + 3],
+      ]);
+      i += 1;
+    }
+    out
+  }
+}
+
+/// Gets the [`WaveFrequency`] rate value that plays a full 32 sample wave at
+/// `hz` times per second.
+///
+/// The wave channel can play a 32 sample wave at 32 Hz up to 65,536 Hz.
+/// Values outside that range are clamped.
+///
+/// ```
+/// # use gba::sound::*;
+/// assert_eq!(wave_rate(440), 1899);
+/// assert_eq!(wave_rate(32), 0);
+/// assert_eq!(wave_rate(1), 0);
+/// ```
+#[inline]
+#[must_use]
+pub const fn wave_rate(hz: u32) -> u16 {
+  let hz = if hz < 32 {
+    32
+  } else if hz > 65536 {
+    65536
+  } else {
+    hz
+  };
+  // the wave plays at 2097152 / (32 * (2048 - rate)) Hz
+  let period = (65536 + hz / 2) / hz;
+  (2048 - period) as u16
+}
+
+/// Uses the wave channel (PSG channel 3) as a simple oscillator.
+///
+/// The channel is set to one bank of 32 samples, and the selected
+/// [`Waveform`] is loaded into that bank.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Wavetable {
+  waveform: Waveform,
+}
+impl Wavetable {
+  /// Makes a new wavetable, using [`Waveform::Sine`].
+  ///
+  /// This doesn't touch the hardware until you call
+  /// [`set_waveform`](Wavetable::set_waveform).
+  #[inline]
+  #[must_use]
+  pub const fn new() -> Self {
+    Self { waveform: Waveform::Sine }
+  }
+
+  /// The current waveform.
+  #[inline]
+  #[must_use]
+  pub const fn waveform(&self) -> Waveform {
+    self.waveform
+  }
+
+  /// Loads a waveform into wave RAM bank 0.
+  ///
+  /// The CPU can only write the bank that isn't being played, so this
+  /// switches playback to bank 1 during the write and then back to bank 0.
+  /// Any sound currently playing on the channel is stopped.
+  #[inline]
+  #[cfg(feature = "on_gba")]
+  pub fn set_waveform(&mut self, waveform: Waveform) {
+    self.waveform = waveform;
+    WAVE_BANK.write(WaveBank::new().with_bank1(true));
+    for (addr, word) in WAVE_RAM.iter().zip(waveform.wave_ram_words()) {
+      addr.write(word);
+    }
+    WAVE_BANK.write(WaveBank::new().with_enabled(true));
+  }
+
+  /// Plays the loaded waveform at `hz`, on both speakers at full volume.
+  ///
+  /// See [`wave_rate`] for the allowed range of `hz`.
+  #[inline]
+  #[cfg(feature = "on_gba")]
+  pub fn play(&self, hz: u32) {
+    SOUND_ENABLED.write(SOUND_ENABLED.read().with_enabled(true));
+    LEFT_RIGHT_VOLUME.write(
+      LEFT_RIGHT_VOLUME
+        .read()
+        .with_left_volume(7)
+        .with_right_volume(7)
+        .with_wave_left(true)
+        .with_wave_right(true),
+    );
+    WAVE_BANK.write(WaveBank::new().with_enabled(true));
+    WAVE_LEN_VOLUME.write(WaveLenVolume::new().with_volume(1));
+    WAVE_FREQ.write(
+      WaveFrequency::new().with_length(wave_rate(hz)).with_enabled(true),
+    );
+  }
+
+  /// Stops the wave channel.
+  #[inline]
+  #[cfg(feature = "on_gba")]
+  pub fn stop(&self) {
+    WAVE_BANK.write(WaveBank::new());
+  }
+}
+
+/// Sets which speakers a PSG channel plays on, from a pan position.
+///
+/// `channel` is the PSG channel number: 1 and 2 are the tone channels, 3 is
+/// the wave channel, and 4 is the noise channel. The other settings of
+/// `current` are kept.
+///
+/// The hardware can only turn each channel on or off for each side, so `pan`
+/// is split into three zones:
+/// * `-64..=-22` (or lower): left only.
+/// * `-21..=21`: both sides.
+/// * `22..=63` (or higher): right only.
+///
+/// ```
+/// # use gba::sound::*;
+/// let hard_left = channel_pan(LeftRightVolume::new(), 2, -64);
+/// assert!(hard_left.tone2_left() && !hard_left.tone2_right());
+/// let center = channel_pan(hard_left, 2, 0);
+/// assert!(center.tone2_left() && center.tone2_right());
+/// let hard_right = channel_pan(center, 2, 63);
+/// assert!(!hard_right.tone2_left() && hard_right.tone2_right());
+/// ```
+///
+/// ## Panics
+/// * If `channel` isn't `1..=4`.
+#[inline]
+#[must_use]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub const fn channel_pan(
+  current: LeftRightVolume, channel: u8, pan: i8,
+) -> LeftRightVolume {
+  assert!(channel >= 1 && channel <= 4, "PSG channels are 1 to 4");
+  let left = pan < 22;
+  let right = pan > -22;
+  // the left bits are 4 above the matching right bits
+  let right_bit = 1 << (7 + channel);
+  let left_bit = right_bit << 4;
+  let mut bits = current.0 & !(left_bit | right_bit);
+  if left {
+    bits |= left_bit;
+  }
+  if right {
+    bits |= right_bit;
+  }
+  LeftRightVolume(bits)
+}
+
+/// Pans a PSG channel by updating
+/// [`LEFT_RIGHT_VOLUME`](crate::mmio::LEFT_RIGHT_VOLUME).
+///
+/// See [`channel_pan`] for how `pan` is used.
+///
+/// ## Panics
+/// * If `channel` isn't `1..=4`.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn set_channel_pan(channel: u8, pan: i8) {
+  LEFT_RIGHT_VOLUME.write(channel_pan(LEFT_RIGHT_VOLUME.read(), channel, pan));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn adsr_volume_trajectory() {
+    let mut env = Adsr::new(3, 2, 9, 4);
+    assert_eq!(env.advance(), 0);
+    env.note_on(0);
+    let levels = [0; 7].map(|_| env.advance());
+    assert_eq!(levels, [5, 10, 15, 12, 9, 9, 9]);
+    assert_eq!(env.stage(), AdsrStage::Sustain);
+    env.release();
+    let levels = [0; 5].map(|_| env.advance());
+    assert_eq!(levels, [7, 5, 3, 0, 0]);
+    assert_eq!(env.stage(), AdsrStage::Idle);
+  }
+
+  #[test]
+  fn adsr_zero_length_stages() {
+    let mut env = Adsr::new(0, 0, 12, 0);
+    env.note_on(0);
+    assert_eq!(env.advance(), 12);
+    assert_eq!(env.stage(), AdsrStage::Sustain);
+    env.release();
+    assert_eq!(env.advance(), 0);
+    assert_eq!(env.stage(), AdsrStage::Idle);
+  }
+
+  #[test]
+  fn one_shot_teardown_frames() {
+    // 761 cycles per sample at 22050Hz, and a frame is 280,896 cycles
+    assert_eq!(sample_play_frames(22_050, 22_050), 60);
+    assert_eq!(sample_play_frames(1_000, 22_050), 3);
+    // at 16KHz a frame is 274.3 samples long
+    assert_eq!(sample_play_frames(274, 16_384), 1);
+    assert_eq!(sample_play_frames(275, 16_384), 2);
+    assert_eq!(sample_play_frames(0, 8_000), 0);
+  }
+
+  #[test]
+  fn square_wave_ram_packing() {
+    let bytes = Waveform::Square.wave_ram_bytes();
+    assert_eq!(
+      bytes,
+      [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0, 0, 0, 0, 0]
+    );
+    // the words go in wave RAM order, so the low byte is played first
+    assert_eq!(
+      Waveform::Square.wave_ram_words(),
+      [0xFFFF_FFFF, 0xFFFF_FFFF, 0, 0]
+    );
+    // the first sample of each pair is the high nibble
+    let bytes = Waveform::Triangle.wave_ram_bytes();
+    assert_eq!(bytes[..2], [0x01, 0x23]);
+    assert_eq!(bytes[7..9], [0xEF, 0xFE]);
+    assert_eq!(Waveform::Triangle.wave_ram_words()[0], 0x6745_2301);
+  }
+
+  #[test]
+  fn pan_enable_bits() {
+    // master volumes set, and channel 1 on both sides already
+    let current = LeftRightVolume(0x1177);
+    let bits = |pan| channel_pan(current, 3, pan).0;
+    assert_eq!(bits(-64), 0x5177);
+    assert_eq!(bits(0), 0x5577);
+    assert_eq!(bits(63), 0x1577);
+    // the edges of the zones, and the extremes
+    assert_eq!([bits(-128), bits(-22), bits(-21)], [0x5177, 0x5177, 0x5577]);
+    assert_eq!([bits(21), bits(22), bits(127)], [0x5577, 0x1577, 0x1577]);
+    // each channel has its own pair of bits
+    assert_eq!(channel_pan(LeftRightVolume(0), 1, -64).0, 0x1000);
+    assert_eq!(channel_pan(LeftRightVolume(0), 4, 63).0, 0x0800);
+  }
+}
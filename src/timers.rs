@@ -62,6 +62,14 @@
 //! cascade bit.
 
 use crate::macros::{pub_const_fn_new_zeroed, u16_bool_field, u16_enum_field};
+#[cfg(feature = "on_gba")]
+use crate::{
+  interrupts::{set_irq_handler, IrqBits, IrqFn},
+  mmio::{
+    IE, TIMER0_CONTROL, TIMER0_RELOAD, TIMER1_CONTROL, TIMER1_RELOAD,
+    TIMER2_CONTROL, TIMER2_RELOAD, TIMER3_CONTROL, TIMER3_RELOAD,
+  },
+};
 
 /// A number of CPU cycles per timer tick.
 ///
@@ -106,3 +114,315 @@ impl TimerControl {
   u16_bool_field!(6, overflow_irq, with_overflow_irq);
   u16_bool_field!(7, enabled, with_enabled);
 }
+
+/// The number of CPU cycles per second.
+pub const CPU_FREQUENCY: u32 = 16_777_216;
+
+/// The number of CPU cycles in one full frame (draw plus blank periods).
+pub const CYCLES_PER_FRAME: u32 = 280_896;
+
+/// A fixed timestep accumulator.
+///
+/// This lets game logic run a fixed number of times per real second no matter
+/// how long each frame takes to render. Each time you call [`update`] (or
+/// [`advance`]) you get back how many fixed steps have elapsed, and you should
+/// run your logic that many times. Any leftover time that isn't enough for a
+/// full step is carried over to the next call.
+///
+/// The accounting is done in exact integer cycles, so there's no drift.
+///
+/// [`update`]: FixedStep::update
+/// [`advance`]: FixedStep::advance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FixedStep {
+  hz: u32,
+  /// Elapsed time in units of `1 / (CPU_FREQUENCY * hz)` seconds, always less
+  /// than one step.
+  acc: u64,
+}
+impl FixedStep {
+  /// Makes an accumulator that steps `hz` times per second.
+  ///
+  /// ## Panics
+  /// * If `hz` is 0.
+  #[inline]
+  #[must_use]
+  #[cfg_attr(feature = "track_caller", track_caller)]
+  pub const fn new(hz: u32) -> Self {
+    assert!(hz > 0);
+    Self { hz, acc: 0 }
+  }
+
+  /// The number of steps per second.
+  #[inline]
+  #[must_use]
+  pub const fn hz(&self) -> u32 {
+    self.hz
+  }
+
+  /// Adds `cycles` of elapsed time, returning how many steps elapsed.
+  ///
+  /// Use this when you're measuring time with a timer unit.
+  #[inline]
+  pub fn advance(&mut self, cycles: u32) -> u32 {
+    self.acc += u64::from(cycles) * u64::from(self.hz);
+    let steps = self.acc / u64::from(CPU_FREQUENCY);
+    self.acc %= u64::from(CPU_FREQUENCY);
+    steps as u32
+  }
+
+  /// Adds one frame of elapsed time, returning how many steps elapsed.
+  ///
+  /// Call this once per vertical blank.
+  #[inline]
+  pub fn update(&mut self) -> u32 {
+    self.advance(CYCLES_PER_FRAME)
+  }
+}
+
+/// Picks the timer scale and reload value that overflow `hz` times per second.
+///
+/// The smallest scale that can reach `hz` is used, since that gives the most
+/// precise timing. The number of ticks per overflow is rounded to the nearest
+/// whole tick, so the actual frequency can be slightly off from `hz`.
+///
+/// Returns `None` if `hz` is 0 or too high, or too low for even the largest
+/// scale (below about 0.25 Hz).
+///
+/// ```
+/// # use gba::timers::{timer_settings_for, TimerScale};
+/// // 1 Hz is a full 65,536 ticks at the 256 scale.
+/// assert_eq!(timer_settings_for(1), Some((TimerScale::_256, 0)));
+/// // 1000 Hz is 16,777 cycles (rounded) without a scale.
+/// assert_eq!(
+///   timer_settings_for(1000),
+///   Some((TimerScale::_1, 16777_u16.wrapping_neg()))
+/// );
+/// // 100 Hz needs 167,772 cycles, so the 64 scale is used: 2621 ticks.
+/// assert_eq!(
+///   timer_settings_for(100),
+///   Some((TimerScale::_64, 2621_u16.wrapping_neg()))
+/// );
+/// assert_eq!(timer_settings_for(0), None);
+/// ```
+#[inline]
+#[must_use]
+pub const fn timer_settings_for(hz: u32) -> Option<(TimerScale, u16)> {
+  if hz == 0 {
+    return None;
+  }
+  let scales = [
+    (TimerScale::_1, 0),
+    (TimerScale::_64, 6),
+    (TimerScale::_256, 8),
+    (TimerScale::_1024, 10),
+  ];
+  let mut i = 0;
+  while i < scales.len() {
+    let (scale, shift) = scales[i];
+    let ticks = ((CPU_FREQUENCY >> shift) + hz / 2) / hz;
+    if ticks == 0 {
+      return None;
+    }
+    if ticks <= 0x1_0000 {
+      return Some((scale, (0x1_0000 - ticks) as u16));
+    }
+    i += 1;
+  }
+  None
+}
+
+/// Calls `handler` from a timer's overflow interrupt, `hz` times per second.
+///
+/// This sets the timer's scale and reload (see [`timer_settings_for`]), sets
+/// `handler` as the timer's handler with
+/// [`set_irq_handler`](crate::interrupts::set_irq_handler), and enables the
+/// timer's bit in `IE`. For the handler to actually be called,
+/// [`irq_dispatcher`](crate::interrupts::irq_dispatcher) must be set as the
+/// [`RUST_IRQ_HANDLER`](crate::RUST_IRQ_HANDLER), and `IME` must be on.
+///
+/// To stop, disable the timer (eg:
+/// `TIMER1_CONTROL.write(TimerControl::new())`).
+///
+/// ## Panics
+/// * If `timer` is 4 or more.
+/// * If `hz` can't be reached by a timer (see [`timer_settings_for`]).
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn on_frequency(timer: usize, hz: u32, handler: IrqFn) {
+  let (scale, reload) =
+    timer_settings_for(hz).expect("frequency out of range for a timer");
+  let ie = IE.read();
+  let (reload_reg, control_reg, bit, ie) = match timer {
+    0 => (TIMER0_RELOAD, TIMER0_CONTROL, IrqBits::TIMER0, ie.with_timer0(true)),
+    1 => (TIMER1_RELOAD, TIMER1_CONTROL, IrqBits::TIMER1, ie.with_timer1(true)),
+    2 => (TIMER2_RELOAD, TIMER2_CONTROL, IrqBits::TIMER2, ie.with_timer2(true)),
+    3 => (TIMER3_RELOAD, TIMER3_CONTROL, IrqBits::TIMER3, ie.with_timer3(true)),
+    _ => panic!("timer index out of range"),
+  };
+  control_reg.write(TimerControl::new());
+  set_irq_handler(bit, Some(handler));
+  IE.write(ie);
+  reload_reg.write(reload);
+  control_reg.write(
+    TimerControl::new()
+      .with_scale(scale)
+      .with_overflow_irq(true)
+      .with_enabled(true),
+  );
+}
+
+/// A problem found by [`sample_clock_settings`] or [`sample_clock`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SampleClockError {
+  /// The timer index wasn't 0 or 1, the only timers that can drive the sound
+  /// FIFOs.
+  TimerOutOfRange {
+    /// The timer index given.
+    timer: usize,
+  },
+  /// The sample rate was 0, faster than the CPU, or too slow for even the
+  /// largest timer scale.
+  RateOutOfRange {
+    /// The sample rate given.
+    sample_rate_hz: u32,
+  },
+}
+
+/// Picks the timer scale and reload value for a sample rate.
+///
+/// This is [`timer_settings_for`] with an error for rates it can't reach, so
+/// the cycles per sample are rounded the same way. Sample rates of 256 Hz and
+/// up always use [`TimerScale::_1`], and then the reload is the same as
+/// [`sample_timer_reload`](crate::sound::sample_timer_reload).
+///
+/// ```
+/// # use gba::timers::*;
+/// // 16,777,216 / 16,384 is exactly 1024 cycles per sample.
+/// assert_eq!(
+///   sample_clock_settings(16384),
+///   Ok((TimerScale::_1, 1024_u16.wrapping_neg()))
+/// );
+/// assert_eq!(
+///   sample_clock_settings(0),
+///   Err(SampleClockError::RateOutOfRange { sample_rate_hz: 0 })
+/// );
+/// ```
+#[inline]
+pub const fn sample_clock_settings(
+  sample_rate_hz: u32,
+) -> Result<(TimerScale, u16), SampleClockError> {
+  match timer_settings_for(sample_rate_hz) {
+    Some(settings) => Ok(settings),
+    None => Err(SampleClockError::RateOutOfRange { sample_rate_hz }),
+  }
+}
+
+/// Sets a timer to overflow once per sample, for driving DMA sound.
+///
+/// The timer's scale and reload are set from [`sample_clock_settings`], and
+/// then the timer is enabled (without an overflow interrupt). Point the sound
+/// FIFO at the same timer with
+/// [`SoundMix::with_sound_a_timer`](crate::sound::SoundMix::with_sound_a_timer)
+/// or
+/// [`SoundMix::with_sound_b_timer`](crate::sound::SoundMix::with_sound_b_timer)
+/// (`false` for timer 0, `true` for timer 1).
+///
+/// ## Failures
+/// * If `timer` isn't 0 or 1.
+/// * If the sample rate can't be reached by a timer.
+///
+/// In either case the timer isn't changed.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn sample_clock(
+  timer: usize, sample_rate_hz: u32,
+) -> Result<(), SampleClockError> {
+  let (reload_reg, control_reg) = match timer {
+    0 => (TIMER0_RELOAD, TIMER0_CONTROL),
+    1 => (TIMER1_RELOAD, TIMER1_CONTROL),
+    _ => return Err(SampleClockError::TimerOutOfRange { timer }),
+  };
+  let (scale, reload) = sample_clock_settings(sample_rate_hz)?;
+  control_reg.write(TimerControl::new());
+  reload_reg.write(reload);
+  control_reg.write(TimerControl::new().with_scale(scale).with_enabled(true));
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::sound::sample_timer_reload;
+
+  #[test]
+  fn sample_clock_matches_sound_reload() {
+    for rate in [
+      256, 5_734, 10_512, 13_379, 16_384, 18_157, 22_050, 31_536, 32_768,
+      44_100,
+    ] {
+      assert_eq!(
+        sample_clock_settings(rate),
+        Ok((TimerScale::_1, sample_timer_reload(rate))),
+        "{rate} Hz"
+      );
+    }
+    // 16,777,216 / 22,050 is 760.87 cycles, which rounds up.
+    assert_eq!(sample_timer_reload(22_050), 761_u16.wrapping_neg());
+  }
+
+  #[test]
+  fn sample_clock_matches_timer_settings() {
+    for rate in [1, 60, 100, 255, 1_000, 22_050] {
+      assert_eq!(sample_clock_settings(rate).ok(), timer_settings_for(rate));
+    }
+  }
+
+  #[test]
+  fn fixed_step_carries_remainder() {
+    let mut step = FixedStep::new(30);
+    // a frame is a bit over 1/60th of a second
+    assert_eq!(step.update(), 0);
+    assert_eq!(step.update(), 1);
+    let total: u32 = (2..60).map(|_| step.update()).sum();
+    assert_eq!(total, 29);
+    // half a second, twice, with nothing lost along the way
+    let mut step = FixedStep::new(3);
+    assert_eq!(step.advance(CPU_FREQUENCY / 2), 1);
+    assert_eq!(step.advance(CPU_FREQUENCY / 2), 2);
+    assert_eq!(step.advance(CPU_FREQUENCY * 2), 6);
+  }
+
+  #[test]
+  fn timer_settings_for_picks_smallest_scale() {
+    let ticks = |(scale, reload): (TimerScale, u16)| {
+      let shift = match scale {
+        TimerScale::_1 => 0,
+        TimerScale::_64 => 6,
+        TimerScale::_256 => 8,
+        TimerScale::_1024 => 10,
+      };
+      (0x1_0000 - u32::from(reload)) << shift
+    };
+    // 256 Hz is exactly 65,536 cycles, which still fits the 1 scale
+    assert_eq!(timer_settings_for(256), Some((TimerScale::_1, 0)));
+    assert_eq!(timer_settings_for(255).unwrap().0, TimerScale::_64);
+    // 60 Hz is 279,620 cycles, 4369 ticks at the 64 scale
+    assert_eq!(
+      timer_settings_for(60),
+      Some((TimerScale::_64, 4369_u16.wrapping_neg()))
+    );
+    for hz in [1, 60, 440, 1_000, 32_768] {
+      let cycles = ticks(timer_settings_for(hz).unwrap());
+      let actual = CPU_FREQUENCY / cycles;
+      assert!(actual.abs_diff(hz) <= hz / 100, "{hz} Hz");
+    }
+    // one cycle per overflow is the highest frequency there is
+    assert_eq!(
+      timer_settings_for(CPU_FREQUENCY),
+      Some((TimerScale::_1, 0xFFFF))
+    );
+    assert_eq!(timer_settings_for(CPU_FREQUENCY * 2 + 1), None);
+  }
+}
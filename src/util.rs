@@ -0,0 +1,653 @@
+//! Small, general purpose helpers for game logic.
+//!
+//! Nothing in here touches the hardware, these are just common bits of math
+//! and bookkeeping that many GBA games end up writing for themselves.
+//!
+//! ## Positions
+//!
+//! The motion helpers keep positions in `i16fx8` (or [`Vec2<i16, 8>`]), the
+//! same format as the affine parameters. That only reaches about 127 whole
+//! pixels either side of 0, so give positions relative to something nearby,
+//! such as the camera or the point an effect starts from, and add that back
+//! on as a whole number of pixels when placing things on screen.
+
+use crate::fixed::{fx8_bits, fx8_from_bits, i16fx8, Vec2};
+
+/// A point that smoothly follows a target, such as a camera following the
+/// player.
+///
+/// Each call to [`follow`](SmoothFollow::follow) moves the point part of the
+/// way toward the target:
+/// `current += (target - current) * stiffness`. A stiffness of 1.0 snaps
+/// directly to the target, and smaller values give a longer lag.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SmoothFollow {
+  /// The current position.
+  pub current: Vec2<i16, 8>,
+}
+impl SmoothFollow {
+  /// Starts following from the given position.
+  #[inline]
+  #[must_use]
+  pub const fn new(current: Vec2<i16, 8>) -> Self {
+    Self { current }
+  }
+
+  /// Moves the current position toward `target`.
+  ///
+  /// Usually you'd call this once per frame. The `stiffness` should be in the
+  /// range `0.0 ..= 1.0`.
+  #[inline]
+  pub fn follow(&mut self, target: Vec2<i16, 8>, stiffness: i16fx8) {
+    let stiffness = i64::from(fx8_bits(stiffness));
+    let step = |current: i16, target: i16| {
+      let offset = i64::from(target) - i64::from(current);
+      let moved = i64::from(current) + mul_fx8(stiffness, offset);
+      moved.clamp(i16::MIN.into(), i16::MAX.into()) as i16
+    };
+    self.current = Vec2::from_bits(
+      step(self.current.x.to_bits(), target.x.to_bits()),
+      step(self.current.y.to_bits(), target.y.to_bits()),
+    );
+  }
+
+  /// The current position, rounded to the nearest whole pixel.
+  ///
+  /// Add this to the scroll of whatever the position is relative to, and
+  /// write that to a background's scroll registers.
+  #[inline]
+  #[must_use]
+  pub fn scroll(&self) -> (i16, i16) {
+    let round = |bits: i16| ((i32::from(bits) + (1 << 7)) >> 8) as i16;
+    (round(self.current.x.to_bits()), round(self.current.y.to_bits()))
+  }
+}
+
+/// A damped spring that pulls a value toward a target, for bouncy motion
+/// such as menu cursors or a camera that overshoots a little.
+///
+/// Each [`update`](Spring::update) is one step of semi-implicit Euler
+/// integration, with a time step of one frame:
+/// * `velocity += stiffness * (target - position) - damping * velocity`
+/// * `position += velocity`
+///
+/// Everything is in `i16fx8`, and the coefficients have to be kept in range
+/// for the spring to settle down:
+/// * `damping` must be between 0.0 and 2.0, and `stiffness` must be between 0.0
+///   and `4.0 - 2.0 * damping`. Outside of that the motion grows every frame
+///   instead of dying out.
+/// * The spring overshoots and wobbles less as `damping` goes up compared to
+///   `stiffness`. With a `damping` of 0.5, a `stiffness` of 0.08 or less
+///   doesn't overshoot at all.
+/// * Good starting points are a stiffness around 0.1 and a damping around 0.5.
+///
+/// The math is rounded to 1/256 of a pixel each frame, so the position can
+/// come to rest a tiny bit short of the target. The position and velocity
+/// stop at the edges of the `i16fx8` range rather than wrapping around.
+///
+/// ```
+/// # use gba::prelude::*;
+/// let mut spring = Spring::new(i16fx8::from_bits(26), i16fx8::from_bits(128));
+/// let target = i16fx8::from_bits(100 << 8);
+/// let mut highest = 0;
+/// for _ in 0..120 {
+///   highest = highest.max(spring.update(target).to_bits());
+/// }
+/// // it overshoots, but only by a little
+/// assert!(highest > 100 << 8 && highest < 101 << 8);
+/// // and it settles at the target
+/// assert!((spring.position().to_bits() - (100 << 8)).abs() < 8);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spring {
+  stiffness: i32,
+  damping: i32,
+  position: i32,
+  velocity: i32,
+}
+impl Spring {
+  /// Makes a spring at rest at position 0.
+  #[inline]
+  #[must_use]
+  pub const fn new(stiffness: i16fx8, damping: i16fx8) -> Self {
+    Self {
+      stiffness: fx8_bits(stiffness),
+      damping: fx8_bits(damping),
+      position: 0,
+      velocity: 0,
+    }
+  }
+
+  /// The current position.
+  #[inline]
+  #[must_use]
+  pub const fn position(&self) -> i16fx8 {
+    fx8_from_bits(self.position)
+  }
+
+  /// The current velocity, per frame.
+  #[inline]
+  #[must_use]
+  pub const fn velocity(&self) -> i16fx8 {
+    fx8_from_bits(self.velocity)
+  }
+
+  /// Moves the spring to a position and stops it there.
+  #[inline]
+  pub fn set_position(&mut self, position: i16fx8) {
+    self.position = fx8_bits(position);
+    self.velocity = 0;
+  }
+
+  /// Advances the spring one frame toward `target`, returning the new
+  /// position.
+  #[inline]
+  pub fn update(&mut self, target: i16fx8) -> i16fx8 {
+    let clamp = |v: i64| v.clamp(i16::MIN.into(), i16::MAX.into()) as i32;
+    let offset = i64::from(fx8_bits(target)) - i64::from(self.position);
+    let accel = mul_fx8(i64::from(self.stiffness), offset)
+      - mul_fx8(i64::from(self.damping), i64::from(self.velocity));
+    self.velocity = clamp(i64::from(self.velocity) + accel);
+    self.position = clamp(i64::from(self.position) + i64::from(self.velocity));
+    self.position()
+  }
+}
+
+/// Multiplies two fx8 values given as bits, rounding to nearest.
+#[inline]
+const fn mul_fx8(a: i64, b: i64) -> i64 {
+  (a * b + (1 << 7)) >> 8
+}
+
+/// A fixed capacity set of countdown timers, measured in frames.
+///
+/// Schedule an id to fire some number of frames from now, and then call
+/// [`poll`](TimerWheel::poll) once per frame (eg: after each vblank) to get
+/// the ids of all timers that have expired.
+///
+/// * A timer scheduled for `n` frames expires during the `n`th poll after it
+///   was scheduled. A timer scheduled for 0 frames expires on the next poll,
+///   the same as one scheduled for 1 frame.
+/// * Up to `N` timers can be pending at once.
+///
+/// ```
+/// # use gba::util::TimerWheel;
+/// let mut timers: TimerWheel<u8, 4> = TimerWheel::new();
+/// timers.schedule(2, 7).unwrap();
+/// assert_eq!(timers.poll().next(), None);
+/// assert_eq!(timers.poll().next(), Some(7));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TimerWheel<T: Copy, const N: usize> {
+  now: u32,
+  slots: [Option<(u32, T)>; N],
+}
+impl<T: Copy, const N: usize> TimerWheel<T, N> {
+  /// Makes a new timer wheel with no timers pending.
+  #[inline]
+  #[must_use]
+  pub const fn new() -> Self {
+    Self { now: 0, slots: [None; N] }
+  }
+
+  /// The number of polls that have happened.
+  #[inline]
+  #[must_use]
+  pub const fn now(&self) -> u32 {
+    self.now
+  }
+
+  /// The number of pending timers.
+  #[inline]
+  #[must_use]
+  pub fn len(&self) -> usize {
+    self.slots.iter().filter(|s| s.is_some()).count()
+  }
+
+  /// If there are no pending timers.
+  #[inline]
+  #[must_use]
+  pub fn is_empty(&self) -> bool {
+    self.slots.iter().all(|s| s.is_none())
+  }
+
+  /// Schedules `id` to expire after `frames` more polls.
+  ///
+  /// ## Failure
+  /// If all `N` timers are already pending, the id is given back as an error.
+  #[inline]
+  pub fn schedule(&mut self, frames: u32, id: T) -> Result<(), T> {
+    let deadline = self.now.wrapping_add(frames);
+    match self.slots.iter_mut().find(|s| s.is_none()) {
+      Some(slot) => {
+        *slot = Some((deadline, id));
+        Ok(())
+      }
+      None => Err(id),
+    }
+  }
+
+  /// Removes all pending timers for `id`.
+  #[inline]
+  pub fn cancel(&mut self, id: T)
+  where
+    T: PartialEq,
+  {
+    for slot in self.slots.iter_mut() {
+      if matches!(slot, Some((_, t)) if *t == id) {
+        *slot = None;
+      }
+    }
+  }
+
+  /// Advances time by one frame, and then iterates the ids of all timers
+  /// that have expired.
+  ///
+  /// Expired timers are removed as they're iterated. If you drop the iterator
+  /// early, any remaining expired timers will be returned by the next poll.
+  #[inline]
+  pub fn poll(&mut self) -> impl Iterator<Item = T> + '_ {
+    self.now = self.now.wrapping_add(1);
+    let now = self.now;
+    self.slots.iter_mut().filter_map(move |slot| match *slot {
+      // the deadline is "at or before now", allowing for wrap around.
+      Some((deadline, id)) if (now.wrapping_sub(deadline) as i32) >= 0 => {
+        *slot = None;
+        Some(id)
+      }
+      _ => None,
+    })
+  }
+}
+impl<T: Copy, const N: usize> Default for TimerWheel<T, N> {
+  #[inline]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// One particle of a [`Particles`] system, with everything stored as fx8
+/// bits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Particle {
+  x: i16,
+  y: i16,
+  vx: i16,
+  vy: i16,
+  life: u16,
+}
+
+/// A fixed capacity particle system, for effects such as sparks and dust.
+///
+/// Each particle has a position, a velocity, and a number of frames left to
+/// live. Call [`update`](Particles::update) once per frame, and then use
+/// [`for_each_alive`](Particles::for_each_alive) to place an object for each
+/// particle.
+///
+/// Positions and velocities (in pixels per frame) are [`Vec2<i16, 8>`], and
+/// gravity is `i16fx8`. A position that goes past the edge of the range wraps
+/// around, so particles that can travel that far should be given a short
+/// enough life.
+///
+/// ```
+/// # use gba::prelude::*;
+/// let mut particles: Particles<4> = Particles::new();
+/// let pos = Vec2::from_bits(10 << 8, 20 << 8);
+/// let vel = Vec2::from_bits(1 << 8, 0);
+/// assert!(particles.spawn(pos, vel, 2));
+/// // half a pixel per frame of gravity
+/// particles.update(i16fx8::from_bits(128));
+/// let mut seen = None;
+/// particles.for_each_alive(|p| seen = Some((p.x.to_bits(), p.y.to_bits())));
+/// assert_eq!(seen, Some((11 << 8, (20 << 8) + 128)));
+/// particles.update(i16fx8::from_bits(128));
+/// assert_eq!(particles.alive(), 0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Particles<const N: usize> {
+  slots: [Particle; N],
+}
+impl<const N: usize> Particles<N> {
+  /// Makes a system with no particles alive.
+  #[inline]
+  #[must_use]
+  pub const fn new() -> Self {
+    Self { slots: [Particle { x: 0, y: 0, vx: 0, vy: 0, life: 0 }; N] }
+  }
+
+  /// The number of particles alive.
+  #[inline]
+  #[must_use]
+  pub fn alive(&self) -> usize {
+    self.slots.iter().filter(|p| p.life > 0).count()
+  }
+
+  /// Adds a particle that lives for `life` updates.
+  ///
+  /// Returns `false` (and adds nothing) if all `N` particles are already
+  /// alive, or if `life` is 0.
+  #[inline]
+  pub fn spawn(
+    &mut self, pos: Vec2<i16, 8>, vel: Vec2<i16, 8>, life: u16,
+  ) -> bool {
+    if life == 0 {
+      return false;
+    }
+    match self.slots.iter_mut().find(|p| p.life == 0) {
+      Some(slot) => {
+        *slot = Particle {
+          x: pos.x.to_bits(),
+          y: pos.y.to_bits(),
+          vx: vel.x.to_bits(),
+          vy: vel.y.to_bits(),
+          life,
+        };
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Advances every particle by one frame.
+  ///
+  /// Gravity is added to the y velocity, then the velocity is added to the
+  /// position, and then the particle ages by one frame. A particle that runs
+  /// out of life is removed.
+  #[inline]
+  pub fn update(&mut self, gravity: i16fx8) {
+    let gravity = fx8_bits(gravity);
+    for p in self.slots.iter_mut().filter(|p| p.life > 0) {
+      p.vy = (i32::from(p.vy) + gravity).clamp(i16::MIN.into(), i16::MAX.into())
+        as i16;
+      p.x = p.x.wrapping_add(p.vx);
+      p.y = p.y.wrapping_add(p.vy);
+      p.life -= 1;
+    }
+  }
+
+  /// Calls `f` with the position of each particle that's alive.
+  #[inline]
+  pub fn for_each_alive(&self, mut f: impl FnMut(Vec2<i16, 8>)) {
+    for p in self.slots.iter().filter(|p| p.life > 0) {
+      f(Vec2::from_bits(p.x, p.y));
+    }
+  }
+
+  /// Removes all particles.
+  #[inline]
+  pub fn clear(&mut self) {
+    for p in self.slots.iter_mut() {
+      p.life = 0;
+    }
+  }
+}
+impl<const N: usize> Default for Particles<N> {
+  #[inline]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// A cubic Bézier curve, such as a path for an object to follow during a
+/// cutscene.
+///
+/// The curve starts at `p0` heading toward `p1`, and ends at `p3` coming from
+/// the direction of `p2`. Points are `(x, y)` pairs.
+///
+/// [`eval`](CubicBezier::eval) uses de Casteljau's algorithm: three rounds of
+/// linear interpolation, each rounded to the nearest 1/256. Each round can be
+/// off by up to half of 1/256, so a point on the curve is always within 2/256
+/// of a pixel of the exact value, and the endpoints are exact. Since an
+/// `i16fx8` only reaches 127 whole pixels, it's usually best to give the
+/// points relative to where the path starts.
+///
+/// ```
+/// # use gba::prelude::*;
+/// let fx = |v: i32| i16fx8::from_bits((v << 8) as _);
+/// let p = |x, y| (fx(x), fx(y));
+/// let curve = CubicBezier::new(p(0, 0), p(0, 100), p(100, 100), p(100, 0));
+/// assert_eq!(curve.eval(fx(0)), p(0, 0));
+/// assert_eq!(curve.eval(fx(1)), p(100, 0));
+/// assert_eq!(curve.eval(i16fx8::from_bits(128)), p(50, 75));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CubicBezier {
+  /// The start point.
+  pub p0: (i16fx8, i16fx8),
+  /// The first control point.
+  pub p1: (i16fx8, i16fx8),
+  /// The second control point.
+  pub p2: (i16fx8, i16fx8),
+  /// The end point.
+  pub p3: (i16fx8, i16fx8),
+}
+impl CubicBezier {
+  /// Makes a curve from its start point, two control points, and end point.
+  #[inline]
+  #[must_use]
+  pub const fn new(
+    p0: (i16fx8, i16fx8), p1: (i16fx8, i16fx8), p2: (i16fx8, i16fx8),
+    p3: (i16fx8, i16fx8),
+  ) -> Self {
+    Self { p0, p1, p2, p3 }
+  }
+
+  /// The point that's `t` of the way along the curve.
+  ///
+  /// `t` is clamped to `0.0 ..= 1.0`, where 0 is the start and 1 is the end.
+  /// Equal steps of `t` aren't quite equal distances along the curve, the
+  /// point moves slower where the curve bends more sharply.
+  #[inline]
+  #[must_use]
+  pub const fn eval(&self, t: i16fx8) -> (i16fx8, i16fx8) {
+    let t = fx8_bits(t);
+    let t = if t < 0 {
+      0
+    } else if t > 1 << 8 {
+      1 << 8
+    } else {
+      t
+    };
+    (
+      bezier_axis(
+        fx8_bits(self.p0.0),
+        fx8_bits(self.p1.0),
+        fx8_bits(self.p2.0),
+        fx8_bits(self.p3.0),
+        t,
+      ),
+      bezier_axis(
+        fx8_bits(self.p0.1),
+        fx8_bits(self.p1.1),
+        fx8_bits(self.p2.1),
+        fx8_bits(self.p3.1),
+        t,
+      ),
+    )
+  }
+}
+
+/// Linearly interpolates fx8 bits from `a` to `b` by `t` (fx8 bits, in
+/// `0..=256`), rounding to nearest.
+#[inline]
+const fn lerp_fx8(a: i32, b: i32, t: i32) -> i32 {
+  a + (((b - a) * t + (1 << 7)) >> 8)
+}
+
+/// One axis of [`CubicBezier::eval`], with everything as fx8 bits.
+#[inline]
+const fn bezier_axis(p0: i32, p1: i32, p2: i32, p3: i32, t: i32) -> i16fx8 {
+  let a = lerp_fx8(p0, p1, t);
+  let b = lerp_fx8(p1, p2, t);
+  let c = lerp_fx8(p2, p3, t);
+  let ab = lerp_fx8(a, b, t);
+  let bc = lerp_fx8(b, c, t);
+  fx8_from_bits(lerp_fx8(ab, bc, t))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Whole pixels as `i16fx8`.
+  fn px(v: i32) -> i16fx8 {
+    fx8_from_bits(v << 8)
+  }
+
+  #[test]
+  fn smooth_follow_converges() {
+    let at = |x: i16, y: i16| Vec2::<i16, 8>::from_bits(x << 8, y << 8);
+    let mut cam = SmoothFollow::new(at(0, 120));
+    let stiffness = fx8_from_bits(64);
+    let mut last_dist = i32::MAX;
+    for _ in 0..60 {
+      cam.follow(at(100, -50), stiffness);
+      let dx = (100 << 8) - i32::from(cam.current.x.to_bits());
+      let dy = i32::from(cam.current.y.to_bits()) + (50 << 8);
+      assert!(dx >= 0 && dy >= 0, "overshot the target");
+      assert!(dx + dy <= last_dist, "moved away from the target");
+      last_dist = dx + dy;
+    }
+    assert_eq!(cam.scroll(), (100, -50));
+    // a stiffness of 1.0 snaps directly to the target
+    let mut cam = SmoothFollow::new(at(0, 0));
+    cam.follow(at(-30, 40), fx8_from_bits(1 << 8));
+    assert_eq!(cam, SmoothFollow::new(at(-30, 40)));
+  }
+
+  #[test]
+  fn timer_wheel_expires_on_the_right_frame() {
+    let mut timers: TimerWheel<u8, 4> = TimerWheel::new();
+    timers.schedule(3, 1).unwrap();
+    timers.schedule(1, 2).unwrap();
+    timers.schedule(3, 3).unwrap();
+    timers.schedule(5, 4).unwrap();
+    assert_eq!(timers.schedule(2, 5), Err(5));
+    let mut expired = [[0_u8; 2]; 6];
+    for frame in expired.iter_mut() {
+      for (out, id) in frame.iter_mut().zip(timers.poll()) {
+        *out = id;
+      }
+    }
+    assert_eq!(expired, [[2, 0], [0, 0], [1, 3], [0, 0], [4, 0], [0, 0]]);
+    assert!(timers.is_empty());
+    assert_eq!(timers.now(), 6);
+  }
+
+  #[test]
+  fn timer_wheel_wraps_and_cancels() {
+    let mut timers: TimerWheel<u8, 2> =
+      TimerWheel { now: u32::MAX - 1, slots: [None; 2] };
+    timers.schedule(3, 1).unwrap();
+    timers.schedule(2, 2).unwrap();
+    timers.cancel(2);
+    assert_eq!(timers.len(), 1);
+    assert_eq!(timers.poll().next(), None);
+    assert_eq!(timers.poll().next(), None);
+    assert_eq!(timers.poll().next(), Some(1));
+    assert_eq!(timers.now(), 1);
+  }
+
+  #[test]
+  fn spring_settles_without_growing() {
+    for (stiffness, damping) in [(26, 128), (64, 64), (128, 192), (200, 256)] {
+      let mut spring =
+        Spring::new(fx8_from_bits(stiffness), fx8_from_bits(damping));
+      spring.set_position(px(-40));
+      // each swing past the target is no bigger than the one before it, apart
+      // from a wobble of a few bits from rounding once it's settled
+      let mut last_peak = i32::MAX;
+      let mut peak = 0_i32;
+      for _ in 0..300 {
+        let offset = fx8_bits(spring.update(px(40))) - (40 << 8);
+        assert!(offset.abs() <= 80 << 8, "{stiffness}, {damping}");
+        if offset.signum() == peak.signum() || peak == 0 {
+          peak = if offset.abs() > peak.abs() { offset } else { peak };
+        } else {
+          assert!(peak.abs() <= last_peak.max(8), "{stiffness}, {damping}");
+          last_peak = peak.abs();
+          peak = offset;
+        }
+      }
+      let rest = fx8_bits(spring.position()) - (40 << 8);
+      assert!(rest.abs() < 8, "{stiffness}, {damping}: {rest}");
+    }
+    // a soft enough spring never overshoots at all
+    let mut spring = Spring::new(fx8_from_bits(20), fx8_from_bits(128));
+    for _ in 0..300 {
+      assert!(fx8_bits(spring.update(px(100))) <= 100 << 8);
+    }
+  }
+
+  #[test]
+  fn particles_integrate_and_age_out() {
+    let mut particles: Particles<3> = Particles::new();
+    let at = |x: i16, y: i16| Vec2::<i16, 8>::from_bits(x << 8, y << 8);
+    let vel = Vec2::from_bits(-384, -512);
+    assert!(particles.spawn(at(100, 50), vel, 10));
+    assert!(particles.spawn(at(0, 0), vel, 3));
+    assert!(!particles.spawn(at(0, 0), vel, 0));
+    assert_eq!(particles.alive(), 2);
+    let gravity = fx8_from_bits(64);
+    for n in 1..=10 {
+      particles.update(gravity);
+      assert_eq!(
+        particles.alive(),
+        if n < 3 {
+          2
+        } else if n < 10 {
+          1
+        } else {
+          0
+        }
+      );
+      if n == 5 {
+        // semi-implicit Euler: x0 + n*vx, and y0 + n*vy + g*n*(n+1)/2
+        let mut pos = None;
+        particles
+          .for_each_alive(|p| pos = Some((p.x.to_bits(), p.y.to_bits())));
+        let x = (100 << 8) - 5 * 384;
+        let y = (50 << 8) - 5 * 512 + 64 * 5 * 6 / 2;
+        assert_eq!(pos, Some((x, y)));
+      }
+    }
+    // dead particles free their slots up for reuse
+    for _ in 0..3 {
+      assert!(particles.spawn(at(0, 0), vel, 1));
+    }
+    assert!(!particles.spawn(at(0, 0), vel, 1));
+    particles.clear();
+    assert_eq!(particles.alive(), 0);
+  }
+
+  #[test]
+  fn bezier_endpoints_and_precision() {
+    let p = |x: i32, y: i32| (fx8_from_bits(x << 8), fx8_from_bits(y << 8));
+    let bits = |(x, y): (i16fx8, i16fx8)| (fx8_bits(x), fx8_bits(y));
+    let curve = CubicBezier::new(p(0, 0), p(0, 100), p(100, 100), p(100, -30));
+    assert_eq!(curve.eval(fx8_from_bits(0)), curve.p0);
+    assert_eq!(curve.eval(fx8_from_bits(256)), curve.p3);
+    // out of range `t` is clamped to the ends
+    assert_eq!(curve.eval(fx8_from_bits(-50)), curve.p0);
+    assert_eq!(curve.eval(fx8_from_bits(300)), curve.p3);
+    // the midpoint is (p0 + 3*p1 + 3*p2 + p3) / 8
+    assert_eq!(bits(curve.eval(fx8_from_bits(128))), (50 << 8, 0x4740));
+    // every step is within 2/256 of the exact curve
+    let ctrl = [curve.p0, curve.p1, curve.p2, curve.p3].map(bits);
+    for t in 0..=256 {
+      let (x, y) = bits(curve.eval(fx8_from_bits(t)));
+      let t = f64::from(t) / 256.0;
+      let w = [
+        (1.0 - t).powi(3),
+        3.0 * t * (1.0 - t).powi(2),
+        3.0 * t * t * (1.0 - t),
+        t.powi(3),
+      ];
+      let exact = |axis: fn((i32, i32)) -> i32| {
+        (0..4).map(|i| w[i] * f64::from(axis(ctrl[i]))).sum::<f64>()
+      };
+      assert!((f64::from(x) - exact(|c| c.0)).abs() <= 2.0, "x at {t}");
+      assert!((f64::from(y) - exact(|c| c.1)).abs() <= 2.0, "y at {t}");
+    }
+  }
+}
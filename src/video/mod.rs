@@ -97,6 +97,7 @@
 //! sort your object entries so that any lower priority objects are also the
 //! lower index objects.
 
+use bitfrob::u8x2;
 use bytemuck::{Pod, TransparentWrapper, Zeroable};
 
 #[allow(unused_imports)]
@@ -105,7 +106,7 @@ use crate::{
   macros::{
     pub_const_fn_new_zeroed, u16_bool_field, u16_enum_field, u16_int_field,
   },
-  mem::{copy_u32x8_unchecked, set_u32x80_unchecked},
+  mem::{copy_u32x8, set_u32x80, MemoryRegion},
 };
 
 pub mod obj;
@@ -137,12 +138,378 @@ impl Color {
   pub const fn from_rgb(r: u16, g: u16, b: u16) -> Self {
     Self(r & 0b11111 | (g & 0b11111) << 5 | (b & 0b11111) << 10)
   }
+
+  /// Blends this color toward `other`, channel by channel.
+  ///
+  /// `weight_of_other` is in sixteenths and is clamped to `0..=16`: 0 gives
+  /// `self`, 16 gives `other`, and anything between is a mix (rounded to
+  /// nearest). The math is done in `u16`, so it can't overflow. The unused top
+  /// bit of the result is always 0.
+  ///
+  /// ```
+  /// # use gba::video::Color;
+  /// const SUNSET: Color = Color::RED.blend(Color::YELLOW, 4);
+  /// assert_eq!(SUNSET, Color::from_rgb(31, 8, 0));
+  /// assert_eq!(Color::RED.blend(Color::BLUE, 0), Color::RED);
+  /// assert_eq!(Color::RED.blend(Color::BLUE, 200), Color::BLUE);
+  /// ```
+  #[inline]
+  #[must_use]
+  pub const fn blend(self, other: Color, weight_of_other: u8) -> Color {
+    let t = if weight_of_other > 16 { 16 } else { weight_of_other as u16 };
+    Color::from_rgb(
+      blend_channel(self.red(), other.red(), t),
+      blend_channel(self.green(), other.green(), t),
+      blend_channel(self.blue(), other.blue(), t),
+    )
+  }
+
+  /// The color halfway between this color and `other`.
+  ///
+  /// This is `self.blend(other, 8)`, so channels that are an odd distance
+  /// apart round up.
+  ///
+  /// ```
+  /// # use gba::video::Color;
+  /// let mid = Color::BLACK.lerp(Color::from_rgb(31, 20, 3));
+  /// assert_eq!(mid, Color::from_rgb(16, 10, 2));
+  /// ```
+  #[inline]
+  #[must_use]
+  pub const fn lerp(self, other: Color) -> Color {
+    self.blend(other, 8)
+  }
+
+  /// Makes a color from 8-bit channels, such as a `#RRGGBB` hex code from an
+  /// art program.
+  ///
+  /// Each channel is scaled to 5 bits, rounded to nearest.
+  ///
+  /// ```
+  /// # use gba::video::Color;
+  /// assert_eq!(Color::from_rgb_888(0xFF, 0xFF, 0xFF), Color::WHITE);
+  /// assert_eq!(Color::from_rgb_888(0, 0, 0), Color::BLACK);
+  /// // 0x07 is 0.85 out of 31, where `0x07 >> 3` would give 0
+  /// assert_eq!(Color::from_rgb_888(0x07, 0x80, 0x04).red(), 1);
+  /// assert_eq!(Color::from_rgb_888(0x07, 0x80, 0x04).green(), 16);
+  /// assert_eq!(Color::from_rgb_888(0x07, 0x80, 0x04).blue(), 0);
+  /// ```
+  #[inline]
+  #[must_use]
+  pub const fn from_rgb_888(r: u8, g: u8, b: u8) -> Self {
+    Self::from_rgb(narrow_channel(r), narrow_channel(g), narrow_channel(b))
+  }
+
+  /// Gets the color as 8-bit channels.
+  ///
+  /// Each 5-bit channel is widened by repeating its top bits in the new low
+  /// bits, so 0 becomes 0 and 31 becomes 255.
+  ///
+  /// ```
+  /// # use gba::video::Color;
+  /// assert_eq!(Color::WHITE.to_rgb_888(), (255, 255, 255));
+  /// assert_eq!(Color::from_rgb(16, 1, 0).to_rgb_888(), (132, 8, 0));
+  /// // converting back gives the same color
+  /// let c = Color::from_rgb(7, 19, 30);
+  /// let (r, g, b) = c.to_rgb_888();
+  /// assert_eq!(Color::from_rgb_888(r, g, b), c);
+  /// ```
+  #[inline]
+  #[must_use]
+  pub const fn to_rgb_888(self) -> (u8, u8, u8) {
+    (
+      widen_channel(self.red()),
+      widen_channel(self.green()),
+      widen_channel(self.blue()),
+    )
+  }
+
+  /// Makes a color from hue, saturation, and value, using only integer math.
+  ///
+  /// * `h` is the hue, and the full `u8` range is one trip around the color
+  ///   wheel: 0 is red, about 43 is yellow, 85 is green, 128 is cyan, 171 is
+  ///   blue, and 213 is magenta. Since the value wraps, adding to a hue each
+  ///   frame cycles through the rainbow.
+  /// * `s` is the saturation: 0 is gray, 255 is full color.
+  /// * `v` is the value (brightness): 0 is black, 255 is full brightness.
+  ///
+  /// ```
+  /// # use gba::video::Color;
+  /// assert_eq!(Color::from_hsv(0, 255, 255), Color::RED);
+  /// assert_eq!(Color::from_hsv(85, 255, 255), Color::GREEN);
+  /// assert_eq!(Color::from_hsv(200, 0, 255), Color::WHITE);
+  /// assert_eq!(Color::from_hsv(200, 255, 0), Color::BLACK);
+  /// let blue = Color::from_hsv(171, 255, 255);
+  /// assert_eq!((blue.red(), blue.green(), blue.blue()), (0, 0, 31));
+  /// ```
+  #[inline]
+  #[must_use]
+  pub const fn from_hsv(h: u8, s: u8, v: u8) -> Self {
+    let (s, v) = (s as u32, v as u32);
+    if s == 0 {
+      return Self::from_rgb_888(v as u8, v as u8, v as u8);
+    }
+    // six regions of 43 hues each, with the position within the region
+    // scaled up to 0..=252.
+    let region = h / 43;
+    let rem = (h - region * 43) as u32 * 6;
+    let p = (v * (255 - s) / 255) as u8;
+    let q = (v * (255 - s * rem / 255) / 255) as u8;
+    let t = (v * (255 - s * (255 - rem) / 255) / 255) as u8;
+    let v = v as u8;
+    match region {
+      0 => Self::from_rgb_888(v, t, p),
+      1 => Self::from_rgb_888(q, v, p),
+      2 => Self::from_rgb_888(p, v, t),
+      3 => Self::from_rgb_888(p, q, v),
+      4 => Self::from_rgb_888(t, p, v),
+      _ => Self::from_rgb_888(v, p, q),
+    }
+  }
+
+  /// Scales the brightness of the color, such as for a fade to black.
+  ///
+  /// `scale` is in sixteenths and is clamped to `0..=16`: 16 keeps the color
+  /// as it is, 8 gives half brightness, and 0 gives black. Each channel is
+  /// rounded to nearest.
+  ///
+  /// ```
+  /// # use gba::video::Color;
+  /// let c = Color::from_rgb(31, 20, 3);
+  /// assert_eq!(c.with_value_scaled(16), c);
+  /// assert_eq!(c.with_value_scaled(8), Color::from_rgb(16, 10, 2));
+  /// assert_eq!(c.with_value_scaled(0), Color::BLACK);
+  /// ```
+  #[inline]
+  #[must_use]
+  pub const fn with_value_scaled(self, scale: u8) -> Self {
+    let scale = if scale > 16 { 16 } else { scale };
+    Color::BLACK.blend(self, scale)
+  }
+
+  /// The perceived brightness of the color, in the range `0..=31`.
+  ///
+  /// This weights the channels by 77/256 red, 150/256 green, and 29/256 blue
+  /// (close to the usual 0.30, 0.59, 0.11), rounded to nearest.
+  ///
+  /// ```
+  /// # use gba::video::Color;
+  /// assert_eq!(Color::WHITE.luminance(), 31);
+  /// assert_eq!(Color::BLACK.luminance(), 0);
+  /// assert_eq!(Color::RED.luminance(), 9);
+  /// assert_eq!(Color::GREEN.luminance(), 18);
+  /// assert_eq!(Color::BLUE.luminance(), 4);
+  /// ```
+  #[inline]
+  #[must_use]
+  pub const fn luminance(self) -> u8 {
+    let r = self.red() as u32;
+    let g = self.green() as u32;
+    let b = self.blue() as u32;
+    ((77 * r + 150 * g + 29 * b + 128) >> 8) as u8
+  }
+
+  /// Converts the color to a gray with the same
+  /// [`luminance`](Color::luminance).
+  ///
+  /// ```
+  /// # use gba::video::Color;
+  /// assert_eq!(Color::WHITE.to_grayscale(), Color::WHITE);
+  /// assert_eq!(Color::GREEN.to_grayscale(), Color::from_rgb(18, 18, 18));
+  /// ```
+  #[inline]
+  #[must_use]
+  pub const fn to_grayscale(self) -> Self {
+    let l = self.luminance() as u16;
+    Self::from_rgb(l, l, l)
+  }
+}
+
+/// One channel of [`Color::blend`], with `t` already clamped to `0..=16`.
+#[inline]
+const fn blend_channel(x: u16, y: u16, t: u16) -> u16 {
+  (x * (16 - t) + y * t + 8) / 16
+}
+
+/// Scales an 8-bit channel to 5 bits, rounded to nearest.
+#[inline]
+const fn narrow_channel(c: u8) -> u16 {
+  (c as u16 * 31 + 127) / 255
+}
+
+/// Scales a 5-bit channel to 8 bits by repeating the top bits.
+#[inline]
+const fn widen_channel(c: u16) -> u8 {
+  ((c << 3) | (c >> 2)) as u8
 }
 
 unsafe impl Zeroable for Color {}
 unsafe impl Pod for Color {}
 unsafe impl TransparentWrapper<u16> for Color {}
 
+/// A general purpose 16 color palette, for getting something on screen
+/// before you have art of your own.
+///
+/// These are the colors of the PICO-8 fantasy console's palette, which is
+/// designed to work well together. As `#RRGGBB` codes (which are rounded to
+/// the GBA's 5 bits per channel), in index order:
+///
+/// | Index | Color | Hex | Index | Color | Hex |
+/// |:-:|:-|:-|:-:|:-|:-|
+/// | 0 | black | `#000000` | 8 | red | `#FF004D` |
+/// | 1 | dark blue | `#1D2B53` | 9 | orange | `#FFA300` |
+/// | 2 | dark purple | `#7E2553` | 10 | yellow | `#FFEC27` |
+/// | 3 | dark green | `#008751` | 11 | green | `#00E436` |
+/// | 4 | brown | `#AB5236` | 12 | blue | `#29ADFF` |
+/// | 5 | dark gray | `#5F574F` | 13 | lavender | `#83769C` |
+/// | 6 | light gray | `#C2C3C7` | 14 | pink | `#FF77A8` |
+/// | 7 | white | `#FFF1E8` | 15 | peach | `#FFCCAA` |
+///
+/// Remember that in tiles and objects, index 0 is transparent.
+pub const DEFAULT_PALETTE16: [Color; 16] = [
+  Color::from_rgb_888(0x00, 0x00, 0x00),
+  Color::from_rgb_888(0x1D, 0x2B, 0x53),
+  Color::from_rgb_888(0x7E, 0x25, 0x53),
+  Color::from_rgb_888(0x00, 0x87, 0x51),
+  Color::from_rgb_888(0xAB, 0x52, 0x36),
+  Color::from_rgb_888(0x5F, 0x57, 0x4F),
+  Color::from_rgb_888(0xC2, 0xC3, 0xC7),
+  Color::from_rgb_888(0xFF, 0xF1, 0xE8),
+  Color::from_rgb_888(0xFF, 0x00, 0x4D),
+  Color::from_rgb_888(0xFF, 0xA3, 0x00),
+  Color::from_rgb_888(0xFF, 0xEC, 0x27),
+  Color::from_rgb_888(0x00, 0xE4, 0x36),
+  Color::from_rgb_888(0x29, 0xAD, 0xFF),
+  Color::from_rgb_888(0x83, 0x76, 0x9C),
+  Color::from_rgb_888(0xFF, 0x77, 0xA8),
+  Color::from_rgb_888(0xFF, 0xCC, 0xAA),
+];
+
+/// 16 evenly spaced grays, from black at index 0 to white at index 15.
+///
+/// Gray `i` has all three channels at `i * 31 / 15` (rounded to nearest).
+///
+/// ```
+/// # use gba::video::*;
+/// assert_eq!(DEFAULT_GRAYSCALE16[0], Color::BLACK);
+/// assert_eq!(DEFAULT_GRAYSCALE16[15], Color::WHITE);
+/// for pair in DEFAULT_GRAYSCALE16.windows(2) {
+///   assert!(pair[0].red() < pair[1].red());
+///   assert_eq!(pair[1].red(), pair[1].green());
+///   assert_eq!(pair[1].red(), pair[1].blue());
+/// }
+/// ```
+pub const DEFAULT_GRAYSCALE16: [Color; 16] = {
+  let mut out = [Color::BLACK; 16];
+  let mut i = 0;
+  while i < 16 {
+    let v = (i as u16 * 31 + 7) / 15;
+    out[i] = Color::from_rgb(v, v, v);
+    i += 1;
+  }
+  out
+};
+
+/// Makes a darkened copy of a palette, such as for reflections in water.
+///
+/// Each red, green, and blue channel of each color is shifted right by
+/// `shift` bits, so the darkening factor is `1 / 2**shift`: 1 gives half
+/// brightness, 2 gives a quarter, and so on. A `shift` of 0 copies the colors
+/// unchanged, and 5 or more gives black.
+///
+/// Colors are paired up by index. If one slice is longer than the other, the
+/// extra elements are ignored (and any extra `out` colors are left as they
+/// were).
+///
+/// ```
+/// # use gba::video::{reflection_palette, Color};
+/// let base = [Color::from_rgb(31, 16, 4), Color::WHITE];
+/// let mut out = [Color::BLACK; 2];
+/// reflection_palette(&base, &mut out, 1);
+/// assert_eq!(out, [Color::from_rgb(15, 8, 2), Color::from_rgb(15, 15, 15)]);
+/// ```
+#[inline]
+pub fn reflection_palette(base: &[Color], out: &mut [Color], shift: u8) {
+  let shift = u32::from(shift.min(5));
+  for (o, c) in out.iter_mut().zip(base.iter()) {
+    *o =
+      Color::from_rgb(c.red() >> shift, c.green() >> shift, c.blue() >> shift);
+  }
+}
+
+/// Copies all 256 background palette entries into `out`.
+///
+/// This is a single DMA transfer (see [`fast_copy`](crate::mem::fast_copy)),
+/// so it's quick enough to snapshot the palette before an effect (such as a
+/// fade or a flash) and then restore it afterward with
+/// [`write_bg_palette`].
+///
+/// ```no_run
+/// # use gba::prelude::*;
+/// let mut saved = [Color::BLACK; 256];
+/// read_bg_palette(&mut saved);
+/// BG_PALETTE.index(1).write(Color::WHITE); // flash!
+/// write_bg_palette(&saved);
+/// let mut check = [Color::BLACK; 256];
+/// read_bg_palette(&mut check);
+/// assert_eq!(saved, check);
+/// ```
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn read_bg_palette(out: &mut [Color; 256]) {
+  // Safety: both regions are 512 bytes, and the copy is out of PALRAM.
+  unsafe {
+    crate::mem::fast_copy(
+      out.as_mut_ptr().cast(),
+      BG_PALETTE.as_usize() as *const u8,
+      512,
+    )
+  };
+}
+
+/// Sets all 256 background palette entries, see [`read_bg_palette`].
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn write_bg_palette(colors: &[Color; 256]) {
+  // Safety: both regions are 512 bytes, and the copy is into PALRAM.
+  unsafe {
+    crate::mem::fast_copy(
+      BG_PALETTE.as_usize() as *mut u8,
+      colors.as_ptr().cast(),
+      512,
+    )
+  };
+}
+
+/// Copies all 256 object palette entries into `out`, see
+/// [`read_bg_palette`].
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn read_obj_palette(out: &mut [Color; 256]) {
+  // Safety: both regions are 512 bytes, and the copy is out of PALRAM.
+  unsafe {
+    crate::mem::fast_copy(
+      out.as_mut_ptr().cast(),
+      OBJ_PALETTE.as_usize() as *const u8,
+      512,
+    )
+  };
+}
+
+/// Sets all 256 object palette entries, see [`read_bg_palette`].
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn write_obj_palette(colors: &[Color; 256]) {
+  // Safety: both regions are 512 bytes, and the copy is into PALRAM.
+  unsafe {
+    crate::mem::fast_copy(
+      OBJ_PALETTE.as_usize() as *mut u8,
+      colors.as_ptr().cast(),
+      512,
+    )
+  };
+}
+
 /// The video mode controls how each background layer will operate.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(u16)]
@@ -197,18 +564,259 @@ impl DisplayStatus {
   u16_int_field!(8 - 15, vcount_setting, with_vcount_setting);
 }
 
+/// The display state saved by [`loading_screen`].
+///
+/// Pass this to [`end_loading`] to put the display back how it was.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[must_use]
+pub struct LoadingScreen {
+  display_control: DisplayControl,
+  backdrop: Color,
+}
+impl LoadingScreen {
+  /// The `DISPCNT` value from before the loading screen started.
+  #[inline]
+  #[must_use]
+  pub const fn saved_display_control(&self) -> DisplayControl {
+    self.display_control
+  }
+}
+
+/// The `DISPCNT` value to use during a loading screen.
+///
+/// * Without a backdrop, this is `current` with forced blank turned on. The
+///   screen shows white, and all of VRAM, PALRAM, and OAM can be accessed at
+///   full speed at any time.
+/// * With a backdrop, forced blank is left off (it would hide the backdrop) and
+///   instead every layer and window is hidden, so only the backdrop color
+///   shows. VRAM and PALRAM accesses are then slower outside of blanking, just
+///   like normal.
+///
+/// ```
+/// # use gba::video::*;
+/// let current = DisplayControl::new().with_show_bg0(true).with_show_obj(true);
+/// let white = loading_display_control(current, false);
+/// assert!(white.forced_blank());
+/// assert!(white.show_bg0());
+/// let backdrop = loading_display_control(current, true);
+/// assert!(!backdrop.forced_blank());
+/// assert!(!backdrop.show_bg0() && !backdrop.show_obj());
+/// ```
+#[inline]
+#[must_use]
+pub const fn loading_display_control(
+  current: DisplayControl, backdrop: bool,
+) -> DisplayControl {
+  if backdrop {
+    // Keep the mode and frame settings, drop every layer and window bit.
+    DisplayControl(current.0 & 0x7F)
+  } else {
+    current.with_forced_blank(true)
+  }
+}
+
+/// Starts a loading screen, for long operations that write a lot of VRAM.
+///
+/// With `None` the display is put into forced blank (a white screen). With a
+/// backdrop color, the screen instead shows just that color. See
+/// [`loading_display_control`] for the details.
+///
+/// Unlike a guard value, this can be started in one function and ended in
+/// another, which suits a load that spans several steps. Call
+/// [`end_loading`] with the returned value when the load is done.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn loading_screen(backdrop: Option<Color>) -> LoadingScreen {
+  let display_control = DISPCNT.read();
+  let saved =
+    LoadingScreen { display_control, backdrop: BACKDROP_COLOR.read() };
+  if let Some(color) = backdrop {
+    BACKDROP_COLOR.write(color);
+  }
+  DISPCNT.write(loading_display_control(display_control, backdrop.is_some()));
+  saved
+}
+
+/// Ends a loading screen, restoring `DISPCNT` and the backdrop color.
+///
+/// If the load changed the display setup you want to show next, write that
+/// `DISPCNT` value after this call (or instead of it).
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn end_loading(saved: LoadingScreen) {
+  BACKDROP_COLOR.write(saved.backdrop);
+  DISPCNT.write(saved.display_control);
+}
+
+/// Where the display is within the current frame, from [`display_timing`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct DisplayTiming {
+  /// If the display is in vertical blank (lines 160 to 226).
+  pub in_vblank: bool,
+  /// If the display is in horizontal blank.
+  pub in_hblank: bool,
+  /// If the current line matches the `vcount_setting` of `DISPSTAT`.
+  pub vcount_match: bool,
+  /// The line being drawn (`0..=227`).
+  pub current_line: u16,
+}
+impl DisplayTiming {
+  /// Decodes a `DISPSTAT` value and a `VCOUNT` value.
+  ///
+  /// ```
+  /// # use gba::video::{DisplayStatus, DisplayTiming};
+  /// let stat = DisplayStatus::new().with_currently_vblank(true);
+  /// let timing = DisplayTiming::decode(stat, 170);
+  /// assert!(timing.in_vblank);
+  /// assert!(!timing.in_hblank);
+  /// assert!(!timing.vcount_match);
+  /// assert_eq!(timing.current_line, 170);
+  /// ```
+  #[inline]
+  #[must_use]
+  pub const fn decode(dispstat: DisplayStatus, vcount: u16) -> Self {
+    Self {
+      in_vblank: dispstat.currently_vblank(),
+      in_hblank: dispstat.currently_hblank(),
+      vcount_match: dispstat.currently_vcount(),
+      current_line: vcount & 0xFF,
+    }
+  }
+}
+
+/// Reads the current [`DisplayTiming`].
+///
+/// `DISPSTAT` and `VCOUNT` are next to each other, so this reads both of them
+/// with a single 32-bit load, and the values are always from the same moment.
+/// This is handy for logging where in the frame something happened.
+#[inline]
+#[must_use]
+#[cfg(feature = "on_gba")]
+pub fn display_timing() -> DisplayTiming {
+  // Safety: this is the address of `DISPSTAT`, with `VCOUNT` in the upper half.
+  let both = unsafe { VolAddress::<u32, Safe, ()>::new(0x0400_0004) }.read();
+  DisplayTiming::decode(DisplayStatus(both as u16), (both >> 16) as u16)
+}
+
+/// The drawing priority of a background or object.
+///
+/// Things with a higher priority are drawn over top of things with a lower
+/// priority, see the [module docs](self#priority). In the hardware this is a
+/// 2-bit number where *lower* numbers are drawn on top, so `Highest` is 0 and
+/// `Lowest` is 3.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u16)]
+pub enum Priority {
+  /// Priority 0, drawn over everything else.
+  #[default]
+  Highest = 0,
+  /// Priority 1.
+  High = 1,
+  /// Priority 2.
+  Low = 2,
+  /// Priority 3, drawn under everything else.
+  Lowest = 3,
+}
+impl Priority {
+  /// Converts a raw 2-bit priority number, ignoring any higher bits.
+  ///
+  /// ```
+  /// # use gba::video::Priority;
+  /// assert_eq!(Priority::from_bits(0), Priority::Highest);
+  /// assert_eq!(Priority::from_bits(3), Priority::Lowest);
+  /// assert_eq!(Priority::from_bits(5), Priority::High);
+  /// ```
+  #[inline]
+  #[must_use]
+  pub const fn from_bits(bits: u16) -> Self {
+    match bits & 0b11 {
+      0 => Self::Highest,
+      1 => Self::High,
+      2 => Self::Low,
+      _ => Self::Lowest,
+    }
+  }
+}
+
+/// The size of a text mode background, in pixels.
+///
+/// Each 256x256 area uses one screenblock, so the larger sizes use 2 or 4
+/// screenblocks in a row, starting with the background's screenblock.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u16)]
+pub enum TextBgSize {
+  /// 32x32 tiles, one screenblock.
+  #[default]
+  Size256x256 = 0 << 14,
+  /// 64x32 tiles, two screenblocks (left then right).
+  Size512x256 = 1 << 14,
+  /// 32x64 tiles, two screenblocks (top then bottom).
+  Size256x512 = 2 << 14,
+  /// 64x64 tiles, four screenblocks (top left, top right, bottom left, bottom
+  /// right).
+  Size512x512 = 3 << 14,
+}
+
+/// The size of an affine background, in pixels.
+///
+/// Affine backgrounds are always square, and use one byte per tile.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u16)]
+pub enum AffineBgSize {
+  /// 16x16 tiles, 256 bytes.
+  #[default]
+  Size128x128 = 0 << 14,
+  /// 32x32 tiles, 1,024 bytes.
+  Size256x256 = 1 << 14,
+  /// 64x64 tiles, 4,096 bytes.
+  Size512x512 = 2 << 14,
+  /// 128x128 tiles, 16,384 bytes.
+  Size1024x1024 = 3 << 14,
+}
+
+/// Background control bits.
+///
+/// The `size` field means something different for text and affine
+/// backgrounds, so it's best set with either
+/// [`with_text_size`](BackgroundControl::with_text_size) or
+/// [`with_affine_size`](BackgroundControl::with_affine_size).
+///
+/// ```
+/// # use gba::video::*;
+/// let text = BackgroundControl::new().with_text_size(TextBgSize::Size512x256);
+/// assert_eq!(text.size(), 1);
+/// let affine =
+///   BackgroundControl::new().with_affine_size(AffineBgSize::Size1024x1024);
+/// assert_eq!(affine.size(), 3);
+/// assert_eq!(affine.affine_size(), AffineBgSize::Size1024x1024);
+/// ```
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(transparent)]
 pub struct BackgroundControl(u16);
 impl BackgroundControl {
   pub_const_fn_new_zeroed!();
-  u16_int_field!(0 - 1, priority, with_priority);
+  #[inline]
+  #[must_use]
+  #[allow(missing_docs)]
+  pub const fn priority(self) -> u16 {
+    bitfrob::u16_get_value(0, 1, self.0)
+  }
+  #[inline]
+  #[must_use]
+  #[allow(missing_docs)]
+  #[deprecated = "use `with_priority_level`, which can't be out of range"]
+  pub const fn with_priority(self, val: u16) -> Self {
+    Self(bitfrob::u16_with_value(0, 1, self.0, val))
+  }
+  u16_enum_field!(0 - 1: Priority, priority_level, with_priority_level);
   u16_int_field!(2 - 3, charblock, with_charblock);
   u16_bool_field!(6, mosaic, with_mosaic);
   u16_bool_field!(7, bpp8, with_bpp8);
   u16_int_field!(8 - 12, screenblock, with_screenblock);
   u16_bool_field!(13, is_affine_wrapping, with_is_affine_wrapping);
   u16_int_field!(14 - 15, size, with_size);
+  u16_enum_field!(14 - 15: TextBgSize, text_size, with_text_size);
+  u16_enum_field!(14 - 15: AffineBgSize, affine_size, with_affine_size);
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -258,6 +866,50 @@ impl Mosaic {
   u16_int_field!(4 - 7, bg_v_extra, with_bg_v_extra);
   u16_int_field!(8 - 11, obj_h_extra, with_obj_h_extra);
   u16_int_field!(12 - 15, obj_v_extra, with_obj_v_extra);
+
+  /// Sets the background mosaic block size, in pixels.
+  ///
+  /// Unlike [`with_bg_h_extra`](Self::with_bg_h_extra) and
+  /// [`with_bg_v_extra`](Self::with_bg_v_extra), this takes the actual size of
+  /// the blocks, so a 2x2 mosaic is `with_bg_size(2, 2)`, and `(1, 1)` is no
+  /// mosaic at all.
+  ///
+  /// ```
+  /// # use gba::video::Mosaic;
+  /// let m = Mosaic::new().with_bg_size(2, 16).with_obj_size(1, 4);
+  /// assert_eq!((m.bg_h_extra(), m.bg_v_extra()), (1, 15));
+  /// assert_eq!((m.obj_h_extra(), m.obj_v_extra()), (0, 3));
+  /// ```
+  ///
+  /// ## Panics
+  /// * With `debug_assertions` enabled, if either size isn't in `1..=16`.
+  #[inline]
+  #[must_use]
+  #[cfg_attr(feature = "track_caller", track_caller)]
+  pub const fn with_bg_size(self, w: u8, h: u8) -> Self {
+    debug_assert!(w >= 1 && w <= 16, "mosaic width must be 1..=16");
+    debug_assert!(h >= 1 && h <= 16, "mosaic height must be 1..=16");
+    self
+      .with_bg_h_extra(w.wrapping_sub(1) as u16)
+      .with_bg_v_extra(h.wrapping_sub(1) as u16)
+  }
+
+  /// Sets the object mosaic block size, in pixels.
+  ///
+  /// This works like [`with_bg_size`](Self::with_bg_size), but for objects.
+  ///
+  /// ## Panics
+  /// * With `debug_assertions` enabled, if either size isn't in `1..=16`.
+  #[inline]
+  #[must_use]
+  #[cfg_attr(feature = "track_caller", track_caller)]
+  pub const fn with_obj_size(self, w: u8, h: u8) -> Self {
+    debug_assert!(w >= 1 && w <= 16, "mosaic width must be 1..=16");
+    debug_assert!(h >= 1 && h <= 16, "mosaic height must be 1..=16");
+    self
+      .with_obj_h_extra(w.wrapping_sub(1) as u16)
+      .with_obj_v_extra(h.wrapping_sub(1) as u16)
+  }
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -290,95 +942,4011 @@ impl BlendControl {
   u16_bool_field!(13, target2_backdrop, with_target2_backdrop);
 }
 
-/// Data for a 4-bit-per-pixel tile.
-pub type Tile4 = [u32; 8];
+/// Packs alpha blend coefficients into the form used by [`BLDALPHA`].
+///
+/// Each coefficient is in 1/16ths and is clamped to `0..=16`. The EVA
+/// coefficient (target 1) goes in the low byte, and EVB (target 2) goes in the
+/// high byte.
+#[inline]
+#[must_use]
+pub fn blend_alpha(eva: u8, evb: u8) -> u8x2 {
+  u8x2::default().with_low(eva.min(16)).with_high(evb.min(16))
+}
 
-/// Data for an 8-bit-per-pixel tile.
-pub type Tile8 = [u32; 16];
+/// Clamps a brightness blend coefficient to the `0..=16` used by [`BLDY`].
+#[inline]
+#[must_use]
+pub const fn blend_brightness(evy: u8) -> u8 {
+  if evy > 16 {
+    16
+  } else {
+    evy
+  }
+}
 
-/// An entry within a tile mode tilemap.
+/// Sets the alpha blend coefficients, clamping each to `0..=16`.
 ///
-/// * `tile` is the index of the tile, offset from the `charblock` that the
-///   background is using. This is a 10-bit value, so indexes are in the range
-///   `0..=1023`. You *cannot* index past the end of background VRAM into object
-///   VRAM (it just won't draw properly), but you *can* index past the end of
-///   one charblock into the next charblock.
-/// * `hflip` If you want the tile horizontally flipped.
-/// * `vflip` If you want the tile vertically flipped.
-/// * `palbank` sets the palbank for this tile. If the background is in 8bpp
-///   mode this has no effect.
+/// Values above 16 don't give a stronger effect, the hardware just treats
+/// them as 16 (at best), so this does the clamping up front.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn set_blend_alpha(eva: u8, evb: u8) {
+  BLDALPHA.write(blend_alpha(eva, evb));
+}
+
+/// Sets the brightness blend coefficient, clamping it to `0..=16`.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn set_blend_brightness(evy: u8) {
+  BLDY.write(blend_brightness(evy));
+}
+
+/// Sets the alpha blend coefficients for target 1 (`eva`) and target 2
+/// (`evb`), like [`set_blend_alpha`] but with coefficients that are already
+/// known to be in range.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn set_blend_alpha_coefficients(
+  eva: BlendCoefficient, evb: BlendCoefficient,
+) {
+  BLDALPHA.write(u8x2::default().with_low(eva.get()).with_high(evb.get()));
+}
+
+/// Sets the brightness blend coefficient, like [`set_blend_brightness`] but
+/// with a coefficient that's already known to be in range.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn set_blend_brightness_coefficient(evy: BlendCoefficient) {
+  BLDY.write(evy.get());
+}
+
+/// A blend coefficient, in 1/16ths, that's always in `0..=16`.
+///
+/// This can be used with [`set_blend_alpha_coefficients`] and
+/// [`set_blend_brightness_coefficient`] when a coefficient is kept around,
+/// such as the current step of a fade, so that it's known to be in range.
+///
+/// ```
+/// # use gba::video::BlendCoefficient;
+/// assert_eq!(BlendCoefficient::new(20), BlendCoefficient::FULL);
+/// assert_eq!(BlendCoefficient::try_new(17), None);
+/// assert_eq!(BlendCoefficient::try_new(8), Some(BlendCoefficient::HALF));
+/// let c = BlendCoefficient::new(15);
+/// assert_eq!(c.saturating_add(3).get(), 16);
+/// assert_eq!(c.saturating_sub(20).get(), 0);
+/// assert_eq!(u8::from(c), 15);
+/// ```
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(transparent)]
-pub struct TextEntry(u16);
-impl TextEntry {
-  pub_const_fn_new_zeroed!();
-  u16_int_field!(0 - 9, tile, with_tile);
-  u16_bool_field!(10, hflip, with_hflip);
-  u16_bool_field!(11, vflip, with_vflip);
-  u16_int_field!(12 - 15, palbank, with_palbank);
+pub struct BlendCoefficient(u8);
+impl BlendCoefficient {
+  /// No amount of the layer (or no change in brightness).
+  pub const ZERO: Self = Self(0);
+  /// Half of the layer.
+  pub const HALF: Self = Self(8);
+  /// All of the layer (or fully white/black for brightness).
+  pub const FULL: Self = Self(16);
 
-  /// Shorthand for `TextEntry::new().with_tile(id)`
+  /// Makes a coefficient, clamping it to `0..=16`.
   #[inline]
   #[must_use]
-  pub const fn from_tile(id: u16) -> Self {
-    Self(id & 0b11_1111_1111)
+  pub const fn new(sixteenths: u8) -> Self {
+    Self(if sixteenths > 16 { 16 } else { sixteenths })
   }
 
-  /// Unwrap this value into its raw `u16` form.
+  /// Makes a coefficient, or `None` if it's more than 16.
   #[inline]
   #[must_use]
-  pub const fn to_u16(self) -> u16 {
+  pub const fn try_new(sixteenths: u8) -> Option<Self> {
+    if sixteenths > 16 {
+      None
+    } else {
+      Some(Self(sixteenths))
+    }
+  }
+
+  /// The coefficient, in 1/16ths.
+  #[inline]
+  #[must_use]
+  pub const fn get(self) -> u8 {
     self.0
   }
-}
 
-#[inline]
-#[cfg(feature = "on_gba")]
-pub fn video3_clear_to(c: Color) {
-  let u = u32::from(c.0) << 16 | u32::from(c.0);
-  unsafe {
-    let p = VIDEO3_VRAM.as_usize() as *mut _;
-    set_u32x80_unchecked(p, u, 240_usize);
+  /// Adds to the coefficient, stopping at 16.
+  #[inline]
+  #[must_use]
+  pub const fn saturating_add(self, sixteenths: u8) -> Self {
+    Self::new(self.0.saturating_add(sixteenths))
   }
-}
 
-#[repr(C, align(4))]
-pub struct Video3Bitmap(pub [Color; 240 * 160]);
-impl Video3Bitmap {
-  /// Wraps an array of raw color bit data as a Video Mode 3 bitmap.
-  ///
-  /// This is intended for generating static values at compile time. You should
-  /// not attempt to call this function at runtime, because the argument to the
-  /// function is larger than the GBA's stack space.
+  /// Subtracts from the coefficient, stopping at 0.
   #[inline]
   #[must_use]
-  pub const fn new_from_u16(bits: [u16; 240 * 160]) -> Self {
-    Self(unsafe { core::mem::transmute(bits) })
+  pub const fn saturating_sub(self, sixteenths: u8) -> Self {
+    Self(self.0.saturating_sub(sixteenths))
+  }
+}
+impl From<BlendCoefficient> for u8 {
+  #[inline]
+  fn from(c: BlendCoefficient) -> Self {
+    c.0
   }
 }
 
+/// A layer that can take part in color blending, see [`blend_layers`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u16)]
+pub enum BlendTarget {
+  /// Background 0.
+  Bg0 = 0,
+  /// Background 1.
+  Bg1 = 1,
+  /// Background 2.
+  Bg2 = 2,
+  /// Background 3.
+  Bg3 = 3,
+  /// Objects.
+  Obj = 4,
+  /// The backdrop color.
+  Backdrop = 5,
+}
+impl BlendTarget {
+  /// The bit for this layer within the first target bits of
+  /// [`BlendControl`]. Shift it up by 8 for the second target bits.
+  #[inline]
+  #[must_use]
+  pub const fn bit(self) -> u16 {
+    1 << (self as u16)
+  }
+}
+
+/// The [`BlendControl`] value for alpha blending `front` over `back`.
+///
+/// `front` is the first target and `back` is the second target. No other
+/// layers are targets.
+///
+/// ```
+/// # use gba::video::*;
+/// let cnt = blend_layers_control(BlendTarget::Bg1, BlendTarget::Bg2);
+/// assert_eq!(cnt.mode(), ColorEffectMode::AlphaBlend);
+/// assert!(cnt.target1_bg1() && cnt.target2_bg2());
+/// assert!(!cnt.target1_bg2() && !cnt.target2_bg1());
+/// ```
+#[inline]
+#[must_use]
+pub const fn blend_layers_control(
+  front: BlendTarget, back: BlendTarget,
+) -> BlendControl {
+  BlendControl(front.bit() | (back.bit() << 8))
+    .with_mode(ColorEffectMode::AlphaBlend)
+}
+
+/// Alpha blends the `front` layer over the `back` layer.
+///
+/// This sets [`BLDCNT`] to [`blend_layers_control`] and sets the coefficients
+/// like [`set_blend_alpha`]. The front layer is multiplied by `eva` sixteenths
+/// and the back layer by `evb` sixteenths, so 8 and 8 gives a 50% mix.
+///
+/// Blending only happens where the front layer is drawn directly on top of
+/// the back layer. Objects can also be blended by setting them to
+/// semi-transparent mode, regardless of the first target bits.
 #[inline]
 #[cfg(feature = "on_gba")]
-pub fn video3_set_bitmap(bitmap: &Video3Bitmap) {
-  let p = VIDEO3_VRAM.as_usize() as *mut _;
+pub fn blend_layers(front: BlendTarget, back: BlendTarget, eva: u8, evb: u8) {
+  BLDCNT.write(blend_layers_control(front, back));
+  set_blend_alpha(eva, evb);
+}
+
+/// Fills a table of per-scanline horizontal offsets that follow a sine wave.
+///
+/// Scrolling each scanline by a slightly different amount makes a background
+/// ripple, like water or heat shimmer. Entry `y` is
+/// `amplitude * sin(phase + y / wavelength)` (rounded, with the angle in the
+/// same units as [`sin`] and `y / wavelength` being whole turns), so the wave
+/// repeats every `wavelength` scanlines. Negative offsets wrap around, which
+/// is what the offset registers expect.
+///
+/// Advance `phase` a little each frame to make the wave move, and use
+/// [`hblank_dma0_u16`](crate::dma::hblank_dma0_u16) to apply the table to a
+/// background's `BGxHOFS` register. If the background also has a normal
+/// scroll, add it to each entry.
+///
+/// ## Panics
+/// * If `wavelength` is 0.
+#[inline]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn sine_scroll_table(
+  amplitude: i16, wavelength: u16, phase: u16, out: &mut [u16; 160],
+) {
+  assert!(wavelength > 0);
+  for (y, entry) in out.iter_mut().enumerate() {
+    let turn = ((y as u32) << 16) / u32::from(wavelength);
+    let angle = phase.wrapping_add(turn as u16);
+    let s = i32::from(sin(angle).to_bits());
+    *entry = ((i32::from(amplitude) * s + (1 << 13)) >> 14) as u16;
+  }
+}
+
+/// Data for a 4-bit-per-pixel tile.
+pub type Tile4 = [u32; 8];
+
+/// Data for an 8-bit-per-pixel tile.
+pub type Tile8 = [u32; 16];
+
+/// Removes duplicate tiles, remapping a tilemap to use the unique tiles.
+///
+/// * `tiles` are the tiles that the `map` indexes refer to.
+/// * `unique` is filled with each different tile that `map` uses, in the order
+///   they're first used.
+/// * Each index in `map` is changed to the matching index within `unique`.
+///
+/// Tiles are only merged if they're exactly the same (flipped copies aren't
+/// detected), and tiles that `map` never uses aren't kept at all. This does a
+/// simple search for every map entry, so it's best used for preparing data
+/// once (eg: when a level loads) rather than every frame.
+///
+/// Returns the number of unique tiles. If there are more than fit in `unique`,
+/// `None` is returned and `map` isn't changed.
+///
+/// ```
+/// # use gba::video::{dedup_tiles, Tile4};
+/// let tiles: [Tile4; 4] = [[0; 8], [1; 8], [0; 8], [2; 8]];
+/// let mut map = [0, 1, 2, 1, 2, 0];
+/// let mut unique = [[0; 8]; 4];
+/// assert_eq!(dedup_tiles(&tiles, &mut unique, &mut map), Some(2));
+/// assert_eq!(map, [0, 1, 0, 1, 0, 0]);
+/// assert_eq!(&unique[..2], &[[0; 8], [1; 8]]);
+/// ```
+///
+/// ## Panics
+/// * If an index in `map` is out of bounds for `tiles`.
+#[inline]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn dedup_tiles(
+  tiles: &[Tile4], unique: &mut [Tile4], map: &mut [u16],
+) -> Option<usize> {
+  let mut count = 0;
+  for index in map.iter() {
+    let tile = &tiles[usize::from(*index)];
+    if !unique[..count].contains(tile) {
+      *unique.get_mut(count)? = *tile;
+      count += 1;
+    }
+  }
+  for index in map.iter_mut() {
+    let tile = &tiles[usize::from(*index)];
+    // every tile the map uses was just added, so this always finds one.
+    let pos = unique[..count].iter().position(|u| u == tile).unwrap_or(0);
+    *index = pos as u16;
+  }
+  Some(count)
+}
+
+/// Expands one tile of 1bpp data into a [`Tile4`].
+///
+/// * `src` is 8 bytes, one per row of the tile from top to bottom. Within each
+///   byte the lowest bit is the leftmost pixel. This is the same bit order that
+///   the BIOS [`BitUnPack`](crate::bios::BitUnPack) function uses, and that the
+///   [builtin art](crate::builtin_art) is stored in.
+/// * Set bits become `fg_index` pixels and clear bits become `bg_index` pixels.
+///   Only the low 4 bits of each index are used.
+///
+/// ## Panics
+/// * If `src` is less than 8 bytes long.
+#[inline]
+#[must_use]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub const fn unpack_1bpp_to_tile4(
+  src: &[u8], fg_index: u8, bg_index: u8,
+) -> Tile4 {
+  let fg = (fg_index & 0xF) as u32;
+  let bg = (bg_index & 0xF) as u32;
+  let mut tile = [0_u32; 8];
+  let mut y = 0;
+  while y < 8 {
+    let bits = src[y];
+    let mut row = 0_u32;
+    let mut x = 0;
+    while x < 8 {
+      let index = if bits & (1 << x) != 0 { fg } else { bg };
+      row |= index << (4 * x);
+      x += 1;
+    }
+    tile[y] = row;
+    y += 1;
+  }
+  tile
+}
+
+/// An entry within a tile mode tilemap.
+///
+/// * `tile` is the index of the tile, offset from the `charblock` that the
+///   background is using. This is a 10-bit value, so indexes are in the range
+///   `0..=1023`. You *cannot* index past the end of background VRAM into object
+///   VRAM (it just won't draw properly), but you *can* index past the end of
+///   one charblock into the next charblock.
+/// * `hflip` If you want the tile horizontally flipped.
+/// * `vflip` If you want the tile vertically flipped.
+/// * `palbank` sets the palbank for this tile. If the background is in 8bpp
+///   mode this has no effect.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct TextEntry(u16);
+impl TextEntry {
+  pub_const_fn_new_zeroed!();
+  u16_int_field!(0 - 9, tile, with_tile);
+  u16_bool_field!(10, hflip, with_hflip);
+  u16_bool_field!(11, vflip, with_vflip);
+  u16_int_field!(12 - 15, palbank, with_palbank);
+
+  /// Shorthand for `TextEntry::new().with_tile(id)`
+  #[inline]
+  #[must_use]
+  pub const fn from_tile(id: u16) -> Self {
+    Self(id & 0b11_1111_1111)
+  }
+
+  /// Unwrap this value into its raw `u16` form.
+  #[inline]
+  #[must_use]
+  pub const fn to_u16(self) -> u16 {
+    self.0
+  }
+}
+
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn video3_clear_to(c: Color) {
+  let u = u32::from(c.0) << 16 | u32::from(c.0);
   unsafe {
-    copy_u32x8_unchecked(p, bitmap as *const _ as *const _, 2400_usize)
-  };
+    let p = VIDEO3_VRAM.as_usize() as *mut _;
+    set_u32x80(p, u, 240_usize);
+  }
 }
 
 #[repr(C, align(4))]
-pub struct Video4Indexmap(pub [u8; 240 * 160]);
+pub struct Video3Bitmap(pub [Color; 240 * 160]);
+impl Video3Bitmap {
+  /// Wraps an array of raw color bit data as a Video Mode 3 bitmap.
+  ///
+  /// This is intended for generating static values at compile time. You should
+  /// not attempt to call this function at runtime, because the argument to the
+  /// function is larger than the GBA's stack space.
+  #[inline]
+  #[must_use]
+  pub const fn new_from_u16(bits: [u16; 240 * 160]) -> Self {
+    Self(unsafe { core::mem::transmute(bits) })
+  }
+}
 
-/// Sets the indexmap of the frame requested.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn video3_set_bitmap(bitmap: &Video3Bitmap) {
+  let p = VIDEO3_VRAM.as_usize() as *mut _;
+  unsafe { copy_u32x8(p, bitmap as *const _ as *const _, 2400_usize) };
+}
+
+/// Reads the color of a pixel of the video mode 3 bitmap.
+///
+/// Returns `None` if the position is off the edge of the screen.
+///
+/// ```no_run
+/// # use gba::prelude::*;
+/// mode3_plot(17, 99, Color::RED);
+/// assert_eq!(mode3_read(17, 99), Some(Color::RED));
+/// assert_eq!(mode3_read(240, 0), None);
+/// ```
+#[inline]
+#[must_use]
+#[cfg(feature = "on_gba")]
+pub fn mode3_read(col: usize, row: usize) -> Option<Color> {
+  VIDEO3_VRAM.get(col, row).map(|addr| addr.read())
+}
+
+/// Reads a rectangle of the video mode 3 bitmap into `out`.
+///
+/// The `w` by `h` pixels starting at (`x`, `y`) are copied in row-major
+/// order, so pixel (`x + i`, `y + j`) ends up at `out[j * w + i]`. This is
+/// mostly for tests running in an emulator, to check what a drawing routine
+/// actually put on the screen.
+///
+/// ```no_run
+/// # use gba::prelude::*;
+/// mode3_plot(10, 20, Color::RED);
+/// mode3_plot(11, 21, Color::BLUE);
+/// let mut out = [Color::BLACK; 4];
+/// dump_mode3_region(10, 20, 2, 2, &mut out);
+/// assert_eq!(out[0], Color::RED);
+/// assert_eq!(out[3], Color::BLUE);
+/// ```
 ///
 /// ## Panics
-/// Only frames 0 and 1 exist, if `frame` is 2 or more this will panic.
+/// * If the rectangle goes past the edge of the screen.
+/// * If `out` is shorter than `w * h`.
 #[inline]
 #[cfg(feature = "on_gba")]
-pub fn video4_set_indexmap(indexes: &Video4Indexmap, frame: usize) {
-  let p = VIDEO4_VRAM.get_frame(usize::from(frame)).unwrap().as_usize()
-    as *mut [u32; 8];
-  unsafe {
-    copy_u32x8_unchecked(p, indexes as *const _ as *const _, 1200_usize)
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn dump_mode3_region(
+  x: usize, y: usize, w: usize, h: usize, out: &mut [Color],
+) {
+  assert!(x + w <= 240 && y + h <= 160, "region is off the screen");
+  assert!(out.len() >= w * h, "output buffer is too small");
+  if w == 0 {
+    return;
+  }
+  for (row, line) in out.chunks_exact_mut(w).take(h).enumerate() {
+    for (col, color) in line.iter_mut().enumerate() {
+      *color = VIDEO3_VRAM.index(x + col, y + row).read();
+    }
+  }
+}
+
+/// Sets the color of a pixel of the video mode 3 bitmap.
+///
+/// Positions off the edge of the screen are ignored.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn mode3_plot(col: usize, row: usize, color: Color) {
+  if let Some(addr) = VIDEO3_VRAM.get(col, row) {
+    addr.write(color);
+  }
+}
+
+/// Calls `plot` for every pixel of a circle's outline.
+///
+/// This is the midpoint circle algorithm, giving the circle of `radius`
+/// pixels around (`cx`, `cy`). Some pixels can be given more than once, and
+/// positions aren't clipped to any screen size, so `plot` must ignore
+/// positions that are out of bounds. A radius of 0 gives just the center
+/// pixel, and a negative radius gives nothing.
+///
+/// ```
+/// # use gba::video::circle_outline;
+/// let mut grid = [[false; 5]; 5];
+/// circle_outline(2, 2, 2, |x, y| grid[y as usize][x as usize] = true);
+/// let rows = grid.map(|row| row.map(|b| if b { '#' } else { '.' }));
+/// assert_eq!(rows[0], ['.', '#', '#', '#', '.']);
+/// assert_eq!(rows[1], ['#', '.', '.', '.', '#']);
+/// assert_eq!(rows[2], ['#', '.', '.', '.', '#']);
+/// assert_eq!(rows[3], ['#', '.', '.', '.', '#']);
+/// assert_eq!(rows[4], ['.', '#', '#', '#', '.']);
+/// ```
+#[inline]
+pub fn circle_outline(
+  cx: isize, cy: isize, radius: isize, mut plot: impl FnMut(isize, isize),
+) {
+  let mut x = radius;
+  let mut y = 0;
+  let mut err = 1 - radius;
+  while x >= y {
+    plot(cx + x, cy + y);
+    plot(cx - x, cy + y);
+    plot(cx + x, cy - y);
+    plot(cx - x, cy - y);
+    plot(cx + y, cy + x);
+    plot(cx - y, cy + x);
+    plot(cx + y, cy - x);
+    plot(cx - y, cy - x);
+    y += 1;
+    if err < 0 {
+      err += 2 * y + 1;
+    } else {
+      x -= 1;
+      err += 2 * (y - x) + 1;
+    }
+  }
+}
+
+/// Calls `span` with the horizontal spans that fill a circle.
+///
+/// Each call is `span(y, x_start, x_end)`, with both ends inclusive. The spans
+/// exactly cover the inside of the matching [`circle_outline`], though some
+/// rows can be given more than once. Like with `circle_outline`, nothing is
+/// clipped.
+///
+/// ```
+/// # use gba::video::circle_spans;
+/// let mut widths = [0; 5];
+/// circle_spans(2, 2, 2, |y, x0, x1| widths[y as usize] = x1 - x0 + 1);
+/// assert_eq!(widths, [3, 5, 5, 5, 3]);
+/// ```
+#[inline]
+pub fn circle_spans(
+  cx: isize, cy: isize, radius: isize,
+  mut span: impl FnMut(isize, isize, isize),
+) {
+  let mut x = radius;
+  let mut y = 0;
+  let mut err = 1 - radius;
+  while x >= y {
+    span(cy + y, cx - x, cx + x);
+    span(cy - y, cx - x, cx + x);
+    span(cy + x, cx - y, cx + y);
+    span(cy - x, cx - y, cx + y);
+    y += 1;
+    if err < 0 {
+      err += 2 * y + 1;
+    } else {
+      x -= 1;
+      err += 2 * (y - x) + 1;
+    }
+  }
+}
+
+/// Draws the outline of a circle on the video mode 3 bitmap.
+///
+/// See [`circle_outline`] for the shape. The center can be anywhere, even off
+/// the screen, and any part of the circle that's off the screen is clipped.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn mode3_draw_circle(cx: isize, cy: isize, radius: isize, color: Color) {
+  circle_outline(cx, cy, radius, |x, y| {
+    if x >= 0 && y >= 0 {
+      mode3_plot(x as usize, y as usize, color);
+    }
+  });
+}
+
+/// Draws a filled circle on the video mode 3 bitmap.
+///
+/// See [`circle_spans`] for the shape. Like with [`mode3_draw_circle`], any
+/// part of the circle that's off the screen is clipped.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn mode3_fill_circle(cx: isize, cy: isize, radius: isize, color: Color) {
+  circle_spans(cx, cy, radius, |y, x0, x1| {
+    if !(0..160).contains(&y) || x1 < 0 || x0 >= 240 {
+      return;
+    }
+    for x in x0.max(0)..=x1.min(239) {
+      VIDEO3_VRAM.index(x as usize, y as usize).write(color);
+    }
+  });
+}
+
+/// Clips a `src_width` by `src_height` rectangle drawn with its top left at
+/// (`dest_x`, `dest_y`) to a `screen_width` by `screen_height` screen.
+///
+/// Returns the width and height of the part that's on the screen (either can
+/// be 0). Only the right and bottom edges can clip, since the position can't
+/// be negative.
+///
+/// ```
+/// # use gba::video::blit_clip;
+/// assert_eq!(blit_clip(10, 10, 32, 16, 240, 160), (32, 16));
+/// assert_eq!(blit_clip(230, 150, 32, 16, 240, 160), (10, 10));
+/// assert_eq!(blit_clip(300, 0, 32, 16, 240, 160), (0, 16));
+/// ```
+#[inline]
+#[must_use]
+pub const fn blit_clip(
+  dest_x: usize, dest_y: usize, src_width: usize, src_height: usize,
+  screen_width: usize, screen_height: usize,
+) -> (usize, usize) {
+  let w = screen_width.saturating_sub(dest_x);
+  let h = screen_height.saturating_sub(dest_y);
+  (
+    if src_width < w { src_width } else { w },
+    if src_height < h { src_height } else { h },
+  )
+}
+
+/// Copies a rectangle of pixels onto the video mode 3 bitmap.
+///
+/// `pixels` is `src_width` by `src_height` pixels in row-major order, and is
+/// drawn with its top left at (`dest_x`, `dest_y`). Anything past the right or
+/// bottom edge of the screen is clipped (see [`blit_clip`]). When `dest_x` is
+/// even, pixels are written in pairs as `u32` values, which is about twice as
+/// fast.
+///
+/// ```no_run
+/// # use gba::prelude::*;
+/// static SPLASH: [Color; 64 * 32] = [Color::RED; 64 * 32];
+/// mode3_blit(88, 64, 64, 32, &SPLASH);
+/// ```
+///
+/// ## Panics
+/// * If `pixels` is shorter than `src_width * src_height`.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn mode3_blit(
+  dest_x: usize, dest_y: usize, src_width: usize, src_height: usize,
+  pixels: &[Color],
+) {
+  assert!(pixels.len() >= src_width * src_height, "pixels is too short");
+  let (w, h) = blit_clip(dest_x, dest_y, src_width, src_height, 240, 160);
+  if w == 0 {
+    return;
+  }
+  for (row, src) in pixels.chunks_exact(src_width).take(h).enumerate() {
+    let src = &src[..w];
+    let y = dest_y + row;
+    if dest_x & 1 == 0 {
+      let addr = VIDEO3_VRAM.index(dest_x, y).as_usize();
+      let mut pairs = src.chunks_exact(2);
+      for (i, pair) in pairs.by_ref().enumerate() {
+        let word = u32::from(pair[0].0) | u32::from(pair[1].0) << 16;
+        // Safety: `dest_x` is even, so this is an aligned pair of pixels
+        // within the bitmap.
+        unsafe { VolAddress::<u32, Safe, Safe>::new(addr + i * 4) }.write(word);
+      }
+      if let [last] = pairs.remainder() {
+        VIDEO3_VRAM.index(dest_x + w - 1, y).write(*last);
+      }
+    } else {
+      for (x, color) in src.iter().enumerate() {
+        VIDEO3_VRAM.index(dest_x + x, y).write(*color);
+      }
+    }
+  }
+}
+
+/// Converts a video mode 3 position to the matching video mode 5 position.
+///
+/// Mode 3 is 240x160 and mode 5 is 160x128, so the column is scaled by 2/3
+/// and the row is scaled by 4/5, both rounded down. This lets drawing code
+/// written for mode 3 draw a squashed version of the same picture in mode 5.
+/// Since several mode 3 positions map to each mode 5 pixel, some pixels will
+/// just be drawn more than once.
+///
+/// Positions that are off the edge of the mode 3 screen give `None`, so
+/// anything that's clipped in mode 3 is also clipped in mode 5.
+///
+/// ```
+/// # use gba::video::mode5_scale_from_mode3;
+/// assert_eq!(mode5_scale_from_mode3(0, 0), Some((0, 0)));
+/// assert_eq!(mode5_scale_from_mode3(120, 80), Some((80, 64)));
+/// assert_eq!(mode5_scale_from_mode3(239, 159), Some((159, 127)));
+/// assert_eq!(mode5_scale_from_mode3(240, 0), None);
+/// assert_eq!(mode5_scale_from_mode3(0, 160), None);
+/// ```
+#[inline]
+#[must_use]
+pub const fn mode5_scale_from_mode3(
+  col: usize, row: usize,
+) -> Option<(usize, usize)> {
+  if col < 240 && row < 160 {
+    Some((col * 2 / 3, row * 4 / 5))
+  } else {
+    None
+  }
+}
+
+/// Sets a pixel of a frame of the video mode 5 bitmap, using a mode 3
+/// position.
+///
+/// See [`mode5_scale_from_mode3`]. Positions off the edge of the mode 3 screen
+/// are ignored.
+///
+/// ## Panics
+/// * If `frame` is 2 or more.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn mode5_plot_scaled(frame: usize, col: usize, row: usize, color: Color) {
+  let grid = VIDEO5_VRAM.get_frame(frame).unwrap();
+  if let Some((x, y)) = mode5_scale_from_mode3(col, row) {
+    grid.index(x, y).write(color);
+  }
+}
+
+/// Like [`mode3_draw_circle`], but draws to a frame of the video mode 5
+/// bitmap, using mode 3 positions.
+///
+/// See [`mode5_scale_from_mode3`]. The scaling isn't the same on both axes, so
+/// the circle comes out slightly squashed, just like everything else.
+///
+/// ## Panics
+/// * If `frame` is 2 or more.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn mode5_draw_circle_scaled(
+  frame: usize, cx: isize, cy: isize, radius: isize, color: Color,
+) {
+  let grid = VIDEO5_VRAM.get_frame(frame).unwrap();
+  circle_outline(cx, cy, radius, |x, y| {
+    if x < 0 || y < 0 {
+      return;
+    }
+    if let Some((x, y)) = mode5_scale_from_mode3(x as usize, y as usize) {
+      grid.index(x, y).write(color);
+    }
+  });
+}
+
+/// Like [`mode3_fill_circle`], but draws to a frame of the video mode 5
+/// bitmap, using mode 3 positions.
+///
+/// See [`mode5_scale_from_mode3`].
+///
+/// ## Panics
+/// * If `frame` is 2 or more.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn mode5_fill_circle_scaled(
+  frame: usize, cx: isize, cy: isize, radius: isize, color: Color,
+) {
+  let grid = VIDEO5_VRAM.get_frame(frame).unwrap();
+  circle_spans(cx, cy, radius, |y, x0, x1| {
+    if !(0..160).contains(&y) || x1 < 0 || x0 >= 240 {
+      return;
+    }
+    let start = mode5_scale_from_mode3(x0.max(0) as usize, y as usize);
+    let end = mode5_scale_from_mode3(x1.min(239) as usize, y as usize);
+    if let (Some((x0, y)), Some((x1, _))) = (start, end) {
+      for x in x0..=x1 {
+        grid.index(x, y).write(color);
+      }
+    }
+  });
+}
+
+/// Blends a row of pixels from `a` toward `b` by `t` sixteenths, into `out`.
+///
+/// This is the per-pixel work of a [`Mode3Crossfade`]. `t` is clamped to
+/// `0..=16`: 0 gives `a`, 16 gives `b`. Pixels are paired up by index, and if
+/// the slices are different lengths the extra elements are ignored.
+///
+/// ```
+/// # use gba::video::{crossfade_row, Color};
+/// let a = [Color::from_rgb(30, 0, 10); 240];
+/// let b = [Color::from_rgb(0, 20, 10); 240];
+/// let mut out = [Color::BLACK; 240];
+/// crossfade_row(&a, &b, 8, &mut out);
+/// assert!(out.iter().all(|c| *c == Color::from_rgb(15, 10, 10)));
+/// ```
+#[inline]
+pub fn crossfade_row(a: &[Color], b: &[Color], t: u8, out: &mut [Color]) {
+  for ((o, a), b) in out.iter_mut().zip(a.iter()).zip(b.iter()) {
+    *o = a.blend(*b, t);
+  }
+}
+
+/// A cross-fade between two mode 3 bitmaps that's done a few rows at a time.
+///
+/// Blending a whole 240x160 bitmap takes about 4 frames worth of CPU time, so
+/// usually you'd do part of it each frame. Every call to
+/// [`step`](Mode3Crossfade::step) blends the next group of rows into VRAM,
+/// until all 160 rows are done. About 40 rows per frame leaves a bit of time
+/// for the rest of a simple program. For a full fade, make a new cross-fade
+/// with a larger `t` once the previous one is done.
+///
+/// ```
+/// # use gba::video::Mode3Crossfade;
+/// let mut fade = Mode3Crossfade::new(8);
+/// assert_eq!(fade.next_rows(100), 0..100);
+/// assert_eq!(fade.next_rows(100), 100..160);
+/// assert!(fade.is_done());
+/// assert_eq!(fade.next_rows(100), 160..160);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Mode3Crossfade {
+  t: u8,
+  row: usize,
+}
+impl Mode3Crossfade {
+  /// Starts a new cross-fade at row 0.
+  ///
+  /// The blend amount `t` is in sixteenths (see [`crossfade_row`]).
+  #[inline]
+  #[must_use]
+  pub const fn new(t: u8) -> Self {
+    Self { t, row: 0 }
+  }
+
+  /// The blend amount.
+  #[inline]
+  #[must_use]
+  pub const fn t(&self) -> u8 {
+    self.t
+  }
+
+  /// The next row that will be blended.
+  #[inline]
+  #[must_use]
+  pub const fn row(&self) -> usize {
+    self.row
+  }
+
+  /// If all of the rows have been blended.
+  #[inline]
+  #[must_use]
+  pub const fn is_done(&self) -> bool {
+    self.row >= 160
+  }
+
+  /// Marks up to `rows` more rows as done, returning the range of rows that
+  /// should be blended now.
+  ///
+  /// This doesn't touch VRAM, [`step`](Mode3Crossfade::step) calls this and
+  /// then does the blending.
+  #[inline]
+  pub fn next_rows(&mut self, rows: usize) -> core::ops::Range<usize> {
+    let start = self.row;
+    self.row = start.saturating_add(rows).min(160);
+    start..self.row
+  }
+
+  /// Blends the next `rows` rows of `a` and `b` into the mode 3 bitmap.
+  ///
+  /// Returns if the cross-fade is done.
+  #[inline]
+  #[cfg(feature = "on_gba")]
+  pub fn step(
+    &mut self, a: &Video3Bitmap, b: &Video3Bitmap, rows: usize,
+  ) -> bool {
+    let mut line = [Color::BLACK; 240];
+    for y in self.next_rows(rows) {
+      let range = y * 240..(y + 1) * 240;
+      crossfade_row(&a.0[range.clone()], &b.0[range], self.t, &mut line);
+      for (x, color) in line.iter().enumerate() {
+        VIDEO3_VRAM.index(x, y).write(*color);
+      }
+    }
+    self.is_done()
+  }
+}
+
+/// Blends all of `a` toward `b` by `t` sixteenths, into the mode 3 bitmap.
+///
+/// This takes about 4 frames. Use a [`Mode3Crossfade`] to spread the work out
+/// over several frames instead.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn mode3_crossfade(a: &Video3Bitmap, b: &Video3Bitmap, t: u8) {
+  Mode3Crossfade::new(t).step(a, b, 160);
+}
+
+/// Fills `out` with a vertical gradient, one color per scanline.
+///
+/// The first entry is `top` and the last is `bottom`, with the channels in
+/// between linearly interpolated (rounded to nearest). Use a 160 entry `out`
+/// for one color per row of the screen.
+///
+/// ```
+/// # use gba::video::*;
+/// let mut rows = [Color::BLACK; 160];
+/// gradient_colors(Color::BLUE, Color::WHITE, &mut rows);
+/// assert_eq!(rows[0], Color::BLUE);
+/// assert_eq!(rows[159], Color::WHITE);
+/// assert_eq!(rows[80].blue(), 31);
+/// assert_eq!(rows[80].red(), 16);
+/// ```
+#[inline]
+pub fn gradient_colors(top: Color, bottom: Color, out: &mut [Color]) {
+  let last = out.len().saturating_sub(1).max(1) as u32;
+  let lerp = |a: u16, b: u16, i: u32| -> u16 {
+    let (a, b) = (u32::from(a), u32::from(b));
+    let x = if b >= a {
+      a + ((b - a) * i + last / 2) / last
+    } else {
+      a - ((a - b) * i + last / 2) / last
+    };
+    x as u16
   };
+  for (i, color) in out.iter_mut().enumerate() {
+    let i = i as u32;
+    *color = Color::from_rgb(
+      lerp(top.red(), bottom.red(), i),
+      lerp(top.green(), bottom.green(), i),
+      lerp(top.blue(), bottom.blue(), i),
+    );
+  }
+}
+
+/// The 8bpp tile for one row of tiles of [`fill_gradient_bg`].
+///
+/// Each pixel row of tile row `row` uses its own palette index:
+/// `1 + row * 8 + pixel_row`. So the 20 tile rows of the screen use the
+/// palette indexes `1..=160`, one for each scanline.
+///
+/// ```
+/// # use gba::video::*;
+/// let tile = gradient_tile8(2);
+/// // the first pixel row is all index 17, the last is all index 24
+/// assert_eq!(tile[0], 0x1111_1111);
+/// assert_eq!(tile[14], 0x1818_1818);
+/// ```
+///
+/// ## Panics
+/// * If `row` is 20 or more.
+#[inline]
+#[must_use]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub const fn gradient_tile8(row: u8) -> Tile8 {
+  assert!(row < 20);
+  let mut tile = [0_u32; 16];
+  let mut i = 0;
+  while i < 16 {
+    let index = (1 + row as u32 * 8 + i as u32 / 2) & 0xFF;
+    tile[i] = index * 0x0101_0101;
+    i += 1;
+  }
+  tile
+}
+
+/// Draws a vertical gradient from `top` to `bottom` with a tiled, 8bpp
+/// background.
+///
+/// This is for the tiled video modes. It writes:
+/// * 20 tiles (see [`gradient_tile8`]) to the start of `charblock`.
+/// * A map to the `frame` screenblock, where every entry of tile row `n` uses
+///   tile `n`. Map rows past the bottom of the screen repeat the last tile.
+/// * The colors of the gradient to background palette indexes `1..=160`.
+///
+/// The background itself still needs to be set to 8bpp and to use that
+/// charblock and screenblock (see [`bg8_control`]).
+///
+/// ## Panics
+/// * If `frame` is 32 or more, or `charblock` is 4 or more.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn fill_gradient_bg(
+  frame: usize, charblock: usize, top: Color, bottom: Color,
+) {
+  assert!(charblock < 4);
+  let sb = TEXT_SCREENBLOCKS.get_frame(frame).unwrap();
+  let tiles = match charblock {
+    0 => CHARBLOCK0_8BPP,
+    1 => CHARBLOCK1_8BPP,
+    2 => CHARBLOCK2_8BPP,
+    _ => CHARBLOCK3_8BPP,
+  };
+  for (row, addr) in tiles.iter().take(20).enumerate() {
+    addr.write(gradient_tile8(row as u8));
+  }
+  let mut colors = [Color::BLACK; 160];
+  gradient_colors(top, bottom, &mut colors);
+  for (addr, color) in BG_PALETTE.iter().skip(1).zip(colors) {
+    addr.write(color);
+  }
+  for row in 0..32 {
+    let entry = TextEntry::from_tile(row.min(19) as u16);
+    for col in 0..32 {
+      sb.index(col, row).write(entry);
+    }
+  }
+}
+
+/// Draws a vertical gradient from `top` to `bottom` in the video mode 3
+/// bitmap.
+///
+/// Each row of the bitmap is filled with its own color.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn fill_gradient_mode3(top: Color, bottom: Color) {
+  let mut colors = [Color::BLACK; 160];
+  gradient_colors(top, bottom, &mut colors);
+  for (row, color) in colors.iter().enumerate() {
+    for col in 0..240 {
+      VIDEO3_VRAM.index(col, row).write(*color);
+    }
+  }
+}
+
+/// The per-scanline backdrop colors for [`backdrop_gradient`], as raw `u16`
+/// values ready for DMA.
+///
+/// The colors are the same as from [`gradient_colors`].
+///
+/// ```
+/// # use gba::video::*;
+/// let table = backdrop_gradient_table(Color::BLUE, Color::BLACK);
+/// assert_eq!(table[0], Color::BLUE.0);
+/// assert_eq!(table[159], Color::BLACK.0);
+/// ```
+#[inline]
+#[must_use]
+pub fn backdrop_gradient_table(top: Color, bottom: Color) -> [u16; 160] {
+  let mut colors = [Color::BLACK; 160];
+  gradient_colors(top, bottom, &mut colors);
+  colors.map(|c| c.0)
+}
+
+/// The table that [`backdrop_gradient`] has DMA0 read from.
+#[cfg(feature = "on_gba")]
+static BACKDROP_GRADIENT: [GbaCell<u16>; 160] =
+  [const { GbaCell::new(0) }; 160];
+
+/// Fills the backdrop with a vertical gradient, by changing
+/// [`BACKDROP_COLOR`] every scanline with HBlank DMA.
+///
+/// The backdrop shows wherever no background or object is drawn, so this
+/// gives a sky behind everything else without using any VRAM.
+///
+/// This uses DMA0 (see [`hblank_dma0_u16`](crate::dma::hblank_dma0_u16)),
+/// which needs to be restarted every frame: call this once per frame during
+/// vertical blank. To stop the effect, clear DMA0's control
+/// (`DMA0_CONTROL.write(DmaControl::new())`).
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn backdrop_gradient(top: Color, bottom: Color) {
+  let table = backdrop_gradient_table(top, bottom);
+  for (cell, bits) in BACKDROP_GRADIENT.iter().zip(table) {
+    cell.write(bits);
+  }
+  // Safety: `GbaCell` is `repr(transparent)`, so the static is a `[u16; 160]`
+  // in RAM that never moves, and any `u16` is a valid backdrop color.
+  unsafe {
+    let table = &*(BACKDROP_GRADIENT.as_ptr() as *const [u16; 160]);
+    crate::dma::hblank_dma0_u16(table, BACKDROP_COLOR.as_usize() as *mut u16);
+  }
+}
+
+#[repr(C, align(4))]
+pub struct Video4Indexmap(pub [u8; 240 * 160]);
+
+/// Sets the indexmap of the frame requested.
+///
+/// When double buffering with [`video4_flip`], use [`video4_write_back`]
+/// instead, which always targets the frame that isn't being shown.
+///
+/// ## Panics
+/// Only frames 0 and 1 exist, if `frame` is 2 or more this will panic.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn video4_set_indexmap(indexes: &Video4Indexmap, frame: usize) {
+  let p = VIDEO4_VRAM.get_frame(usize::from(frame)).unwrap().as_usize()
+    as *mut [u32; 8];
+  unsafe { copy_u32x8(p, indexes as *const _ as *const _, 1200_usize) };
+}
+
+/// If frame 1 of the video mode 4 bitmap is the one being shown.
+///
+/// This is kept by [`video4_flip`], so that drawing can always go to the
+/// frame that *isn't* shown.
+#[cfg(feature = "on_gba")]
+static VIDEO4_SHOWING_FRAME1: GbaCell<bool> = GbaCell::new(false);
+
+/// The video mode 4 frame that's not being shown (the back buffer).
+///
+/// This is based on the last [`video4_flip`], and starts as frame 1. If you
+/// change the `show_frame1` bit of `DISPCNT` yourself this won't know about
+/// it, but the next [`video4_flip`] sets the bit to match again.
+#[inline]
+#[must_use]
+#[cfg(feature = "on_gba")]
+pub fn video4_backbuffer_page() -> usize {
+  if VIDEO4_SHOWING_FRAME1.read() {
+    0
+  } else {
+    1
+  }
+}
+
+/// Waits for vblank, then shows the back buffer frame of video mode 4, so
+/// that the frame that was being shown becomes the new back buffer.
+///
+/// This sets the `show_frame1` bit of `DISPCNT`, leaving the other bits
+/// alone. If the display is already in vblank the flip happens right away,
+/// otherwise this busy waits until vblank starts, so the switch never happens
+/// partway through drawing a frame. `IME` is off while checking for vblank and
+/// changing `DISPCNT`, so an interrupt can't push the write past the end of
+/// vblank or change `DISPCNT` in between the read and the write.
+///
+/// ```no_run
+/// # use gba::prelude::*;
+/// DISPCNT.write(
+///   DisplayControl::new().with_video_mode(VideoMode::_4).with_show_bg2(true),
+/// );
+/// loop {
+///   video4_clear_back_to(0);
+///   // ... draw the next frame ...
+///   video4_flip();
+/// }
+/// ```
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn video4_flip() {
+  loop {
+    let ime = IME.read();
+    IME.write(false);
+    let in_vblank = DISPSTAT.read().currently_vblank();
+    if in_vblank {
+      let show_frame1 = !VIDEO4_SHOWING_FRAME1.read();
+      DISPCNT.write(DISPCNT.read().with_show_frame1(show_frame1));
+      VIDEO4_SHOWING_FRAME1.write(show_frame1);
+    }
+    IME.write(ime);
+    if in_vblank {
+      return;
+    }
+  }
+}
+
+/// Copies an indexmap to the back buffer frame of video mode 4.
+///
+/// See [`video4_backbuffer_page`] and [`video4_set_indexmap`].
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn video4_write_back(indexes: &Video4Indexmap) {
+  video4_set_indexmap(indexes, video4_backbuffer_page());
+}
+
+/// Fills the back buffer frame of video mode 4 with one palette index.
+///
+/// See [`video4_backbuffer_page`].
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn video4_clear_back_to(index: u8) {
+  let u = u32::from_ne_bytes([index; 4]);
+  let frame = VIDEO4_VRAM.get_frame(video4_backbuffer_page()).unwrap();
+  unsafe {
+    let p = frame.as_usize() as *mut _;
+    set_u32x80(p, u, 120_usize);
+  }
+}
+
+/// The colors used by the bitmap test patterns, by palette index.
+///
+/// Index 0 isn't used by the pattern. The others are:
+/// 1. Top left corner: red
+/// 2. Top right corner: green
+/// 3. Bottom left corner: blue
+/// 4. Bottom right corner: white
+/// 5. Grid lines: yellow
+/// 6. Checkerboard: dark gray
+/// 7. Checkerboard: black
+pub const TEST_PATTERN_PALETTE: [Color; 8] = [
+  Color::BLACK,
+  Color::RED,
+  Color::GREEN,
+  Color::BLUE,
+  Color::WHITE,
+  Color::YELLOW,
+  Color::from_rgb(8, 8, 8),
+  Color::BLACK,
+];
+
+/// The [`TEST_PATTERN_PALETTE`] index of a pixel of the bitmap test pattern,
+/// for a bitmap that's `width` by `height`.
+///
+/// The pattern is a checkerboard of 8x8 cells, with grid lines every 16
+/// pixels, and a different color 8x8 block in each corner. If the pattern is
+/// shifted, squashed, or flipped on the screen then the bitmap addressing is
+/// wrong somewhere.
+///
+/// ```
+/// # use gba::video::*;
+/// // Mode 3 is 240x160
+/// assert_eq!(test_pattern_index(0, 0, 240, 160), 1);
+/// assert_eq!(test_pattern_index(239, 0, 240, 160), 2);
+/// assert_eq!(test_pattern_index(0, 159, 240, 160), 3);
+/// assert_eq!(test_pattern_index(239, 159, 240, 160), 4);
+/// assert_eq!(test_pattern_index(16, 20, 240, 160), 5);
+/// ```
+#[inline]
+#[must_use]
+pub const fn test_pattern_index(
+  x: usize, y: usize, width: usize, height: usize,
+) -> u8 {
+  let left = x < 8;
+  let right = x + 8 >= width;
+  let top = y < 8;
+  let bottom = y + 8 >= height;
+  match (left, right, top, bottom) {
+    (true, _, true, _) => 1,
+    (_, true, true, _) => 2,
+    (true, _, _, true) => 3,
+    (_, true, _, true) => 4,
+    _ if x & 15 == 0 || y & 15 == 0 => 5,
+    _ if (x / 8 + y / 8) & 1 == 0 => 6,
+    _ => 7,
+  }
+}
+
+/// The color of a pixel of the bitmap test pattern, see
+/// [`test_pattern_index`].
+#[inline]
+#[must_use]
+pub const fn test_pattern_color(
+  x: usize, y: usize, width: usize, height: usize,
+) -> Color {
+  TEST_PATTERN_PALETTE[test_pattern_index(x, y, width, height) as usize]
+}
+
+/// Draws the test pattern (see [`test_pattern_index`]) to the video mode 3
+/// bitmap.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn draw_test_pattern_mode3() {
+  for y in 0..160 {
+    for x in 0..240 {
+      VIDEO3_VRAM.index(x, y).write(test_pattern_color(x, y, 240, 160));
+    }
+  }
+}
+
+/// Draws the test pattern (see [`test_pattern_index`]) to a frame of the
+/// video mode 4 bitmap.
+///
+/// This also sets the first 8 background palette entries to
+/// [`TEST_PATTERN_PALETTE`].
+///
+/// ## Panics
+/// * If `frame` is 2 or more.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn draw_test_pattern_mode4(frame: usize) {
+  let grid = VIDEO4_VRAM.get_frame(frame).unwrap();
+  for (addr, color) in BG_PALETTE.iter().zip(TEST_PATTERN_PALETTE) {
+    addr.write(color);
+  }
+  for y in 0..160 {
+    for pair in 0..120 {
+      let x = pair * 2;
+      let entry = u8x2::default()
+        .with_low(test_pattern_index(x, y, 240, 160))
+        .with_high(test_pattern_index(x + 1, y, 240, 160));
+      grid.index(pair, y).write(entry);
+    }
+  }
+}
+
+/// Draws the test pattern (see [`test_pattern_index`]) to a frame of the
+/// video mode 5 bitmap, which is 160x128.
+///
+/// ## Panics
+/// * If `frame` is 2 or more.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn draw_test_pattern_mode5(frame: usize) {
+  let grid = VIDEO5_VRAM.get_frame(frame).unwrap();
+  for y in 0..128 {
+    for x in 0..160 {
+      grid.index(x, y).write(test_pattern_color(x, y, 160, 128));
+    }
+  }
+}
+
+/// Copies a rectangle of [`TextEntry`] values between text screenblocks.
+///
+/// * `src_frame` and `dst_frame` are screenblock indexes (`0..=31`), they can
+///   be the same screenblock.
+/// * The rectangle is `w` entries wide and `h` entries tall, with the top left
+///   at `(src_x, src_y)` in the source and `(dst_x, dst_y)` in the destination.
+///
+/// The rectangle is clipped so that it stays within the 32x32 bounds of both
+/// the source and the destination. When copying within a single screenblock,
+/// overlapping rectangles are handled correctly (like `memmove`).
+///
+/// ## Panics
+/// * If either screenblock index is 32 or more.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn copy_text_rect(
+  src_frame: usize, src_x: usize, src_y: usize, dst_frame: usize, dst_x: usize,
+  dst_y: usize, w: usize, h: usize,
+) {
+  let src = TEXT_SCREENBLOCKS.get_frame(src_frame).unwrap();
+  let dst = TEXT_SCREENBLOCKS.get_frame(dst_frame).unwrap();
+  let same = src_frame == dst_frame;
+  copy_rect_order((src_x, src_y), (dst_x, dst_y), (w, h), same, |s, d| {
+    dst.index(d.0, d.1).write(src.index(s.0, s.1).read());
+  });
+}
+
+/// Calls `copy(src_pos, dst_pos)` for each entry of a [`copy_text_rect`]
+/// rectangle, clipped to 32x32 and in an order that's safe for overlapping
+/// rectangles when `same` is set.
+#[inline]
+#[cfg(any(feature = "on_gba", test))]
+fn copy_rect_order(
+  (src_x, src_y): (usize, usize), (dst_x, dst_y): (usize, usize),
+  (w, h): (usize, usize), same: bool,
+  mut copy: impl FnMut((usize, usize), (usize, usize)),
+) {
+  let w =
+    w.min(32_usize.saturating_sub(src_x)).min(32_usize.saturating_sub(dst_x));
+  let h =
+    h.min(32_usize.saturating_sub(src_y)).min(32_usize.saturating_sub(dst_y));
+  // When moving "forward" within a single screenblock, go backwards so that
+  // we never overwrite an entry before it has been read.
+  let rows_backward = same && dst_y > src_y;
+  let cols_backward = same && dst_x > src_x;
+  for i in 0..h {
+    let r = if rows_backward { h - 1 - i } else { i };
+    for j in 0..w {
+      let c = if cols_backward { w - 1 - j } else { j };
+      copy((src_x + c, src_y + r), (dst_x + c, dst_y + r));
+    }
+  }
+}
+
+/// Fills a rectangle of a text screenblock with a single entry, such as to
+/// clear the inside of a text box.
+///
+/// * `screenblock` is the screenblock index (`0..=31`).
+/// * The rectangle is `w` entries wide and `h` entries tall, with the top left
+///   at `(x, y)`.
+///
+/// The rectangle is clipped to the 32x32 bounds of the screenblock.
+///
+/// ## Panics
+/// * If `screenblock` is 32 or more.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn fill_text_region(
+  screenblock: usize, x: usize, y: usize, w: usize, h: usize, entry: TextEntry,
+) {
+  let sb = TEXT_SCREENBLOCKS.get_frame(screenblock).unwrap();
+  for row in y..y.saturating_add(h).min(32) {
+    for col in x..x.saturating_add(w).min(32) {
+      sb.index(col, row).write(entry);
+    }
+  }
+}
+
+/// The entry at (`col`, `row`) within a `w` entries wide block of tiles laid
+/// out in row-major order, starting from `first`.
+///
+/// The tile index goes up by one for each entry (wrapping within the 10-bit
+/// tile field), and all other fields are the same as in `first`.
+///
+/// ```
+/// # use gba::video::*;
+/// let first = TextEntry::from_tile(100).with_palbank(2);
+/// assert_eq!(tile_block_entry(first, 4, 0, 0), first);
+/// assert_eq!(tile_block_entry(first, 4, 3, 0).tile(), 103);
+/// assert_eq!(tile_block_entry(first, 4, 1, 2).tile(), 109);
+/// assert_eq!(tile_block_entry(first, 4, 1, 2).palbank(), 2);
+/// ```
+#[inline]
+#[must_use]
+pub const fn tile_block_entry(
+  first: TextEntry, w: usize, col: usize, row: usize,
+) -> TextEntry {
+  let offset = (row * w + col) as u16;
+  first.with_tile(first.tile().wrapping_add(offset) & 0b11_1111_1111)
+}
+
+/// Fills a rectangle of a text screenblock with a block of tiles, such as a
+/// multi-tile graphic.
+///
+/// This is like [`fill_text_region`], but the tile index counts up across
+/// each row and then down the rows, starting from `first` (see
+/// [`tile_block_entry`]). Clipping doesn't change which tile goes where, the
+/// entries that would be off the screenblock are just skipped.
+///
+/// ## Panics
+/// * If `screenblock` is 32 or more.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn fill_text_region_tiles(
+  screenblock: usize, x: usize, y: usize, w: usize, h: usize, first: TextEntry,
+) {
+  let sb = TEXT_SCREENBLOCKS.get_frame(screenblock).unwrap();
+  for row in y..y.saturating_add(h).min(32) {
+    for col in x..x.saturating_add(w).min(32) {
+      sb.index(col, row).write(tile_block_entry(first, w, col - x, row - y));
+    }
+  }
+}
+
+/// Mirrors one row of a text screenblock horizontally.
+///
+/// The columns are reversed, and each entry's `hflip` bit is toggled so that
+/// the tiles themselves face the other way too.
+///
+/// ```
+/// # use gba::video::*;
+/// let mut row = [TextEntry::new(); 32];
+/// row[0] = TextEntry::from_tile(5);
+/// row[1] = TextEntry::from_tile(6).with_hflip(true);
+/// let mirrored = mirror_row_h(&row);
+/// assert_eq!(mirrored[31], TextEntry::from_tile(5).with_hflip(true));
+/// assert_eq!(mirrored[30], TextEntry::from_tile(6));
+/// assert_eq!(mirrored[0], TextEntry::new().with_hflip(true));
+/// ```
+#[inline]
+#[must_use]
+pub const fn mirror_row_h(row: &[TextEntry; 32]) -> [TextEntry; 32] {
+  let mut out = [TextEntry::new(); 32];
+  let mut i = 0;
+  while i < 32 {
+    let e = row[31 - i];
+    out[i] = e.with_hflip(!e.hflip());
+    i += 1;
+  }
+  out
+}
+
+/// Copies a text screenblock into another, mirrored horizontally.
+///
+/// Each row is mirrored with [`mirror_row_h`]. This lets you design half of a
+/// symmetric area and make the other half from it. The source and destination
+/// can be the same screenblock, which mirrors it in place.
+///
+/// ## Panics
+/// * If either screenblock index is 32 or more.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn mirror_screenblock_h(src_frame: usize, dst_frame: usize) {
+  let src = TEXT_SCREENBLOCKS.get_frame(src_frame).unwrap();
+  let dst = TEXT_SCREENBLOCKS.get_frame(dst_frame).unwrap();
+  for row in 0..32 {
+    let mut line = [TextEntry::new(); 32];
+    for (col, entry) in line.iter_mut().enumerate() {
+      *entry = src.index(col, row).read();
+    }
+    for (col, entry) in mirror_row_h(&line).into_iter().enumerate() {
+      dst.index(col, row).write(entry);
+    }
+  }
+}
+
+/// The four entries that draw a 16x16 tile, in the order top left, top
+/// right, bottom left, bottom right.
+///
+/// The 16x16 tile's four 8x8 sub-tiles are assumed to be stored one after the
+/// other in that same order, starting at `base_tile`. This is the "metatile"
+/// layout that common tools (such as `grit` with a 2x2 metatile size) output.
+#[inline]
+#[must_use]
+pub const fn tile16_entries(base_tile: u16, palbank: u16) -> [TextEntry; 4] {
+  let mut entries = [TextEntry::new(); 4];
+  let mut i = 0;
+  while i < 4 {
+    entries[i] =
+      TextEntry::from_tile(base_tile + i as u16).with_palbank(palbank);
+    i += 1;
+  }
+  entries
+}
+
+/// Draws a 16x16 tile (such as a large font glyph) into a text screenblock.
+///
+/// * `frame` is the screenblock index (`0..=31`).
+/// * `col` and `row` are the screenblock position of the top left sub-tile. The
+///   16x16 tile covers that entry and the entries to the right, below, and
+///   below right of it.
+///
+/// See [`tile16_entries`] for the assumed ordering of the sub-tiles. Any
+/// sub-tile that would be outside the 32x32 screenblock isn't drawn.
+///
+/// ## Panics
+/// * If `frame` is 32 or more.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn draw_tile16(
+  frame: usize, col: usize, row: usize, base_tile: u16, palbank: u16,
+) {
+  let sb = TEXT_SCREENBLOCKS.get_frame(frame).unwrap();
+  let entries = tile16_entries(base_tile, palbank);
+  for (i, entry) in entries.iter().enumerate() {
+    if let Some(addr) = sb.get(col + (i & 1), row + (i >> 1)) {
+      addr.write(*entry);
+    }
+  }
+}
+
+/// The address of the [`TextEntry`] at a position within a text screenblock.
+///
+/// ## Panics
+/// * If `screenblock` is 32 or more, or `col` or `row` is 32 or more.
+#[inline]
+#[must_use]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub const fn text_entry_addr(
+  screenblock: usize, col: usize, row: usize,
+) -> usize {
+  assert!(screenblock < 32);
+  assert!(col < 32 && row < 32, "position outside of the 32x32 screenblock");
+  0x0600_0000
+    + screenblock * SCREENBLOCK_BYTES
+    + (row * 32 + col) * core::mem::size_of::<TextEntry>()
+}
+
+/// Changes a single map entry of a text background.
+///
+/// The entry is written to the screenblock that `bg` is currently set to use,
+/// so you don't need to work out the address yourself. `flags` gives the
+/// flip and palbank bits, and its tile index is replaced by `tile`.
+///
+/// ## Panics
+/// * If `bg` is 4 or more, or `col` or `row` is 32 or more.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn set_map_tile(
+  bg: usize, col: usize, row: usize, tile: u16, flags: TextEntry,
+) {
+  let screenblock = usize::from(BGCNT.index(bg).read().screenblock());
+  let addr = text_entry_addr(screenblock, col, row);
+  // Safety: the address is a text entry within background VRAM.
+  unsafe {
+    VolAddress::<TextEntry, Safe, Safe>::new(addr).write(flags.with_tile(tile))
+  };
+}
+
+/// Fills `out` with the tile indexes to draw `value` as right-aligned decimal
+/// digits.
+///
+/// Digit `d` uses tile `digit_base_tile + d`, and leading blanks use tile 0.
+/// The ones digit is always drawn, so 0 shows as "0" rather than all blanks.
+/// If `value` has more digits than fit in `out`, only the lowest digits are
+/// kept.
+///
+/// ```
+/// # use gba::video::u32_digit_tiles;
+/// let mut tiles = [0; 4];
+/// // with the CGA font, the digits start at tile `b'0'`.
+/// u32_digit_tiles(42, u16::from(b'0'), &mut tiles);
+/// assert_eq!(tiles, [0, 0, u16::from(b'4'), u16::from(b'2')]);
+/// u32_digit_tiles(0, 100, &mut tiles);
+/// assert_eq!(tiles, [0, 0, 0, 100]);
+/// u32_digit_tiles(123_456, 100, &mut tiles);
+/// assert_eq!(tiles, [103, 104, 105, 106]);
+/// ```
+#[inline]
+pub fn u32_digit_tiles(mut value: u32, digit_base_tile: u16, out: &mut [u16]) {
+  for (i, tile) in out.iter_mut().rev().enumerate() {
+    *tile = if value == 0 && i > 0 {
+      0
+    } else {
+      digit_base_tile + (value % 10) as u16
+    };
+    value /= 10;
+  }
+}
+
+/// Draws a number into a text screenblock as right-aligned decimal digits.
+///
+/// * `frame` is the screenblock index (`0..=31`).
+/// * `col` and `row` are the screenblock position of the leftmost digit.
+/// * `digits` is how many columns the number takes up. Unused columns on the
+///   left are drawn with tile 0, and if the number doesn't fit then only the
+///   lowest digits are drawn.
+/// * `digit_base_tile` is the tile for the digit 0, with 1 through 9 in the
+///   tiles after it.
+///
+/// This doesn't use [`core::fmt`] at all, so it's cheap enough to call every
+/// frame for score and timer displays. See [`u32_digit_tiles`] for the tiles
+/// that get drawn. Any digit that would be past the right edge of the
+/// screenblock isn't drawn.
+///
+/// ## Panics
+/// * If `frame` is 32 or more.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn draw_u32(
+  frame: usize, col: usize, row: usize, palbank: u16, value: u32,
+  digits: usize, digit_base_tile: u16,
+) {
+  let sb = TEXT_SCREENBLOCKS.get_frame(frame).unwrap();
+  // a screenblock is only 32 entries wide, so more digits than that is never
+  // visible anyway.
+  let mut tiles = [0_u16; 32];
+  let tiles = &mut tiles[..digits.min(32)];
+  u32_digit_tiles(value, digit_base_tile, tiles);
+  for (i, tile) in tiles.iter().enumerate() {
+    if let Some(addr) = sb.get(col + i, row) {
+      addr.write(TextEntry::from_tile(*tile).with_palbank(palbank));
+    }
+  }
+}
+
+/// The number of columns visible on screen with an unscrolled text background.
+const VISIBLE_TEXT_COLS: u16 = 30;
+
+/// How many tiles wide a string is when drawn with a monospace tile font.
+///
+/// Each byte of the string is drawn as one tile (the tile index is the byte
+/// value, as with the [CGA font](crate::builtin_art)), so this is just the
+/// byte length, saturated to `u16::MAX`.
+#[inline]
+#[must_use]
+pub fn text_width_tiles(s: &str) -> u16 {
+  u16::try_from(s.len()).unwrap_or(u16::MAX)
+}
+
+/// The column that a string should start at to be centered within the 30
+/// visible columns of an unscrolled text background.
+///
+/// Strings that are too long to fit on the screen start at column 0.
+///
+/// ```
+/// # use gba::video::centered_text_col;
+/// assert_eq!(centered_text_col("0123456789"), 10);
+/// assert_eq!(centered_text_col("odd"), 13);
+/// assert_eq!(centered_text_col(&"x".repeat(40)), 0);
+/// ```
+#[inline]
+#[must_use]
+pub fn centered_text_col(s: &str) -> usize {
+  usize::from(VISIBLE_TEXT_COLS.saturating_sub(text_width_tiles(s)) / 2)
+}
+
+/// Draws a string into a text screenblock, centered on the screen.
+///
+/// * `frame` is the screenblock index (`0..=31`).
+/// * `row` is the screenblock row to draw on.
+///
+/// The string is centered within the 30 visible columns (see
+/// [`centered_text_col`]), assuming the background isn't scrolled. Each byte
+/// is drawn as one tile, using the byte value as the tile index. Anything
+/// past the right edge of the screenblock isn't drawn.
+///
+/// ## Panics
+/// * If `frame` is 32 or more.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn draw_centered(frame: usize, row: usize, palbank: u16, s: &str) {
+  let sb = TEXT_SCREENBLOCKS.get_frame(frame).unwrap();
+  let start = centered_text_col(s);
+  for (i, b) in s.bytes().enumerate() {
+    match sb.get(start + i, row) {
+      Some(addr) => {
+        addr.write(TextEntry::from_tile(u16::from(b)).with_palbank(palbank))
+      }
+      None => break,
+    }
+  }
+}
+
+/// The screenblock position of the `i`th character of text written starting
+/// at (`col`, `row`), wrapping to the start of the next row after column 31.
+///
+/// Returns `None` once the text goes past the bottom of the screenblock.
+///
+/// ```
+/// # use gba::video::text_wrap_pos;
+/// assert_eq!(text_wrap_pos(30, 5, 0), Some((30, 5)));
+/// assert_eq!(text_wrap_pos(30, 5, 1), Some((31, 5)));
+/// assert_eq!(text_wrap_pos(30, 5, 2), Some((0, 6)));
+/// assert_eq!(text_wrap_pos(31, 31, 1), None);
+/// ```
+#[inline]
+#[must_use]
+pub const fn text_wrap_pos(
+  col: usize, row: usize, i: usize,
+) -> Option<(usize, usize)> {
+  let linear = col + i;
+  let row = row + linear / 32;
+  if row < 32 {
+    Some((linear & 31, row))
+  } else {
+    None
+  }
+}
+
+/// Writes bytes into a text screenblock, one tile per byte, such as for debug
+/// text.
+///
+/// * `screenblock` is the screenblock index (`0..=31`).
+/// * The first byte goes at (`col`, `row`), and each byte after goes one column
+///   to the right, wrapping to the start of the next row after column 31 (see
+///   [`text_wrap_pos`]). Anything past the bottom of the screenblock isn't
+///   drawn.
+///
+/// Each byte value is used as the tile index, so this lines up with a font
+/// such as [`CGA_8X8_THICK`](crate::builtin_art::CGA_8X8_THICK) loaded at the
+/// start of the charblock.
+///
+/// Returns the number of bytes written.
+///
+/// ## Panics
+/// * If `screenblock` is 32 or more.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn put_str(
+  screenblock: usize, col: usize, row: usize, palbank: u16, s: &[u8],
+) -> usize {
+  let sb = TEXT_SCREENBLOCKS.get_frame(screenblock).unwrap();
+  for (i, b) in s.iter().enumerate() {
+    match text_wrap_pos(col, row, i) {
+      Some((c, r)) => sb
+        .index(c, r)
+        .write(TextEntry::from_tile(u16::from(*b)).with_palbank(palbank)),
+      None => return i,
+    }
+  }
+  s.len()
+}
+
+/// A problem found when checking the background configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum VideoConfigError {
+  /// The background is shown, but the video mode doesn't have that background.
+  BgUnavailableInMode {
+    /// The background index.
+    bg: usize,
+    /// The video mode that was set.
+    mode: VideoMode,
+  },
+  /// The background's screenblock data overlaps the charblock that a shown
+  /// background's tile data starts in (possibly its own).
+  ///
+  /// This isn't *always* a bug: if the background uses few enough tiles then
+  /// the tiles and the screenblock can share a charblock without touching.
+  /// Still, it's usually a mistake, so it gets reported.
+  ScreenblockInCharblock {
+    /// The background index.
+    bg: usize,
+    /// The background that uses the charblock.
+    tiles_of: usize,
+  },
+  /// Two shown backgrounds have screenblock data that overlaps.
+  ScreenblockOverlap {
+    /// The first background index.
+    bg: usize,
+    /// The other background index.
+    other: usize,
+  },
+  /// A background index was 4 or more.
+  BgOutOfRange {
+    /// The background index.
+    bg: usize,
+  },
+  /// The same background was configured more than once.
+  DuplicateBg {
+    /// The background index.
+    bg: usize,
+  },
+}
+
+/// The byte spacing of screenblock indexes.
+const SCREENBLOCK_BYTES: usize = 2 * 1024;
+
+/// The byte spacing of charblock indexes.
+const CHARBLOCK_BYTES: usize = 16 * 1024;
+
+/// How a background layer operates within a particular video mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BgKind {
+  Text,
+  Affine,
+  Bitmap,
+}
+
+/// Gets how `bg` operates in `mode`, or `None` if it's unavailable.
+const fn bg_kind(mode: VideoMode, bg: usize) -> Option<BgKind> {
+  match (mode, bg) {
+    (VideoMode::_0, 0..=3) | (VideoMode::_1, 0..=1) => Some(BgKind::Text),
+    (VideoMode::_1, 2) | (VideoMode::_2, 2..=3) => Some(BgKind::Affine),
+    (VideoMode::_3 | VideoMode::_4 | VideoMode::_5, 2) => Some(BgKind::Bitmap),
+    _ => None,
+  }
+}
+
+/// The number of bytes of screenblock data a text background uses.
+const fn text_map_bytes(size: u16) -> usize {
+  match size {
+    0 => 2 * 1024,
+    1 | 2 => 4 * 1024,
+    _ => 8 * 1024,
+  }
+}
+
+/// The number of bytes of screenblock data an affine background uses.
+const fn affine_map_bytes(size: u16) -> usize {
+  match size {
+    0 => 256,
+    1 => 1024,
+    2 => 4 * 1024,
+    _ => 16 * 1024,
+  }
+}
+
+/// Checks a display control value and the four background control values for
+/// inconsistencies.
+///
+/// Only backgrounds that are actually shown are checked. This looks for:
+/// * Backgrounds shown that the video mode doesn't have.
+/// * Tiled backgrounds whose screenblock data lands within the charblock that a
+///   shown background's tile data starts in.
+/// * Tiled backgrounds whose screenblock data overlaps each other.
+///
+/// This is the pure version of [`validate_bg_config`], which reads the
+/// registers for you.
+#[inline]
+pub fn check_bg_config(
+  display: DisplayControl, bgs: [BackgroundControl; 4],
+) -> Result<(), VideoConfigError> {
+  let mode = display.video_mode();
+  let shown = [
+    display.show_bg0(),
+    display.show_bg1(),
+    display.show_bg2(),
+    display.show_bg3(),
+  ];
+  // (map_start, map_end, tile_start) of each shown tiled background
+  let mut layers: [Option<(usize, usize, usize)>; 4] = [None; 4];
+  for (bg, (cnt, shown)) in bgs.iter().zip(shown).enumerate() {
+    if !shown {
+      continue;
+    }
+    let map_bytes = match bg_kind(mode, bg) {
+      None => return Err(VideoConfigError::BgUnavailableInMode { bg, mode }),
+      Some(BgKind::Bitmap) => continue,
+      Some(BgKind::Text) => text_map_bytes(cnt.size()),
+      Some(BgKind::Affine) => affine_map_bytes(cnt.size()),
+    };
+    let map_start = usize::from(cnt.screenblock()) * SCREENBLOCK_BYTES;
+    let tile_start = usize::from(cnt.charblock()) * CHARBLOCK_BYTES;
+    layers[bg] = Some((map_start, map_start + map_bytes, tile_start));
+  }
+  for (bg, layer) in layers.iter().enumerate() {
+    let Some((map_start, map_end, _)) = *layer else { continue };
+    for (other, other_layer) in layers.iter().enumerate() {
+      let Some((other_start, other_end, tile_start)) = *other_layer else {
+        continue;
+      };
+      let tile_end = tile_start + CHARBLOCK_BYTES;
+      if map_start < tile_end && tile_start < map_end {
+        return Err(VideoConfigError::ScreenblockInCharblock {
+          bg,
+          tiles_of: other,
+        });
+      }
+      if other > bg && map_start < other_end && other_start < map_end {
+        return Err(VideoConfigError::ScreenblockOverlap { bg, other });
+      }
+    }
+  }
+  Ok(())
+}
+
+/// The total size of VRAM, in bytes.
+const VRAM_BYTES: usize = 96 * 1024;
+
+/// A plan of what will be stored where in VRAM, for [`vram_budget`].
+///
+/// All positions are in the usual VRAM units: charblocks are 16K and
+/// screenblocks are 2K, both counted from the start of VRAM. Charblocks 4 and
+/// 5 are the object tile memory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct VramPlan<'a> {
+  /// Tile data, as `(first charblock, size in bytes)`.
+  ///
+  /// Tile data can be larger than one charblock, it just continues into the
+  /// next one.
+  pub tiles: &'a [(u8, usize)],
+  /// Map data, as `(first screenblock, number of screenblocks)`.
+  pub maps: &'a [(u8, u8)],
+  /// The number of bytes of bitmap data, starting at the beginning of VRAM.
+  ///
+  /// Mode 3 uses 76,800 bytes, and modes 4 and 5 use 40,960 bytes per frame
+  /// (so 81,920 bytes when using both frames).
+  pub bitmap_bytes: usize,
+}
+
+/// One of the entries of a [`VramPlan`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum VramUse {
+  /// An entry of [`tiles`](VramPlan::tiles), with its first charblock.
+  Tiles {
+    /// The entry's index within the list.
+    index: usize,
+    /// The first charblock of the tile data.
+    charblock: u8,
+  },
+  /// An entry of [`maps`](VramPlan::maps), with its first screenblock.
+  Map {
+    /// The entry's index within the list.
+    index: usize,
+    /// The first screenblock of the map data.
+    screenblock: u8,
+  },
+  /// The bitmap data.
+  Bitmap,
+}
+
+/// A problem found by [`vram_budget`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum VramOverflow {
+  /// The data goes past the end of the 96K of VRAM.
+  PastEnd {
+    /// What the data is.
+    user: VramUse,
+    /// The byte offset where the data ends.
+    end: usize,
+  },
+  /// Two things use the same part of VRAM.
+  Overlap {
+    /// The first user.
+    first: VramUse,
+    /// The second user.
+    second: VramUse,
+    /// The byte offset where the overlap starts.
+    at: usize,
+  },
+}
+
+/// Gets entry `i` of a plan as `(user, start, end)` byte offsets, counting the
+/// tiles, then the maps, then the bitmap.
+#[inline]
+fn vram_plan_entry(plan: &VramPlan, i: usize) -> (VramUse, usize, usize) {
+  if let Some(&(charblock, bytes)) = plan.tiles.get(i) {
+    let start = usize::from(charblock) * CHARBLOCK_BYTES;
+    (VramUse::Tiles { index: i, charblock }, start, start + bytes)
+  } else if let Some(&(screenblock, count)) =
+    plan.maps.get(i - plan.tiles.len())
+  {
+    let index = i - plan.tiles.len();
+    let start = usize::from(screenblock) * SCREENBLOCK_BYTES;
+    let end = start + usize::from(count) * SCREENBLOCK_BYTES;
+    (VramUse::Map { index, screenblock }, start, end)
+  } else {
+    (VramUse::Bitmap, 0, plan.bitmap_bytes)
+  }
+}
+
+/// Checks that everything in a plan fits in VRAM without overlapping.
+///
+/// The first problem found is returned. Entries with a size of 0 are ignored.
+///
+/// This only checks the plan against itself. It doesn't know which video
+/// mode will be used, so it can't tell (for example) that backgrounds can't
+/// use tile data in charblocks 4 and 5.
+///
+/// ```
+/// # use gba::video::*;
+/// // tiles in charblock 0, map in screenblock 31
+/// let plan =
+///   VramPlan { tiles: &[(0, 8192)], maps: &[(31, 1)], bitmap_bytes: 0 };
+/// assert_eq!(vram_budget(&plan), Ok(()));
+/// // 20K of tiles runs into screenblock 8, where the map is.
+/// let plan =
+///   VramPlan { tiles: &[(0, 20480)], maps: &[(8, 1)], bitmap_bytes: 0 };
+/// assert_eq!(
+///   vram_budget(&plan),
+///   Err(VramOverflow::Overlap {
+///     first: VramUse::Tiles { index: 0, charblock: 0 },
+///     second: VramUse::Map { index: 0, screenblock: 8 },
+///     at: 16384,
+///   })
+/// );
+/// ```
+#[inline]
+pub fn vram_budget(plan: &VramPlan) -> Result<(), VramOverflow> {
+  let count = plan.tiles.len() + plan.maps.len() + 1;
+  for i in 0..count {
+    let (user, start, end) = vram_plan_entry(plan, i);
+    if start == end {
+      continue;
+    }
+    if end > VRAM_BYTES {
+      return Err(VramOverflow::PastEnd { user, end });
+    }
+    for j in (i + 1)..count {
+      let (other, other_start, other_end) = vram_plan_entry(plan, j);
+      if other_start != other_end && start < other_end && other_start < end {
+        return Err(VramOverflow::Overlap {
+          first: user,
+          second: other,
+          at: start.max(other_start),
+        });
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Reads [`DISPCNT`] and the [`BGCNT`] registers and checks them for
+/// inconsistencies.
+///
+/// This is intended as a debugging aid during development, see
+/// [`check_bg_config`] for the list of checks.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn validate_bg_config() -> Result<(), VideoConfigError> {
+  let bgs = [
+    BGCNT.index(0).read(),
+    BGCNT.index(1).read(),
+    BGCNT.index(2).read(),
+    BGCNT.index(3).read(),
+  ];
+  check_bg_config(DISPCNT.read(), bgs)
+}
+
+/// A tiled background image: 4bpp tiles, a screenblock map, and a palette.
+///
+/// This bundles up the three parts of "show a background image" so that they
+/// can be loaded together with [`Scene::load`].
+#[derive(Debug, Clone, Copy)]
+pub struct Scene<'a> {
+  /// Tile data, copied to the start of the charblock.
+  pub tiles: &'a [Tile4],
+  /// Map data, copied to the start of the screenblock. Multiple 32x32
+  /// screenblocks are used in sequence when there's more than 1024 entries.
+  pub map: &'a [TextEntry],
+  /// Palette data, copied to the start of the background palette.
+  pub palette: &'a [Color],
+}
+impl<'a> Scene<'a> {
+  /// Works out where [`load`](Scene::load) puts the scene's data, and the
+  /// background size it uses.
+  ///
+  /// ## Panics
+  /// * Same as [`load`](Scene::load), other than the checks on `bg`.
+  #[inline]
+  #[must_use]
+  #[cfg_attr(feature = "track_caller", track_caller)]
+  pub fn layout(&self, charblock: usize, screenblock: usize) -> SceneLayout {
+    assert!(charblock < 4);
+    assert!(screenblock < 32);
+    let tile_offset = charblock * CHARBLOCK_BYTES;
+    let tile_bytes = core::mem::size_of_val(self.tiles);
+    assert!(tile_offset + tile_bytes <= 4 * CHARBLOCK_BYTES);
+    let map_offset = screenblock * SCREENBLOCK_BYTES;
+    let map_bytes = core::mem::size_of_val(self.map);
+    assert!(self.map.len() <= 4 * 1024);
+    assert!(map_offset + map_bytes <= 4 * CHARBLOCK_BYTES);
+    assert!(self.palette.len() <= 256);
+    let size = match self.map.len() {
+      0..=1024 => 0,
+      1025..=2048 => 1,
+      _ => 3,
+    };
+    SceneLayout { tile_offset, map_offset, size }
+  }
+
+  /// Copies the scene's data into VRAM and PALRAM, and then sets the
+  /// background's control register to use that data.
+  ///
+  /// The background's priority and mosaic settings are kept. The background
+  /// is set to 4bpp, and the size is picked by the length of the map:
+  /// * up to 1024 entries: 32x32
+  /// * up to 2048 entries: 64x32
+  /// * more than that: 64x64
+  ///
+  /// If you want a 64x32 tall background, adjust the size after loading.
+  ///
+  /// ## Panics
+  /// * `bg` must be `0..=3`, `charblock` must be `0..=3`, and `screenblock`
+  ///   must be `0..=31`.
+  /// * The tiles must fit in background VRAM starting from the charblock.
+  /// * The map must be no more than 4096 entries, and must fit in background
+  ///   VRAM starting from the screenblock.
+  /// * The palette must be no more than 256 entries.
+  #[inline]
+  #[cfg(feature = "on_gba")]
+  #[cfg_attr(feature = "track_caller", track_caller)]
+  pub fn load(&self, bg: usize, charblock: usize, screenblock: usize) {
+    assert!(bg < 4);
+    let layout = self.layout(charblock, screenblock);
+    let vram = CHARBLOCK0_4BPP.as_usize();
+    unsafe {
+      dma3_copy_u32(
+        self.tiles.as_ptr().cast(),
+        (vram + layout.tile_offset) as *mut u32,
+        self.tiles.len() * 8,
+      );
+      dma3_copy_u16(
+        self.map.as_ptr().cast(),
+        (vram + layout.map_offset) as *mut u16,
+        self.map.len(),
+      );
+      dma3_copy_u16(
+        self.palette.as_ptr().cast(),
+        BG_PALETTE.as_usize() as *mut u16,
+        self.palette.len(),
+      );
+    }
+    let cnt = BGCNT.index(bg);
+    cnt.write(
+      cnt
+        .read()
+        .with_charblock(charblock as u16)
+        .with_screenblock(screenblock as u16)
+        .with_bpp8(false)
+        .with_size(layout.size),
+    );
+  }
+}
+
+/// Where [`Scene::load`] puts a scene's data, see [`Scene::layout`].
+///
+/// The palette always goes at the start of the background palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SceneLayout {
+  /// The byte offset of the tiles from the start of VRAM.
+  pub tile_offset: usize,
+  /// The byte offset of the map from the start of VRAM.
+  pub map_offset: usize,
+  /// The background size setting.
+  pub size: u16,
+}
+
+/// The settings for one text mode background, see [`setup_text_bgs`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TextBgConfig {
+  /// Which background (`0..=3`).
+  pub bg: usize,
+  /// The charblock of the background's tiles (`0..=3`).
+  pub charblock: u16,
+  /// The first screenblock of the background's map (`0..=31`).
+  pub screenblock: u16,
+  /// The background's priority (`0..=3`).
+  pub priority: u16,
+  /// The background's size setting (`0..=3`).
+  pub size: u16,
+  /// If the background uses 8bpp tiles.
+  pub bpp8: bool,
+}
+impl TextBgConfig {
+  /// The background control value for this config.
+  #[inline]
+  #[must_use]
+  pub const fn to_control(&self) -> BackgroundControl {
+    BackgroundControl::new()
+      .with_priority_level(Priority::from_bits(self.priority))
+      .with_charblock(self.charblock)
+      .with_screenblock(self.screenblock)
+      .with_size(self.size)
+      .with_bpp8(self.bpp8)
+  }
+}
+
+/// Computes the register values that [`setup_text_bgs`] will use.
+///
+/// The display control value is video mode 0 with each configured background
+/// shown (and no other bits set). Any background that isn't configured gets a
+/// zeroed control value.
+///
+/// The result is checked with [`check_bg_config`].
+#[inline]
+pub fn text_bg_registers(
+  configs: &[TextBgConfig],
+) -> Result<(DisplayControl, [BackgroundControl; 4]), VideoConfigError> {
+  let mut shown = [false; 4];
+  let mut bgs = [BackgroundControl::new(); 4];
+  for config in configs {
+    let bg = config.bg;
+    if bg >= 4 {
+      return Err(VideoConfigError::BgOutOfRange { bg });
+    }
+    if shown[bg] {
+      return Err(VideoConfigError::DuplicateBg { bg });
+    }
+    shown[bg] = true;
+    bgs[bg] = config.to_control();
+  }
+  let display = DisplayControl::new()
+    .with_video_mode(VideoMode::_0)
+    .with_show_bg0(shown[0])
+    .with_show_bg1(shown[1])
+    .with_show_bg2(shown[2])
+    .with_show_bg3(shown[3]);
+  check_bg_config(display, bgs)?;
+  Ok((display, bgs))
+}
+
+/// Sets up video mode 0 with the given text backgrounds.
+///
+/// Each configured background has its control register written and is shown,
+/// all other backgrounds are hidden. Other display control bits (objects,
+/// windows, forced blank, etc) are kept as they were.
+///
+/// Nothing is written if the configuration has a problem, see
+/// [`text_bg_registers`].
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn setup_text_bgs(
+  configs: &[TextBgConfig],
+) -> Result<(), VideoConfigError> {
+  let (display, bgs) = text_bg_registers(configs)?;
+  for config in configs {
+    BGCNT.index(config.bg).write(bgs[config.bg]);
+  }
+  DISPCNT.write(
+    DISPCNT
+      .read()
+      .with_video_mode(VideoMode::_0)
+      .with_show_bg0(display.show_bg0())
+      .with_show_bg1(display.show_bg1())
+      .with_show_bg2(display.show_bg2())
+      .with_show_bg3(display.show_bg3()),
+  );
+  Ok(())
+}
+
+/// Writes a matrix to the affine parameters (`BGxPA` through `BGxPD`) of an
+/// affine background.
+///
+/// See [`Mat2::from_rotation_scale`] for a common way to make the matrix.
+///
+/// ## Panics
+/// * If `bg` isn't 2 or 3, the only backgrounds that can be affine.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn write_bg_affine(bg: usize, m: &Mat2) {
+  let [a, b, c, d] = m.to_bg_params();
+  match bg {
+    2 => {
+      BG2PA.write(a);
+      BG2PB.write(b);
+      BG2PC.write(c);
+      BG2PD.write(d);
+    }
+    3 => {
+      BG3PA.write(a);
+      BG3PB.write(b);
+      BG3PC.write(c);
+      BG3PD.write(d);
+    }
+    _ => panic!("only backgrounds 2 and 3 can be affine"),
+  }
+}
+
+/// A problem found by [`bg8_control`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Bg8Error {
+  /// The background index was 4 or more.
+  BgOutOfRange {
+    /// The background index given.
+    bg: usize,
+  },
+  /// The charblock was 4 or more.
+  CharblockOutOfRange {
+    /// The charblock given.
+    charblock: usize,
+  },
+  /// The screenblock was 32 or more.
+  ScreenblockOutOfRange {
+    /// The screenblock given.
+    screenblock: usize,
+  },
+  /// There were more than 256 tiles, which is all that fits in a charblock.
+  TooManyTiles {
+    /// The number of tiles given.
+    count: usize,
+  },
+  /// The map had more than 1024 entries, which is all that fits in a
+  /// screenblock.
+  MapTooLarge {
+    /// The number of entries given.
+    len: usize,
+  },
+  /// A map entry uses a tile past the end of the tile data.
+  TileOutOfRange {
+    /// The position of the entry in the map.
+    index: usize,
+    /// The tile the entry uses.
+    tile: u8,
+  },
+  /// The screenblock is inside of the tile data.
+  MapOverlapsTiles,
+}
+
+/// Checks the arguments of [`setup_bg8`] and computes the control value for
+/// the background.
+///
+/// The priority and mosaic settings of `current` are kept. The charblock and
+/// screenblock are set, the background is set to 8bpp, and the size is set to
+/// 32x32.
+///
+/// ```
+/// # use gba::video::*;
+/// let map = [0_u8, 1, 2, 1];
+/// let cnt = bg8_control(BackgroundControl::new(), 2, 1, 31, 3, &map).unwrap();
+/// assert!(cnt.bpp8());
+/// assert_eq!(cnt.charblock(), 1);
+/// assert_eq!(cnt.screenblock(), 31);
+/// // tile 3 doesn't exist
+/// assert_eq!(
+///   bg8_control(BackgroundControl::new(), 2, 1, 31, 3, &[3]),
+///   Err(Bg8Error::TileOutOfRange { index: 0, tile: 3 })
+/// );
+/// // 256 tiles is a full charblock, which is screenblocks 8 to 15
+/// assert_eq!(
+///   bg8_control(BackgroundControl::new(), 0, 1, 12, 256, &map),
+///   Err(Bg8Error::MapOverlapsTiles)
+/// );
+/// ```
+#[inline]
+pub fn bg8_control(
+  current: BackgroundControl, bg: usize, charblock: usize, screenblock: usize,
+  tile_count: usize, map: &[u8],
+) -> Result<BackgroundControl, Bg8Error> {
+  if bg >= 4 {
+    return Err(Bg8Error::BgOutOfRange { bg });
+  }
+  if charblock >= 4 {
+    return Err(Bg8Error::CharblockOutOfRange { charblock });
+  }
+  if screenblock >= 32 {
+    return Err(Bg8Error::ScreenblockOutOfRange { screenblock });
+  }
+  if tile_count > 256 {
+    return Err(Bg8Error::TooManyTiles { count: tile_count });
+  }
+  if map.len() > 32 * 32 {
+    return Err(Bg8Error::MapTooLarge { len: map.len() });
+  }
+  if let Some((index, &tile)) =
+    map.iter().enumerate().find(|(_, &t)| usize::from(t) >= tile_count)
+  {
+    return Err(Bg8Error::TileOutOfRange { index, tile });
+  }
+  let tiles_start = charblock * CHARBLOCK_BYTES;
+  let tiles_end = tiles_start + tile_count * core::mem::size_of::<Tile8>();
+  let map_start = screenblock * SCREENBLOCK_BYTES;
+  let map_end = map_start + SCREENBLOCK_BYTES;
+  if tile_count > 0 && tiles_start < map_end && map_start < tiles_end {
+    return Err(Bg8Error::MapOverlapsTiles);
+  }
+  Ok(
+    current
+      .with_charblock(charblock as u16)
+      .with_screenblock(screenblock as u16)
+      .with_bpp8(true)
+      .with_size(0),
+  )
+}
+
+/// Sets up a text background to show 256 color (8bpp) tiles.
+///
+/// * The tiles are copied to the start of the charblock.
+/// * The palette is copied to the background palette.
+/// * Each byte of `map` is a tile index, and they're written in order to the
+///   screenblock as [`TextEntry`] values with no flipping. Entries past the end
+///   of `map` are left as they were.
+/// * The background's control register is set as described in [`bg8_control`].
+///
+/// The display control isn't changed, so the video mode and which
+/// backgrounds are shown are up to you.
+///
+/// Nothing is written if the arguments have a problem, see [`bg8_control`].
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn setup_bg8(
+  bg: usize, charblock: usize, screenblock: usize, tiles: &[Tile8],
+  palette: &[Color; 256], map: &[u8],
+) -> Result<(), Bg8Error> {
+  let cnt = BGCNT.get(bg).ok_or(Bg8Error::BgOutOfRange { bg })?;
+  let control = setup_bg8_with(
+    cnt.read(),
+    bg,
+    charblock,
+    screenblock,
+    tiles,
+    palette,
+    map,
+    |offset, tiles| unsafe {
+      dma3_copy_u32(
+        tiles.as_ptr().cast(),
+        (CHARBLOCK0_8BPP.as_usize() + offset) as *mut u32,
+        tiles.len() * 16,
+      );
+    },
+    |palette| unsafe {
+      dma3_copy_u16(
+        palette.as_ptr().cast(),
+        BG_PALETTE.as_usize() as *mut u16,
+        palette.len(),
+      );
+    },
+    |i, entry| {
+      let sb = TEXT_SCREENBLOCKS.get_frame(screenblock).unwrap();
+      sb.index(i % 32, i / 32).write(entry);
+    },
+  )?;
+  cnt.write(control);
+  Ok(())
+}
+
+/// Checks the arguments of [`setup_bg8`], then does the uploads through the
+/// given closures and returns the new control value.
+///
+/// `copy_tiles` gets the tiles and the byte offset within VRAM to put them
+/// at, `copy_palette` gets the palette, and `write_entry` gets each map entry
+/// with its index within the screenblock. If any argument has a problem, none
+/// of them are called.
+#[inline]
+#[allow(clippy::too_many_arguments)]
+#[cfg(any(feature = "on_gba", test))]
+fn setup_bg8_with(
+  current: BackgroundControl, bg: usize, charblock: usize, screenblock: usize,
+  tiles: &[Tile8], palette: &[Color; 256], map: &[u8],
+  copy_tiles: impl FnOnce(usize, &[Tile8]),
+  copy_palette: impl FnOnce(&[Color; 256]),
+  mut write_entry: impl FnMut(usize, TextEntry),
+) -> Result<BackgroundControl, Bg8Error> {
+  let control =
+    bg8_control(current, bg, charblock, screenblock, tiles.len(), map)?;
+  copy_tiles(charblock * CHARBLOCK_BYTES, tiles);
+  copy_palette(palette);
+  for (i, &tile) in map.iter().enumerate() {
+    write_entry(i, TextEntry::from_tile(u16::from(tile)));
+  }
+  Ok(control)
+}
+
+/// A problem found by [`mode1_affine_playfield`].
+///
+/// Map lengths are in bytes (one byte per map entry).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AffinePlayfieldError {
+  /// The charblock was 4 or more.
+  CharblockOutOfRange {
+    /// The charblock given.
+    charblock: usize,
+  },
+  /// The screenblock was 32 or more.
+  ScreenblockOutOfRange {
+    /// The screenblock given.
+    screenblock: usize,
+  },
+  /// There were more than 256 tiles, which is all an affine map can use.
+  TooManyTiles {
+    /// The number of tiles given.
+    count: usize,
+  },
+  /// The palette had more than 256 colors.
+  PaletteTooLarge {
+    /// The number of colors given.
+    len: usize,
+  },
+  /// The map length isn't one of the affine map sizes: 256 (16x16), 1024
+  /// (32x32), 4096 (64x64), or 16384 (128x128).
+  BadMapLen {
+    /// The map length given.
+    len: usize,
+  },
+  /// The map data doesn't fit in VRAM, or lands inside of the tile data.
+  MapOverlapsTiles,
+}
+
+/// The register settings made by [`setup_mode1_affine_playfield`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AffinePlayfield {
+  /// Video mode 1, with BG2 shown.
+  pub display: DisplayControl,
+  /// BG2's control.
+  pub bg2: BackgroundControl,
+  /// The `BG2X` value that puts the middle of the map at the middle of the
+  /// screen.
+  pub ref_x: i32fx8,
+  /// The `BG2Y` value that puts the middle of the map at the middle of the
+  /// screen.
+  pub ref_y: i32fx8,
+}
+
+/// Checks the arguments of [`setup_mode1_affine_playfield`] and computes the
+/// register settings.
+///
+/// The background size is picked from the length of the map (in bytes), and the
+/// other control bits are 0 (so BG2 has priority 0 and doesn't wrap).
+///
+/// ```
+/// # use gba::prelude::*;
+/// let p = mode1_affine_playfield(1024, 100, 0, 16).unwrap();
+/// assert_eq!(p.display.video_mode(), VideoMode::_1);
+/// assert!(p.display.show_bg2());
+/// assert_eq!(p.bg2.size(), 1);
+/// assert_eq!(p.bg2.charblock(), 0);
+/// assert_eq!(p.bg2.screenblock(), 16);
+/// // a 256x256 map, with its middle at the middle of the 240x160 screen
+/// assert_eq!(p.ref_x, i32fx8::from_bits(8 << 8));
+/// assert_eq!(p.ref_y, i32fx8::from_bits(48 << 8));
+/// assert_eq!(
+///   mode1_affine_playfield(1000, 100, 0, 16),
+///   Err(AffinePlayfieldError::BadMapLen { len: 1000 })
+/// );
+/// ```
+#[inline]
+pub fn mode1_affine_playfield(
+  map_len: usize, tile_count: usize, charblock: usize, screenblock: usize,
+) -> Result<AffinePlayfield, AffinePlayfieldError> {
+  if charblock >= 4 {
+    return Err(AffinePlayfieldError::CharblockOutOfRange { charblock });
+  }
+  if screenblock >= 32 {
+    return Err(AffinePlayfieldError::ScreenblockOutOfRange { screenblock });
+  }
+  if tile_count > 256 {
+    return Err(AffinePlayfieldError::TooManyTiles { count: tile_count });
+  }
+  let (size, width) = match map_len {
+    256 => (0, 128),
+    1024 => (1, 256),
+    4096 => (2, 512),
+    16384 => (3, 1024),
+    len => return Err(AffinePlayfieldError::BadMapLen { len }),
+  };
+  let tiles_start = charblock * CHARBLOCK_BYTES;
+  let tiles_end = tiles_start + tile_count * core::mem::size_of::<Tile8>();
+  let map_start = screenblock * SCREENBLOCK_BYTES;
+  let map_end = map_start + affine_map_bytes(size);
+  let overlaps =
+    tile_count > 0 && tiles_start < map_end && map_start < tiles_end;
+  if overlaps || map_end > 4 * CHARBLOCK_BYTES {
+    return Err(AffinePlayfieldError::MapOverlapsTiles);
+  }
+  Ok(AffinePlayfield {
+    display: DisplayControl::new()
+      .with_video_mode(VideoMode::_1)
+      .with_show_bg2(true),
+    bg2: BackgroundControl::new()
+      .with_charblock(charblock as u16)
+      .with_screenblock(screenblock as u16)
+      .with_size(size),
+    ref_x: i32fx8::from_bits((width / 2 - 120) << 8),
+    ref_y: i32fx8::from_bits((width / 2 - 80) << 8),
+  })
+}
+
+/// Sets up video mode 1 with BG2 as an affine "playfield", such as a rotating
+/// game board with text backgrounds for the HUD.
+///
+/// * `tiles` are copied to the start of `charblock` (affine backgrounds always
+///   use 8bpp tiles).
+/// * `map` is copied to `screenblock`. Each byte is a tile index, so each `u16`
+///   holds two entries (the low byte is the left one). Taking the map as `u16`
+///   keeps it aligned for the copy, since VRAM can't be written one byte at a
+///   time.
+/// * `palette` is copied to the start of the background palette.
+/// * The display is set to mode 1 with BG2 shown. Other display bits, including
+///   if BG0 and BG1 are shown, are kept.
+/// * BG2's control is set as described in [`mode1_affine_playfield`], its
+///   matrix is set to the identity, and its reference point is set so that the
+///   middle of the map is at the middle of the screen.
+///
+/// To rotate around the middle of the screen later, see
+/// [`Mat2`](crate::fixed::Mat2).
+///
+/// Nothing is written if the arguments have a problem.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn setup_mode1_affine_playfield(
+  tiles: &[Tile8], map: &[u16], palette: &[Color], screenblock: usize,
+  charblock: usize,
+) -> Result<(), AffinePlayfieldError> {
+  if palette.len() > 256 {
+    return Err(AffinePlayfieldError::PaletteTooLarge { len: palette.len() });
+  }
+  let p =
+    mode1_affine_playfield(map.len() * 2, tiles.len(), charblock, screenblock)?;
+  let vram = CHARBLOCK0_8BPP.as_usize();
+  unsafe {
+    dma3_copy_u32(
+      tiles.as_ptr().cast(),
+      (vram + charblock * CHARBLOCK_BYTES) as *mut u32,
+      tiles.len() * 16,
+    );
+    dma3_copy_u16(
+      map.as_ptr(),
+      (vram + screenblock * SCREENBLOCK_BYTES) as *mut u16,
+      map.len(),
+    );
+    dma3_copy_u16(
+      palette.as_ptr().cast(),
+      BG_PALETTE.as_usize() as *mut u16,
+      palette.len(),
+    );
+  }
+  BGCNT.index(2).write(p.bg2);
+  BG2PA.write(i16fx8::from_bits(1 << 8));
+  BG2PB.write(i16fx8::from_bits(0));
+  BG2PC.write(i16fx8::from_bits(0));
+  BG2PD.write(i16fx8::from_bits(1 << 8));
+  BG2X.write(p.ref_x);
+  BG2Y.write(p.ref_y);
+  DISPCNT
+    .write(DISPCNT.read().with_video_mode(VideoMode::_1).with_show_bg2(true));
+  Ok(())
+}
+
+/// A problem found by [`runtime_linker_check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LinkError {
+  /// A function that's supposed to be in IWRAM was linked somewhere else.
+  NotInIwram {
+    /// The name of the function.
+    symbol: &'static str,
+    /// Where the function actually ended up.
+    region: MemoryRegion,
+  },
+}
+
+/// Checks that the program was linked the way this crate expects.
+///
+/// Some of this crate's functions must be placed in IWRAM by the linker
+/// script. If a custom or outdated linker script is used they can silently end
+/// up in ROM instead, which makes them much slower, and makes
+/// [`copy_u8_unchecked`](crate::mem::copy_u8_unchecked) unable to work with
+/// SRAM at all. This looks at the address of that function to catch such a
+/// setup problem.
+///
+/// This is only a debugging aid: call it once at startup in debug builds and
+/// report the error however you like.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn runtime_linker_check() -> Result<(), LinkError> {
+  use crate::mem::copy_u8_unchecked;
+  let addr = copy_u8_unchecked as *const () as usize;
+  match MemoryRegion::of_addr(addr) {
+    MemoryRegion::Iwram => Ok(()),
+    region => {
+      Err(LinkError::NotInIwram { symbol: "copy_u8_unchecked", region })
+    }
+  }
+}
+
+/// Allocates space for 4bpp tiles within a single charblock.
+///
+/// When more than one background shares a charblock, each background's tiles
+/// must go in a different part of it. This keeps track of which of the
+/// charblock's 512 tiles are in use so that separately loaded tile sets don't
+/// overwrite each other.
+///
+/// Each allocation is a contiguous run of tiles, found by first fit. The tile
+/// index returned is relative to the start of the charblock, which is what the
+/// [`TextEntry`] values of a background using that charblock need.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TileArena {
+  charblock: u16,
+  /// One bit per tile, set when that tile is in use.
+  used: [u32; 16],
+  /// One bit per tile, set when that tile is the start of an allocation.
+  starts: [u32; 16],
+}
+impl TileArena {
+  /// The number of 4bpp tiles in a charblock.
+  pub const TILES: usize = 512;
+
+  /// Makes an arena for the given charblock, with all tiles free.
+  ///
+  /// ## Panics
+  /// * If `charblock` is 4 or more.
+  #[inline]
+  #[must_use]
+  #[cfg_attr(feature = "track_caller", track_caller)]
+  pub const fn new(charblock: u16) -> Self {
+    assert!(charblock < 4);
+    Self { charblock, used: [0; 16], starts: [0; 16] }
+  }
+
+  /// The charblock this arena manages.
+  #[inline]
+  #[must_use]
+  pub const fn charblock(&self) -> u16 {
+    self.charblock
+  }
+
+  /// The number of tiles not currently allocated.
+  #[inline]
+  #[must_use]
+  pub fn free_tiles(&self) -> usize {
+    Self::TILES
+      - self.used.iter().map(|w| w.count_ones() as usize).sum::<usize>()
+  }
+
+  #[inline]
+  fn get(bits: &[u32; 16], i: usize) -> bool {
+    bits[i / 32] & (1 << (i % 32)) != 0
+  }
+
+  #[inline]
+  fn set(bits: &mut [u32; 16], i: usize, b: bool) {
+    if b {
+      bits[i / 32] |= 1 << (i % 32);
+    } else {
+      bits[i / 32] &= !(1 << (i % 32));
+    }
+  }
+
+  /// Allocates `count` contiguous tiles, returning the first tile's index.
+  ///
+  /// Returns `None` if `count` is 0 or there's no free run that long.
+  #[inline]
+  pub fn alloc(&mut self, count: usize) -> Option<u16> {
+    if count == 0 {
+      return None;
+    }
+    let mut run_start = 0;
+    let mut run_len = 0;
+    for i in 0..Self::TILES {
+      if Self::get(&self.used, i) {
+        run_start = i + 1;
+        run_len = 0;
+        continue;
+      }
+      run_len += 1;
+      if run_len == count {
+        for t in run_start..run_start + count {
+          Self::set(&mut self.used, t, true);
+        }
+        Self::set(&mut self.starts, run_start, true);
+        return Some(run_start as u16);
+      }
+    }
+    None
+  }
+
+  /// Frees an allocation, given the index that [`alloc`](Self::alloc) or
+  /// [`load`](Self::load) returned for it.
+  ///
+  /// This doesn't change the tile data in VRAM.
+  ///
+  /// ## Panics
+  /// * If `base` isn't the start of a current allocation.
+  #[inline]
+  #[cfg_attr(feature = "track_caller", track_caller)]
+  pub fn free(&mut self, base: u16) {
+    let base = usize::from(base);
+    assert!(
+      base < Self::TILES && Self::get(&self.starts, base),
+      "not the start of an allocation"
+    );
+    Self::set(&mut self.starts, base, false);
+    let mut i = base;
+    while i < Self::TILES
+      && Self::get(&self.used, i)
+      && (i == base || !Self::get(&self.starts, i))
+    {
+      Self::set(&mut self.used, i, false);
+      i += 1;
+    }
+  }
+
+  /// Allocates space for `tiles` and copies them into VRAM.
+  ///
+  /// Returns the index of the first tile, or `None` if there wasn't room (in
+  /// which case nothing is copied).
+  #[inline]
+  #[cfg(feature = "on_gba")]
+  pub fn load(&mut self, tiles: &[Tile4]) -> Option<u16> {
+    let base = self.alloc(tiles.len())?;
+    let dest = CHARBLOCK0_4BPP.as_usize()
+      + usize::from(self.charblock) * CHARBLOCK_BYTES
+      + usize::from(base) * core::mem::size_of::<Tile4>();
+    unsafe {
+      dma3_copy_u32(tiles.as_ptr().cast(), dest as *mut u32, tiles.len() * 8)
+    };
+    Some(base)
+  }
+}
+
+/// A "spotlight" transition using window 0.
+///
+/// The spotlight is a rectangle or circle around a center point, and
+/// [`tick`](Spotlight::tick) moves its radius toward a target each frame.
+/// Shrinking the radius to 0 closes the scene off completely, and growing it
+/// opens the scene back up.
+///
+/// This only sets window 0's bounds. For the effect to show, also enable window
+/// 0 in [`DisplayControl`], and use [`WindowInside`] and [`WindowOutside`] to
+/// pick which layers show inside and outside the spotlight.
+/// * **Rectangle:** Call [`apply_rect`](Spotlight::apply_rect) each frame. The
+///   rectangle has the proportions of the screen: it's `2 * radius` wide and
+///   two thirds of that tall, so a radius of 120 centered on the screen covers
+///   the whole screen.
+/// * **Circle:** Each scanline needs its own horizontal bounds. Each frame,
+///   fill a table with [`circle_table`](Spotlight::circle_table) and use
+///   [`hblank_dma0_u16`](crate::dma::hblank_dma0_u16) to copy it into `WIN0H`,
+///   with `WIN0V` set to the full screen height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Spotlight {
+  /// The center, in screen pixels.
+  pub center_x: i16,
+  /// The center, in screen pixels.
+  pub center_y: i16,
+  radius: u16,
+  target: u16,
+  speed: u16,
+}
+impl Spotlight {
+  /// Makes a spotlight with the given center and starting radius, which
+  /// doesn't move until a target is set.
+  #[inline]
+  #[must_use]
+  pub const fn new(center_x: i16, center_y: i16, radius: u16) -> Self {
+    Self { center_x, center_y, radius, target: radius, speed: 0 }
+  }
+
+  /// The current radius.
+  #[inline]
+  #[must_use]
+  pub const fn radius(&self) -> u16 {
+    self.radius
+  }
+
+  /// Sets the radius to move toward, and how many pixels per frame to move.
+  #[inline]
+  pub fn set_target(&mut self, target: u16, speed: u16) {
+    self.target = target;
+    self.speed = speed;
+  }
+
+  /// If the radius has reached the target.
+  #[inline]
+  #[must_use]
+  pub const fn is_done(&self) -> bool {
+    self.radius == self.target
+  }
+
+  /// Moves the radius one frame's worth toward the target.
+  ///
+  /// Returns `true` once the target has been reached.
+  #[inline]
+  pub fn tick(&mut self) -> bool {
+    if self.radius < self.target {
+      self.radius = self.radius.saturating_add(self.speed).min(self.target);
+    } else {
+      self.radius = self.radius.saturating_sub(self.speed).max(self.target);
+    }
+    self.is_done()
+  }
+
+  /// The `WIN0H` and `WIN0V` values for the rectangle shape, clipped to the
+  /// screen.
+  #[inline]
+  #[must_use]
+  pub fn rect_bounds(&self) -> (u8x2, u8x2) {
+    let half_w = i32::from(self.radius);
+    let half_h = half_w * 2 / 3;
+    let (x, y) = (i32::from(self.center_x), i32::from(self.center_y));
+    (
+      window_span(x - half_w, x + half_w, 240),
+      window_span(y - half_h, y + half_h, 160),
+    )
+  }
+
+  /// Fills a table of per-scanline `WIN0H` values for the circle shape.
+  ///
+  /// Scanlines that the circle doesn't touch get an empty span.
+  #[inline]
+  pub fn circle_table(&self, out: &mut [u16; 160]) {
+    let r = u32::from(self.radius);
+    let (x, y) = (i32::from(self.center_x), i32::from(self.center_y));
+    for (row, entry) in out.iter_mut().enumerate() {
+      let dy = (row as i32 - y).unsigned_abs();
+      *entry = if dy > r {
+        0
+      } else {
+        // the radius is a `u16`, so its square always fits in a `u32`.
+        let half = isqrt(r * r - dy * dy) as i32;
+        let span = window_span(x - half, x + half, 240);
+        u16::from(span.high()) << 8 | u16::from(span.low())
+      };
+    }
+  }
+
+  /// Writes the rectangle shape's bounds to `WIN0H` and `WIN0V`.
+  #[inline]
+  #[cfg(feature = "on_gba")]
+  pub fn apply_rect(&self) {
+    let (h, v) = self.rect_bounds();
+    WIN0H.write(h);
+    WIN0V.write(v);
+  }
+}
+
+/// The `WIN0H` and `WIN0V` values for a letterbox with bars `bar_height`
+/// pixels tall at the top and bottom of the screen.
+///
+/// Window 0 covers the full width of the screen, and the rows between the
+/// bars. A `bar_height` of 80 or more leaves no rows at all.
+///
+/// ```
+/// # use gba::video::letterbox_bounds;
+/// let (h, v) = letterbox_bounds(20);
+/// assert_eq!((h.high(), h.low()), (0, 240));
+/// assert_eq!((v.high(), v.low()), (20, 140));
+/// let (_, v) = letterbox_bounds(0);
+/// assert_eq!((v.high(), v.low()), (0, 160));
+/// let (_, v) = letterbox_bounds(200);
+/// assert_eq!((v.high(), v.low()), (0, 0));
+/// ```
+#[inline]
+#[must_use]
+pub fn letterbox_bounds(bar_height: u8) -> (u8x2, u8x2) {
+  let bar = i32::from(bar_height);
+  (window_span(0, 240, 240), window_span(bar, 160 - bar, 160))
+}
+
+/// Letterboxes the screen, such as for a cutscene.
+///
+/// This uses window 0 to cover the band between the bars (see
+/// [`letterbox_bounds`]), with all backgrounds, objects, and effects shown
+/// inside of it. Outside of window 0 nothing is shown, so the bars are the
+/// backdrop color ([`BACKDROP_COLOR`], usually black for this).
+///
+/// This also turns on window 0 in [`DISPCNT`] and sets [`WINOUT`], and the
+/// window 0 half of [`WININ`]. To slide the bars in or out, call this once and
+/// then change the height each frame with [`set_letterbox`].
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn letterbox(bar_height: u8) {
+  set_letterbox(bar_height);
+  WININ.write(
+    WININ
+      .read()
+      .with_win0_bg0(true)
+      .with_win0_bg1(true)
+      .with_win0_bg2(true)
+      .with_win0_bg3(true)
+      .with_win0_obj(true)
+      .with_win0_effect(true),
+  );
+  WINOUT.write(WindowOutside::new());
+  DISPCNT.write(DISPCNT.read().with_enable_win0(true));
+}
+
+/// Changes the bar height of a [`letterbox`], leaving everything else alone.
+///
+/// This only writes `WIN0H` and `WIN0V`, so it's cheap enough to call every
+/// frame (during vblank) to animate the bars.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn set_letterbox(bar_height: u8) {
+  let (h, v) = letterbox_bounds(bar_height);
+  WIN0H.write(h);
+  WIN0V.write(v);
+}
+
+/// The `WIN0H` and `WIN0V` values for a window covering the given rectangle.
+///
+/// All four edges are inclusive pixel coordinates. The hardware stores the
+/// right and bottom edges plus one, so that's done here (saturating at 255).
+///
+/// ```
+/// # use gba::video::window_rect_bounds;
+/// let (h, v) = window_rect_bounds(8, 16, 231, 143);
+/// assert_eq!((h.high(), h.low()), (8, 232));
+/// assert_eq!((v.high(), v.low()), (16, 144));
+/// let (h, v) = window_rect_bounds(0, 0, 239, 159);
+/// assert_eq!((h.high(), h.low()), (0, 240));
+/// assert_eq!((v.high(), v.low()), (0, 160));
+/// ```
+#[inline]
+#[must_use]
+pub fn window_rect_bounds(
+  left: u8, top: u8, right: u8, bottom: u8,
+) -> (u8x2, u8x2) {
+  (
+    u8x2::default().with_high(left).with_low(right.saturating_add(1)),
+    u8x2::default().with_high(top).with_low(bottom.saturating_add(1)),
+  )
+}
+
+/// Sets window 0 to cover the given rectangle.
+///
+/// All four edges are inclusive pixel coordinates (see
+/// [`window_rect_bounds`]). This doesn't turn the window on, for that use
+/// [`enable_window0`].
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn set_window0_rect(left: u8, top: u8, right: u8, bottom: u8) {
+  let (h, v) = window_rect_bounds(left, top, right, bottom);
+  WIN0H.write(h);
+  WIN0V.write(v);
+}
+
+/// Turns on window 0 in [`DISPCNT`], and sets which layers show inside of it
+/// and outside of all windows.
+///
+/// `inside` is written to [`WININ`] as is, so its window 1 half takes effect
+/// too if window 1 is also on.
+///
+/// ```no_run
+/// # use gba::prelude::*;
+/// // a spotlight: only BG0 and objects show in the rectangle, only BG1 outside
+/// set_window0_rect(40, 40, 199, 119);
+/// enable_window0(
+///   WindowInside::new().with_win0_bg0(true).with_win0_obj(true),
+///   WindowOutside::new().with_outside_bg1(true),
+/// );
+/// ```
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn enable_window0(inside: WindowInside, outside: WindowOutside) {
+  WININ.write(inside);
+  WINOUT.write(outside);
+  DISPCNT.write(DISPCNT.read().with_enable_win0(true));
+}
+
+/// Window bounds covering `low .. high`, clipped to `0 .. limit`, in the
+/// format of the window registers (start in the high byte, end+1 in the low).
+///
+/// A span that's entirely off screen is empty.
+#[inline]
+fn window_span(low: i32, high: i32, limit: i32) -> u8x2 {
+  let start = low.clamp(0, limit);
+  let end = high.clamp(0, limit);
+  if start >= end {
+    return u8x2::default();
+  }
+  u8x2::default().with_high(start as u8).with_low(end as u8)
+}
+
+/// The integer square root, rounded down.
+#[inline]
+const fn isqrt(n: u32) -> u32 {
+  let mut x = 0;
+  let mut bit = 1 << 30;
+  let mut n = n;
+  while bit > n {
+    bit >>= 2;
+  }
+  while bit != 0 {
+    if n >= x + bit {
+      n -= x + bit;
+      x = (x >> 1) + bit;
+    } else {
+      x >>= 1;
+    }
+    bit >>= 2;
+  }
+  x
+}
+
+/// Animates the background and object mosaic sizes separately.
+///
+/// Each of the two sizes steps by 1 toward its own target every
+/// `frames_per_step` frames. The size is the number of *extra* pixels in each
+/// direction (`0..=15`), so a size of 0 means no mosaic, and both the
+/// horizontal and vertical amounts are set to the same value.
+///
+/// For example, to make the objects "teleport away" while the background stays
+/// sharp, set an object target of 15 and leave the background target at 0.
+/// Remember that mosaic only affects backgrounds and objects that have their
+/// own mosaic bit set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MosaicAnimator {
+  bg: u8,
+  bg_target: u8,
+  obj: u8,
+  obj_target: u8,
+  frames_per_step: u8,
+  frame: u8,
+}
+impl MosaicAnimator {
+  /// Makes an animator with both sizes at 0.
+  ///
+  /// A `frames_per_step` of 0 is treated as 1.
+  #[inline]
+  #[must_use]
+  pub const fn new(frames_per_step: u8) -> Self {
+    Self {
+      bg: 0,
+      bg_target: 0,
+      obj: 0,
+      obj_target: 0,
+      frames_per_step,
+      frame: 0,
+    }
+  }
+
+  /// Sets the background size to move toward, clamped to `0..=15`.
+  #[inline]
+  pub fn set_bg_target(&mut self, size: u8) {
+    self.bg_target = size.min(15);
+  }
+
+  /// Sets the object size to move toward, clamped to `0..=15`.
+  #[inline]
+  pub fn set_obj_target(&mut self, size: u8) {
+    self.obj_target = size.min(15);
+  }
+
+  /// The current background size.
+  #[inline]
+  #[must_use]
+  pub const fn bg_size(&self) -> u8 {
+    self.bg
+  }
+
+  /// The current object size.
+  #[inline]
+  #[must_use]
+  pub const fn obj_size(&self) -> u8 {
+    self.obj
+  }
+
+  /// If both sizes have reached their targets.
+  #[inline]
+  #[must_use]
+  pub const fn is_done(&self) -> bool {
+    self.bg == self.bg_target && self.obj == self.obj_target
+  }
+
+  /// The `MOSAIC` register value for the current sizes.
+  #[inline]
+  #[must_use]
+  pub const fn mosaic(&self) -> Mosaic {
+    let bg = self.bg as u16;
+    let obj = self.obj as u16;
+    Mosaic::new()
+      .with_bg_h_extra(bg)
+      .with_bg_v_extra(bg)
+      .with_obj_h_extra(obj)
+      .with_obj_v_extra(obj)
+  }
+
+  /// Advances the animation by one frame, returning the new register value.
+  ///
+  /// This doesn't touch the hardware, [`tick`](MosaicAnimator::tick) calls
+  /// this and then writes `MOSAIC`.
+  #[inline]
+  pub fn advance(&mut self) -> Mosaic {
+    self.frame += 1;
+    if self.frame >= self.frames_per_step {
+      self.frame = 0;
+      self.bg = step_toward(self.bg, self.bg_target);
+      self.obj = step_toward(self.obj, self.obj_target);
+    }
+    self.mosaic()
+  }
+
+  /// Advances the animation by one frame and writes `MOSAIC`.
+  #[inline]
+  #[cfg(feature = "on_gba")]
+  pub fn tick(&mut self) {
+    MOSAIC.write(self.advance());
+  }
+}
+
+/// Moves `x` one step toward `target`.
+#[inline]
+const fn step_toward(x: u8, target: u8) -> u8 {
+  if x < target {
+    x + 1
+  } else if x > target {
+    x - 1
+  } else {
+    x
+  }
+}
+
+/// Animates a tile by rewriting its pixel data in VRAM.
+///
+/// Every map entry (or object) that uses the tile changes along with it, so
+/// this is an easy way to animate things like water or torches across a whole
+/// background at once.
+///
+/// The frames are shown in order, each for `frames_per_step` video frames,
+/// and then the animation loops back to the first frame.
+///
+/// ```
+/// # use gba::video::*;
+/// let frames = [[0_u32; 8], [1; 8], [2; 8]];
+/// let mut anim = TileAnimator::new(&frames, 0, 10, 2);
+/// assert_eq!(anim.current_frame(), 0);
+/// assert_eq!(anim.advance(), None);
+/// assert_eq!(anim.advance(), Some(&[1; 8]));
+/// anim.advance();
+/// assert_eq!(anim.advance(), Some(&[2; 8]));
+/// anim.advance();
+/// assert_eq!(anim.advance(), Some(&[0; 8]));
+/// assert_eq!(anim.current_frame(), 0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileAnimator<'a> {
+  frames: &'a [Tile4],
+  charblock: u16,
+  tile: u16,
+  frames_per_step: u8,
+  timer: u8,
+  current: usize,
+}
+impl<'a> TileAnimator<'a> {
+  /// Makes an animator that writes `frames` to one 4bpp tile of a charblock,
+  /// starting at the first frame.
+  ///
+  /// A `frames_per_step` of 0 is treated as 1.
+  ///
+  /// ## Panics
+  /// * If `frames` is empty.
+  /// * If `charblock` is 6 or more, or `tile` is 512 or more. Charblocks 4 and
+  ///   5 are object tile memory.
+  #[inline]
+  #[must_use]
+  #[cfg_attr(feature = "track_caller", track_caller)]
+  pub const fn new(
+    frames: &'a [Tile4], charblock: u16, tile: u16, frames_per_step: u8,
+  ) -> Self {
+    assert!(!frames.is_empty());
+    assert!(charblock < 6);
+    assert!(tile < 512);
+    Self { frames, charblock, tile, frames_per_step, timer: 0, current: 0 }
+  }
+
+  /// The index of the frame currently shown.
+  #[inline]
+  #[must_use]
+  pub const fn current_frame(&self) -> usize {
+    self.current
+  }
+
+  /// Sets how many video frames each animation frame lasts.
+  ///
+  /// A `frames_per_step` of 0 is treated as 1.
+  #[inline]
+  pub fn set_frames_per_step(&mut self, frames_per_step: u8) {
+    self.frames_per_step = frames_per_step;
+  }
+
+  /// Jumps to a frame, which is shown for a full step.
+  ///
+  /// The new frame isn't written until the next [`tick`](TileAnimator::tick),
+  /// use [`write_current`](TileAnimator::write_current) to show it now.
+  ///
+  /// ## Panics
+  /// * If `frame` is out of bounds.
+  #[inline]
+  #[cfg_attr(feature = "track_caller", track_caller)]
+  pub fn set_frame(&mut self, frame: usize) {
+    assert!(frame < self.frames.len());
+    self.current = frame;
+    self.timer = 0;
+  }
+
+  /// The address of the animated tile in VRAM.
+  #[inline]
+  #[must_use]
+  pub const fn tile_addr(&self) -> usize {
+    0x0600_0000
+      + self.charblock as usize * CHARBLOCK_BYTES
+      + self.tile as usize * core::mem::size_of::<Tile4>()
+  }
+
+  /// Advances the animation by one video frame.
+  ///
+  /// When that moves to a new animation frame, the new frame's data is
+  /// returned. This doesn't touch the hardware, [`tick`](TileAnimator::tick)
+  /// calls this and then writes the tile.
+  #[inline]
+  pub fn advance(&mut self) -> Option<&'a Tile4> {
+    self.timer += 1;
+    if self.timer < self.frames_per_step {
+      return None;
+    }
+    self.timer = 0;
+    self.current = (self.current + 1) % self.frames.len();
+    Some(&self.frames[self.current])
+  }
+
+  /// Writes the current frame to the tile.
+  #[inline]
+  #[cfg(feature = "on_gba")]
+  pub fn write_current(&self) {
+    let tile = &self.frames[self.current];
+    unsafe { VolAddress::<Tile4, Safe, Safe>::new(self.tile_addr()) }
+      .write(*tile);
+  }
+
+  /// Advances the animation by one video frame, writing the tile if the frame
+  /// changed.
+  ///
+  /// Call this once per frame, during vblank.
+  #[inline]
+  #[cfg(feature = "on_gba")]
+  pub fn tick(&mut self) {
+    if self.advance().is_some() {
+      self.write_current();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  extern crate std;
+  use super::*;
+
+  #[test]
+  fn bg0_unavailable_in_mode_2() {
+    let display =
+      DisplayControl::new().with_video_mode(VideoMode::_2).with_show_bg0(true);
+    let bgs = [BackgroundControl::new(); 4];
+    assert_eq!(
+      check_bg_config(display, bgs),
+      Err(VideoConfigError::BgUnavailableInMode { bg: 0, mode: VideoMode::_2 })
+    );
+    // the same background is fine in mode 0
+    let display = display.with_video_mode(VideoMode::_0);
+    let bgs = [BackgroundControl::new().with_screenblock(31); 4];
+    assert_eq!(check_bg_config(display, bgs), Ok(()));
+  }
+
+  #[test]
+  fn scene_layout() {
+    let tiles = [Tile4::default(); 10];
+    let map = [TextEntry::new(); 1500];
+    let palette = [Color::WHITE; 16];
+    let scene = Scene { tiles: &tiles, map: &map, palette: &palette };
+    let layout = scene.layout(1, 28);
+    assert_eq!(layout.tile_offset, 0x4000);
+    assert_eq!(layout.map_offset, 0xE000);
+    assert_eq!(layout.size, 1);
+  }
+
+  #[test]
+  #[should_panic]
+  fn scene_layout_map_past_vram() {
+    let map = [TextEntry::new(); 2048];
+    let scene = Scene { tiles: &[], map: &map, palette: &[] };
+    let _ = scene.layout(0, 31);
+  }
+
+  /// A pretend screenblock where each entry holds its own position.
+  fn numbered_screenblock() -> [[TextEntry; 32]; 32] {
+    core::array::from_fn(|y| {
+      core::array::from_fn(|x| TextEntry::from_tile((y * 32 + x) as u16))
+    })
+  }
+
+  #[test]
+  fn copy_text_rect_2x2() {
+    let src = numbered_screenblock();
+    let mut dst = [[TextEntry::new(); 32]; 32];
+    copy_rect_order((3, 4), (10, 20), (2, 2), false, |s, d| {
+      dst[d.1][d.0] = src[s.1][s.0];
+    });
+    assert_eq!(dst[20][10], src[4][3]);
+    assert_eq!(dst[20][11], src[4][4]);
+    assert_eq!(dst[21][10], src[5][3]);
+    assert_eq!(dst[21][11], src[5][4]);
+    let written = dst.iter().flatten().filter(|e| **e != TextEntry::new());
+    assert_eq!(written.count(), 4);
+  }
+
+  #[test]
+  fn copy_text_rect_overlapping_and_clipped() {
+    let start = numbered_screenblock();
+    let mut sb = start;
+    // shift a 3x3 block one step down and right within one screenblock
+    copy_rect_order((0, 0), (1, 1), (3, 3), true, |s, d| {
+      sb[d.1][d.0] = sb[s.1][s.0];
+    });
+    for y in 0..3 {
+      for x in 0..3 {
+        assert_eq!(sb[y + 1][x + 1], start[y][x]);
+      }
+    }
+    // a rectangle running past the edge only copies what fits
+    let mut count = 0;
+    copy_rect_order((30, 0), (0, 0), (5, 1), false, |_, _| count += 1);
+    assert_eq!(count, 2);
+  }
+
+  #[test]
+  fn text_bg_registers_two_bgs() {
+    let configs = [
+      TextBgConfig {
+        bg: 0,
+        screenblock: 31,
+        priority: 1,
+        ..Default::default()
+      },
+      TextBgConfig {
+        bg: 2,
+        charblock: 1,
+        screenblock: 28,
+        priority: 3,
+        size: 1,
+        bpp8: true,
+      },
+    ];
+    let (display, bgs) = text_bg_registers(&configs).unwrap();
+    assert_eq!(display.0, 0x0500);
+    assert_eq!(bgs.map(|bg| bg.0), [0x1F01, 0, 0x5C87, 0]);
+    // a map in the charblock that has the tiles is rejected
+    let configs = [TextBgConfig { bg: 1, ..Default::default() }];
+    assert_eq!(
+      text_bg_registers(&configs),
+      Err(VideoConfigError::ScreenblockInCharblock { bg: 1, tiles_of: 1 })
+    );
+  }
+
+  #[test]
+  fn unpack_1bpp_nibbles() {
+    let src = [0x01, 0x80, 0xFF, 0x00, 0x0F, 0xF0, 0x55, 0xAA];
+    // only the low 4 bits of the background index are used
+    let tile = unpack_1bpp_to_tile4(&src, 3, 0x1C);
+    assert_eq!(
+      tile,
+      [
+        0xCCCCCCC3, 0x3CCCCCCC, 0x33333333, 0xCCCCCCCC, 0xCCCC3333, 0x3333CCCC,
+        0xC3C3C3C3, 0x3C3C3C3C
+      ]
+    );
+  }
+
+  #[test]
+  fn blend_coefficients_clamp_and_pack() {
+    let packed = blend_alpha(5, 20);
+    assert_eq!((packed.low(), packed.high()), (5, 16));
+    assert_eq!(<[u8; 2]>::from(blend_alpha(20, 3)), [16, 3]);
+    assert_eq!(<[u8; 2]>::from(blend_alpha(16, 0)), [16, 0]);
+    assert_eq!(blend_brightness(20), 16);
+    assert_eq!(blend_brightness(16), 16);
+    assert_eq!(blend_brightness(7), 7);
+  }
+
+  #[test]
+  fn tile_arena_alloc_free_reuse() {
+    let mut arena = TileArena::new(2);
+    assert_eq!(arena.alloc(0), None);
+    assert_eq!(arena.alloc(100), Some(0));
+    assert_eq!(arena.alloc(50), Some(100));
+    assert_eq!(arena.alloc(200), Some(150));
+    assert_eq!(arena.free_tiles(), 512 - 350);
+    // more than is left is refused, and changes nothing
+    assert_eq!(arena.alloc(163), None);
+    assert_eq!(arena.free_tiles(), 162);
+    // freeing the middle allocation doesn't touch its neighbors
+    arena.free(100);
+    assert_eq!(arena.free_tiles(), 212);
+    // first fit reuses the hole, and the rest goes after the last allocation
+    assert_eq!(arena.alloc(30), Some(100));
+    assert_eq!(arena.alloc(30), Some(350));
+    assert_eq!(arena.alloc(20), Some(130));
+    assert_eq!(arena.alloc(132), Some(380));
+    assert_eq!(arena.free_tiles(), 0);
+    assert_eq!(arena.alloc(1), None);
+    // freeing every allocation gets the whole charblock back
+    for base in [0, 100, 130, 150, 350, 380] {
+      arena.free(base);
+    }
+    assert_eq!(arena.alloc(512), Some(0));
+  }
+
+  #[test]
+  #[should_panic = "not the start of an allocation"]
+  fn tile_arena_free_not_allocated() {
+    let mut arena = TileArena::new(0);
+    let base = arena.alloc(8).unwrap();
+    arena.free(base + 1);
+  }
+
+  #[test]
+  fn tile16_entries_for_a_glyph() {
+    let entries = tile16_entries(40, 3);
+    assert_eq!(entries.map(|e| e.tile()), [40, 41, 42, 43]);
+    assert!(entries
+      .iter()
+      .all(|e| e.palbank() == 3 && !e.hflip() && !e.vflip()));
+    assert_eq!(entries.map(|e| e.0), [0x3028, 0x3029, 0x302A, 0x302B]);
+  }
+
+  #[test]
+  fn sine_scroll_table_entries() {
+    let mut table = [0xAAAA_u16; 160];
+    sine_scroll_table(8, 32, 0, &mut table);
+    assert_eq!(table[0], 0);
+    // an eighth of a turn, 8 * 0.7071 rounds to 6
+    assert_eq!(table[4], 6);
+    assert_eq!(table[8], 8);
+    assert_eq!(table[16], 0);
+    // negative offsets wrap around
+    assert_eq!(table[24], -8_i16 as u16);
+    assert_eq!(table[32..64], table[..32]);
+    // the phase shifts the whole wave
+    sine_scroll_table(8, 32, 0x4000, &mut table);
+    assert_eq!((table[0], table[8], table[16]), (8, 0, -8_i16 as u16));
+  }
+
+  #[test]
+  fn spotlight_rect_shrinks() {
+    let bounds = |s: &Spotlight| {
+      let (h, v) = s.rect_bounds();
+      ((h.high(), h.low()), (v.high(), v.low()))
+    };
+    let mut spot = Spotlight::new(120, 80, 120);
+    spot.set_target(0, 50);
+    assert_eq!(bounds(&spot), ((0, 240), (0, 160)));
+    assert!(!spot.tick());
+    assert_eq!(bounds(&spot), ((50, 190), (34, 126)));
+    assert!(!spot.tick());
+    assert_eq!(bounds(&spot), ((100, 140), (67, 93)));
+    // the last step stops at the target, which is an empty window
+    assert!(spot.tick());
+    assert_eq!(spot.radius(), 0);
+    assert_eq!(bounds(&spot), ((0, 0), (0, 0)));
+  }
+
+  #[test]
+  fn spotlight_circle_table() {
+    let mut table = [0xAAAA; 160];
+    Spotlight::new(100, 50, 10).circle_table(&mut table);
+    // the very top and bottom of the circle are zero width
+    assert_eq!(table[40], 0);
+    assert_eq!(table[41], 96 << 8 | 104);
+    assert_eq!(table[50], 90 << 8 | 110);
+    assert_eq!(table[56], 92 << 8 | 108);
+    assert_eq!(table[60], 0);
+    // a huge radius covers the whole screen without overflowing
+    Spotlight::new(0, 0, u16::MAX).circle_table(&mut table);
+    assert!(table.iter().all(|&e| e == 240));
+  }
+
+  #[test]
+  fn mosaic_sizes_advance_independently() {
+    let mut mosaic = MosaicAnimator::new(2);
+    mosaic.set_obj_target(3);
+    mosaic.set_bg_target(1);
+    let mut sizes = [(0, 0); 8];
+    for size in sizes.iter_mut() {
+      mosaic.advance();
+      *size = (mosaic.bg_size(), mosaic.obj_size());
+    }
+    assert_eq!(
+      sizes,
+      [(0, 0), (1, 1), (1, 1), (1, 2), (1, 2), (1, 3), (1, 3), (1, 3)]
+    );
+    assert!(mosaic.is_done());
+    let reg = mosaic.mosaic();
+    assert_eq!((reg.bg_h_extra(), reg.bg_v_extra()), (1, 1));
+    assert_eq!((reg.obj_h_extra(), reg.obj_v_extra()), (3, 3));
+    // targets are clamped, and each size heads back down on its own
+    mosaic.set_obj_target(200);
+    mosaic.set_bg_target(0);
+    let mut mosaic = MosaicAnimator { frames_per_step: 0, ..mosaic };
+    let reg = mosaic.advance();
+    assert_eq!((reg.bg_h_extra(), reg.obj_h_extra()), (0, 4));
+    for _ in 0..20 {
+      mosaic.advance();
+    }
+    assert_eq!((mosaic.bg_size(), mosaic.obj_size()), (0, 15));
+  }
+
+  #[test]
+  fn text_entry_addr_in_last_screenblock() {
+    assert_eq!(text_entry_addr(31, 5, 5), 0x0600_F94A);
+    assert_eq!(text_entry_addr(0, 0, 0), 0x0600_0000);
+    assert_eq!(text_entry_addr(31, 31, 31), 0x0601_0000 - 2);
+  }
+
+  #[test]
+  #[should_panic = "position outside of the 32x32 screenblock"]
+  fn text_entry_addr_outside_screenblock() {
+    let _ = text_entry_addr(31, 32, 5);
+  }
+
+  #[test]
+  fn centered_text_columns() {
+    assert_eq!(text_width_tiles("0123456789"), 10);
+    assert_eq!(centered_text_col("0123456789"), 10);
+    assert_eq!(centered_text_col(""), 15);
+    assert_eq!(centered_text_col("odd"), 13);
+    let full = "012345678901234567890123456789";
+    assert_eq!(centered_text_col(full), 0);
+    // too long to fit means left aligned
+    assert_eq!(centered_text_col(&std::format!("{full}!")), 0);
+    assert_eq!(text_width_tiles(&"x".repeat(70_000)), u16::MAX);
+  }
+
+  #[test]
+  fn digit_tiles_right_aligned() {
+    let zero = u16::from(b'0');
+    let mut tiles = [0xFFFF; 4];
+    u32_digit_tiles(42, zero, &mut tiles);
+    assert_eq!(tiles, [0, 0, zero + 4, zero + 2]);
+    // zeroes within the number are drawn, only leading ones are blank
+    u32_digit_tiles(1005, 16, &mut tiles);
+    assert_eq!(tiles, [17, 16, 16, 21]);
+    u32_digit_tiles(0, 16, &mut tiles);
+    assert_eq!(tiles, [0, 0, 0, 16]);
+    u32_digit_tiles(u32::MAX, 16, &mut tiles);
+    assert_eq!(tiles, [16 + 7, 16 + 2, 16 + 9, 16 + 5]);
+    let mut tiles = [0xFFFF; 10];
+    u32_digit_tiles(u32::MAX, 0, &mut tiles);
+    assert_eq!(tiles, [4, 2, 9, 4, 9, 6, 7, 2, 9, 5]);
+  }
+
+  #[test]
+  fn display_timing_decode_vblank_line_170() {
+    // vblank and vcount match flags, the vblank IRQ on, and a setting of 170
+    let timing = DisplayTiming::decode(DisplayStatus(0xAA0D), 170);
+    assert_eq!(
+      timing,
+      DisplayTiming {
+        in_vblank: true,
+        in_hblank: false,
+        vcount_match: true,
+        current_line: 170
+      }
+    );
+    // only the low byte of VCOUNT is the line
+    let timing = DisplayTiming::decode(DisplayStatus(0x0002), 0xFF00 | 35);
+    assert!(!timing.in_vblank && timing.in_hblank && !timing.vcount_match);
+    assert_eq!(timing.current_line, 35);
+  }
+
+  #[test]
+  fn reflection_palette_is_darker() {
+    let base: [Color; 32] =
+      core::array::from_fn(|i| Color::from_rgb(i as u16, 31 - i as u16, 17));
+    let mut out = [Color::MAGENTA; 33];
+    reflection_palette(&base, &mut out, 2);
+    for (o, c) in out.iter().zip(base.iter()) {
+      assert_eq!(o.red(), c.red() / 4);
+      assert_eq!(o.green(), c.green() / 4);
+      assert_eq!(o.blue(), 4);
+    }
+    // the extra output color isn't touched
+    assert_eq!(out[32], Color::MAGENTA);
+    reflection_palette(&base, &mut out, 0);
+    assert_eq!(out[..32], base);
+    reflection_palette(&base, &mut out, 200);
+    assert!(out[..32].iter().all(|&c| c == Color::BLACK));
+  }
+
+  #[test]
+  fn crossfade_resumes_by_rows() {
+    // two solid pretend bitmaps, faded into a pretend VRAM 40 rows at a time
+    let a = std::vec![Color::from_rgb(30, 0, 10); 240 * 160];
+    let b = std::vec![Color::from_rgb(0, 20, 10); 240 * 160];
+    let mut vram = std::vec![Color::BLACK; 240 * 160];
+    let mut fade = Mode3Crossfade::new(8);
+    let mut calls = 0;
+    while !fade.is_done() {
+      let rows = fade.next_rows(40);
+      assert_eq!(rows.start, calls * 40);
+      let range = rows.start * 240..rows.end * 240;
+      crossfade_row(
+        &a[range.clone()],
+        &b[range.clone()],
+        fade.t(),
+        &mut vram[range],
+      );
+      calls += 1;
+      // rows past the ones done so far are still untouched
+      assert!(vram[fade.row() * 240..].iter().all(|&c| c == Color::BLACK));
+    }
+    assert_eq!(calls, 4);
+    assert!(vram.iter().all(|&c| c == Color::from_rgb(15, 10, 10)));
+    // an uneven step size still ends exactly on the last row
+    let mut fade = Mode3Crossfade::new(16);
+    assert_eq!(fade.next_rows(150), 0..150);
+    assert_eq!(fade.next_rows(usize::MAX), 150..160);
+    assert_eq!(fade.row(), 160);
+  }
+
+  #[test]
+  fn dedup_tiles_collapses_duplicates() {
+    let tiles: [Tile4; 5] = [[7; 8], [1; 8], [7; 8], [2; 8], [1; 8]];
+    let mut map = [4, 0, 2, 3, 1, 2, 4];
+    let mut unique = [[0; 8]; 3];
+    assert_eq!(dedup_tiles(&tiles, &mut unique, &mut map), Some(3));
+    assert_eq!(map, [0, 1, 1, 2, 0, 1, 0]);
+    assert_eq!(unique, [[1; 8], [7; 8], [2; 8]]);
+    // too many unique tiles for the buffer leaves the map alone
+    let mut map = [0, 1, 3];
+    let mut small = [[0; 8]; 2];
+    assert_eq!(dedup_tiles(&tiles, &mut small, &mut map), None);
+    assert_eq!(map, [0, 1, 3]);
+  }
+
+  #[test]
+  fn vram_budget_rejects_overlaps() {
+    // a typical mode 0 plan: two tile sets, maps at the end, object tiles
+    let tiles = [(0, 16384), (1, 8192), (4, 32768)];
+    let maps = [(28, 2), (31, 1)];
+    let plan = VramPlan { tiles: &tiles, maps: &maps, bitmap_bytes: 0 };
+    assert_eq!(vram_budget(&plan), Ok(()));
+    // two maps sharing screenblock 29
+    let maps = [(28, 2), (29, 1)];
+    let plan = VramPlan { tiles: &tiles, maps: &maps, bitmap_bytes: 0 };
+    assert_eq!(
+      vram_budget(&plan),
+      Err(VramOverflow::Overlap {
+        first: VramUse::Map { index: 0, screenblock: 28 },
+        second: VramUse::Map { index: 1, screenblock: 29 },
+        at: 29 * 2048,
+      })
+    );
+    // a mode 3 bitmap runs into charblock 4, but not charblock 5
+    let plan =
+      VramPlan { tiles: &[(4, 1)], maps: &[], bitmap_bytes: 240 * 160 * 2 };
+    assert_eq!(
+      vram_budget(&plan),
+      Err(VramOverflow::Overlap {
+        first: VramUse::Tiles { index: 0, charblock: 4 },
+        second: VramUse::Bitmap,
+        at: 65536,
+      })
+    );
+    let plan =
+      VramPlan { tiles: &[(5, 1)], maps: &[], bitmap_bytes: 240 * 160 * 2 };
+    assert_eq!(vram_budget(&plan), Ok(()));
+    // too much object tile data, and empty entries that are skipped
+    let plan = VramPlan {
+      tiles: &[(0, 0), (5, 16385)],
+      maps: &[(0, 0)],
+      bitmap_bytes: 0,
+    };
+    assert_eq!(
+      vram_budget(&plan),
+      Err(VramOverflow::PastEnd {
+        user: VramUse::Tiles { index: 1, charblock: 5 },
+        end: 96 * 1024 + 1,
+      })
+    );
+  }
+
+  #[test]
+  fn setup_bg8_uploads_everything() {
+    let tiles: [Tile8; 3] = [[0; 16], [0x0101_0101; 16], [0x0202_0202; 16]];
+    let mut palette = [Color::BLACK; 256];
+    palette[255] = Color::WHITE;
+    let map = [2_u8, 1, 0, 1];
+    // a pretend VRAM (as words) and palette RAM
+    let mut vram = std::vec![0_u32; 96 * 1024 / 4];
+    let mut pal_ram = [Color::RED; 256];
+    let mut entries = std::vec::Vec::new();
+    let current =
+      BackgroundControl::new().with_priority_level(Priority::Low).with_size(3);
+    let control = setup_bg8_with(
+      current,
+      1,
+      2,
+      30,
+      &tiles,
+      &palette,
+      &map,
+      |offset, tiles| {
+        for (i, tile) in tiles.iter().enumerate() {
+          let start = offset / 4 + i * 16;
+          vram[start..start + 16].copy_from_slice(tile);
+        }
+      },
+      |palette| pal_ram = *palette,
+      |i, entry| entries.push((i, entry)),
+    )
+    .unwrap();
+    assert!(control.bpp8());
+    assert_eq!(control.charblock(), 2);
+    assert_eq!(control.screenblock(), 30);
+    assert_eq!(control.priority_level(), Priority::Low);
+    assert_eq!(control.size(), 0);
+    // the tiles are at the start of charblock 2
+    let cb2 = 2 * 16384 / 4;
+    assert_eq!(vram[cb2 + 16..cb2 + 48], [tiles[1], tiles[2]].concat());
+    assert!(vram[..cb2].iter().all(|&w| w == 0));
+    assert_eq!(pal_ram, palette);
+    let tile_ids: std::vec::Vec<_> =
+      entries.iter().map(|&(i, e)| (i, e.tile())).collect();
+    assert_eq!(tile_ids, [(0, 2), (1, 1), (2, 0), (3, 1)]);
+    // nothing is written when there's a problem
+    let result = setup_bg8_with(
+      current,
+      1,
+      2,
+      30,
+      &tiles,
+      &palette,
+      &[3],
+      |_, _| panic!("tiles written"),
+      |_| panic!("palette written"),
+      |_, _| panic!("map written"),
+    );
+    assert_eq!(result, Err(Bg8Error::TileOutOfRange { index: 0, tile: 3 }));
+  }
+
+  #[test]
+  fn tile_animator_advances_and_loops() {
+    let frames = [[0_u32; 8], [1; 8], [2; 8]];
+    let mut anim = TileAnimator::new(&frames, 1, 3, 3);
+    assert_eq!(anim.tile_addr(), 0x0600_4060);
+    // each frame lasts 3 ticks, and it loops twice through
+    let mut shown = [0_u32; 18];
+    for out in shown.iter_mut() {
+      *out = anim.advance().map_or(9, |tile| tile[0]);
+    }
+    assert_eq!(shown, [9, 9, 1, 9, 9, 2, 9, 9, 0, 9, 9, 1, 9, 9, 2, 9, 9, 0]);
+    // jumping restarts the step
+    anim.advance();
+    anim.set_frame(2);
+    assert_eq!((anim.advance(), anim.advance()), (None, None));
+    assert_eq!(anim.advance(), Some(&frames[0]));
+    // a step of 0 changes every tick, like a step of 1
+    anim.set_frames_per_step(0);
+    assert_eq!(anim.advance(), Some(&frames[1]));
+    assert_eq!(anim.advance(), Some(&frames[2]));
+  }
+
+  #[test]
+  fn gradient_top_and_bottom_rows() {
+    let (top, bottom) = (Color::from_rgb(31, 4, 0), Color::from_rgb(2, 30, 17));
+    for len in [2, 20, 160, 256] {
+      let mut rows = std::vec![Color::BLACK; len];
+      gradient_colors(top, bottom, &mut rows);
+      assert_eq!((rows[0], rows[len - 1]), (top, bottom), "{len}");
+      assert!(rows.windows(2).all(|w| w[0].red() >= w[1].red()));
+      assert!(rows.windows(2).all(|w| w[0].green() <= w[1].green()));
+    }
+    let mut one = [Color::BLACK];
+    gradient_colors(top, bottom, &mut one);
+    assert_eq!(one, [top]);
+    // in the tiled version, each scanline's palette index gets its own color
+    let mut palette = [Color::BLACK; 256];
+    gradient_colors(top, bottom, &mut palette[1..=160]);
+    let scanline_color = |y: usize| {
+      let tile = gradient_tile8((y / 8) as u8);
+      palette[(tile[(y % 8) * 2] & 0xFF) as usize]
+    };
+    assert_eq!(scanline_color(0), top);
+    assert_eq!(scanline_color(159), bottom);
+    assert_eq!(scanline_color(80), palette[81]);
+  }
+
+  #[test]
+  fn mirror_row_flips_columns_and_hflip() {
+    let mut row = [TextEntry::new(); 32];
+    row[0] = TextEntry::from_tile(9).with_palbank(3).with_vflip(true);
+    row[17] = TextEntry::from_tile(4).with_hflip(true);
+    let mirrored = mirror_row_h(&row);
+    // column 0 ends up at column 31, with only hflip changed
+    assert_eq!(
+      mirrored[31],
+      TextEntry::from_tile(9).with_palbank(3).with_vflip(true).with_hflip(true)
+    );
+    assert_eq!(mirrored[14], TextEntry::from_tile(4));
+    // mirroring twice gives back the original row
+    assert_eq!(mirror_row_h(&mirrored), row);
+  }
+
+  #[test]
+  fn test_pattern_corners_are_distinct() {
+    for (w, h) in [(240, 160), (160, 128)] {
+      let corners = [
+        test_pattern_color(0, 0, w, h),
+        test_pattern_color(w - 1, 0, w, h),
+        test_pattern_color(0, h - 1, w, h),
+        test_pattern_color(w - 1, h - 1, w, h),
+      ];
+      assert_eq!(
+        corners,
+        [Color::RED, Color::GREEN, Color::BLUE, Color::WHITE]
+      );
+      // no other pixel uses a corner color
+      for y in 0..h {
+        for x in 0..w {
+          let in_corner = (x < 8 || x + 8 >= w) && (y < 8 || y + 8 >= h);
+          let c = test_pattern_color(x, y, w, h);
+          assert_eq!(corners.contains(&c), in_corner, "({x}, {y}) of {w}x{h}");
+        }
+      }
+    }
+    // each corner is a full 8x8 block
+    assert_eq!(test_pattern_index(7, 7, 240, 160), 1);
+    assert_eq!(test_pattern_index(232, 152, 240, 160), 4);
+  }
+
+  #[test]
+  fn loading_screen_display_control() {
+    let game = DisplayControl::new()
+      .with_video_mode(VideoMode::_1)
+      .with_show_frame1(true)
+      .with_show_bg0(true)
+      .with_show_bg2(true)
+      .with_show_obj(true)
+      .with_enable_win0(true);
+    // without a backdrop: forced blank, everything else left as it was
+    let display = loading_display_control(game, false);
+    assert!(display.forced_blank());
+    assert_eq!(display.with_forced_blank(false), game);
+    // with a backdrop: no layers or windows, so only the backdrop shows
+    let display = loading_display_control(game, true);
+    assert!(!display.forced_blank());
+    assert_eq!(display.0 & 0xFF00, 0);
+    assert_eq!(display.video_mode(), VideoMode::_1);
+    assert!(display.show_frame1());
+  }
+
+  #[test]
+  fn blend_layers_bldcnt_bits() {
+    // BG1 first target (bit 1), BG2 second target (bit 10), alpha mode (bit 6)
+    let cnt = blend_layers_control(BlendTarget::Bg1, BlendTarget::Bg2);
+    assert_eq!(cnt.0, 0b0000_0100_0100_0010);
+    let cnt = blend_layers_control(BlendTarget::Obj, BlendTarget::Backdrop);
+    assert_eq!(cnt.0, 0x2050);
+    // one layer can be both targets
+    let cnt = blend_layers_control(BlendTarget::Bg0, BlendTarget::Bg0);
+    assert_eq!(cnt.0, 0x0141);
+  }
+
+  #[test]
+  fn affine_playfield_registers() {
+    let p = mode1_affine_playfield(4096, 256, 1, 24).unwrap();
+    // mode 1 with only BG2 shown
+    assert_eq!(p.display.0, 0x0401);
+    // charblock 1, screenblock 24, size 2 (512x512)
+    assert_eq!(p.bg2.0, 0x9804);
+    assert_eq!(p.ref_x, i32fx8::from_bits(136 << 8));
+    assert_eq!(p.ref_y, i32fx8::from_bits(176 << 8));
+    // a 128x128 map is smaller than the screen, so the reference is negative
+    let p = mode1_affine_playfield(256, 1, 0, 31).unwrap();
+    assert_eq!((p.ref_x.to_bits(), p.ref_y.to_bits()), (-56 << 8, -16 << 8));
+    // a 1024x1024 map exactly fits at the end of background VRAM
+    assert!(mode1_affine_playfield(16384, 0, 0, 24).is_ok());
+    assert_eq!(
+      mode1_affine_playfield(16384, 0, 0, 25),
+      Err(AffinePlayfieldError::MapOverlapsTiles)
+    );
+    // 256 tiles fill charblock 1, which holds screenblocks 8 to 15
+    assert_eq!(
+      mode1_affine_playfield(256, 256, 1, 15),
+      Err(AffinePlayfieldError::MapOverlapsTiles)
+    );
+    assert_eq!(
+      mode1_affine_playfield(256, 257, 1, 31),
+      Err(AffinePlayfieldError::TooManyTiles { count: 257 })
+    );
+  }
+
+  #[test]
+  fn default_palettes() {
+    assert_eq!(DEFAULT_GRAYSCALE16[0], Color::BLACK);
+    assert_eq!(DEFAULT_GRAYSCALE16[15], Color::WHITE);
+    for pair in DEFAULT_GRAYSCALE16.windows(2) {
+      assert!(pair[0].luminance() < pair[1].luminance(), "{pair:?}");
+      assert_eq!(pair[1].to_grayscale(), pair[1]);
+    }
+    // the general palette starts with black, and has no repeats
+    assert_eq!(DEFAULT_PALETTE16[0], Color::BLACK);
+    for (i, a) in DEFAULT_PALETTE16.iter().enumerate() {
+      assert!(!DEFAULT_PALETTE16[i + 1..].contains(a), "{i}");
+    }
+  }
+
+  #[test]
+  fn mode3_positions_fit_mode5() {
+    let mut hit = std::vec![false; 160 * 128];
+    for row in 0..160 {
+      for col in 0..240 {
+        let (x, y) = mode5_scale_from_mode3(col, row).unwrap();
+        assert!(x < 160 && y < 128, "({col}, {row})");
+        hit[y * 160 + x] = true;
+      }
+    }
+    // scaling down leaves no gaps in the mode 5 bitmap
+    assert!(hit.iter().all(|&h| h));
+    for (col, row) in [(240, 0), (0, 160), (usize::MAX, 5), (300, 300)] {
+      assert_eq!(mode5_scale_from_mode3(col, row), None);
+    }
+  }
+
+  #[test]
+  fn backdrop_gradient_table_endpoints() {
+    let (top, bottom) = (Color::from_rgb(0, 10, 31), Color::from_rgb(31, 2, 0));
+    let table = backdrop_gradient_table(top, bottom);
+    assert_eq!((table[0], table[159]), (top.0, bottom.0));
+    // the raw values are the same colors as the plain gradient
+    let mut colors = [Color::BLACK; 160];
+    gradient_colors(top, bottom, &mut colors);
+    assert_eq!(table, colors.map(|c| c.0));
+    // a flat "gradient" is a single color
+    let table = backdrop_gradient_table(Color::YELLOW, Color::YELLOW);
+    assert!(table.iter().all(|&c| c == Color::YELLOW.0));
+  }
+
+  #[test]
+  fn letterbox_win0v_bounds() {
+    let raw = |v: u8x2| u16::from_le_bytes(<[u8; 2]>::from(v));
+    let (h, v) = letterbox_bounds(24);
+    // WIN0H is the whole width, WIN0V has the top in the high byte
+    assert_eq!(raw(h), 240);
+    assert_eq!(raw(v), 24 << 8 | 136);
+    for bar in 0..80 {
+      let (_, v) = letterbox_bounds(bar);
+      assert_eq!((v.high(), v.low()), (bar, 160 - bar), "{bar}");
+    }
+    // once the bars meet there's no window left
+    for bar in [80, 81, 255] {
+      let (_, v) = letterbox_bounds(bar);
+      assert_eq!((v.high(), v.low()), (0, 0), "{bar}");
+    }
+  }
 }
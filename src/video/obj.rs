@@ -32,6 +32,7 @@
 //! un-configured objects appearing in the upper left corner of the display.
 
 use super::*;
+use crate::fixed::{fx8_bits, fx8_from_bits};
 
 /// How the object should be displayed.
 ///
@@ -113,6 +114,44 @@ impl ObjAttr1 {
   u16_int_field!(14 - 15, size, with_size);
 }
 
+/// The width and height in pixels of an object with the given shape and size
+/// (the 2-bit [`ObjAttr1::size`] field, extra bits are ignored).
+///
+/// | Size | Square | Horizontal | Vertical |
+/// |:----:|:------:|:----------:|:--------:|
+/// | 0    | 8x8    | 16x8       | 8x16     |
+/// | 1    | 16x16  | 32x8       | 8x32     |
+/// | 2    | 32x32  | 32x16      | 16x32    |
+/// | 3    | 64x64  | 64x32      | 32x64    |
+///
+/// ```
+/// # use gba::video::obj::*;
+/// assert_eq!(obj_dimensions(ObjShape::Square, 0), (8, 8));
+/// assert_eq!(obj_dimensions(ObjShape::Square, 3), (64, 64));
+/// assert_eq!(obj_dimensions(ObjShape::Horizontal, 2), (32, 16));
+/// assert_eq!(obj_dimensions(ObjShape::Vertical, 1), (8, 32));
+/// ```
+#[inline]
+#[must_use]
+pub const fn obj_dimensions(shape: ObjShape, size: u16) -> (u8, u8) {
+  let size = size & 0b11;
+  match shape {
+    ObjShape::Square => (8 << size, 8 << size),
+    ObjShape::Horizontal => match size {
+      0 => (16, 8),
+      1 => (32, 8),
+      2 => (32, 16),
+      _ => (64, 32),
+    },
+    ObjShape::Vertical => match size {
+      0 => (8, 16),
+      1 => (8, 32),
+      2 => (16, 32),
+      _ => (32, 64),
+    },
+  }
+}
+
 /// Object Attributes, field 2 of the entry.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(transparent)]
@@ -120,8 +159,40 @@ pub struct ObjAttr2(u16);
 impl ObjAttr2 {
   pub_const_fn_new_zeroed!();
   u16_int_field!(0 - 9, tile_id, with_tile_id);
-  u16_int_field!(10 - 11, priority, with_priority);
   u16_int_field!(12 - 15, palbank, with_palbank);
+
+  #[inline]
+  #[must_use]
+  #[allow(missing_docs)]
+  pub const fn priority(self) -> u16 {
+    bitfrob::u16_get_value(10, 11, self.0)
+  }
+  #[inline]
+  #[must_use]
+  #[allow(missing_docs)]
+  #[deprecated = "use `with_priority_level`, which can't be out of range"]
+  pub const fn with_priority(self, val: u16) -> Self {
+    Self(bitfrob::u16_with_value(10, 11, self.0, val))
+  }
+  /// The object's priority, see [`Priority`].
+  #[inline]
+  #[must_use]
+  pub const fn priority_level(self) -> Priority {
+    Priority::from_bits(self.priority())
+  }
+  /// Sets the object's priority, see [`Priority`].
+  ///
+  /// ```
+  /// # use gba::prelude::*;
+  /// let attr2 = ObjAttr2::new().with_priority_level(Priority::Low);
+  /// assert_eq!(attr2.priority(), 2);
+  /// assert_eq!(attr2.priority_level(), Priority::Low);
+  /// ```
+  #[inline]
+  #[must_use]
+  pub const fn with_priority_level(self, priority: Priority) -> Self {
+    Self(bitfrob::u16_with_value(10, 11, self.0, priority as u16))
+  }
 }
 
 /// Object Attributes.
@@ -158,3 +229,663 @@ impl ObjAttr {
     self.2 = self.2.with_palbank(palbank);
   }
 }
+
+/// Sets the palbank of an object so that it draws with a particular color
+/// variant, such as one from a [`VariantSet`].
+///
+/// This only makes a difference for 4bpp objects.
+#[inline]
+pub fn set_variant(attr: &mut ObjAttr, variant_palbank: u16) {
+  attr.set_palbank(variant_palbank);
+}
+
+/// A group of `N` recolored 16-color palettes, stored in successive object
+/// palbanks.
+///
+/// This is for drawing the same 4bpp object tiles in different colors, such as
+/// "player 1 is blue, player 2 is red". Each variant's palette is stored in
+/// its own palbank, and switching an object between variants is just a
+/// palbank change (see [`set_variant`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VariantSet<const N: usize> {
+  first_palbank: u16,
+}
+impl<const N: usize> VariantSet<N> {
+  /// Makes a set that uses palbanks `first_palbank .. first_palbank + N`.
+  ///
+  /// ## Panics
+  /// * If the palbanks would go past palbank 15.
+  #[inline]
+  #[must_use]
+  #[cfg_attr(feature = "track_caller", track_caller)]
+  pub const fn new(first_palbank: u16) -> Self {
+    assert!(first_palbank as usize + N <= 16);
+    Self { first_palbank }
+  }
+
+  /// The palbank that holds the given variant.
+  ///
+  /// ## Panics
+  /// * If `variant` is `N` or more.
+  #[inline]
+  #[must_use]
+  #[cfg_attr(feature = "track_caller", track_caller)]
+  pub const fn palbank(&self, variant: usize) -> u16 {
+    assert!(variant < N);
+    self.first_palbank + variant as u16
+  }
+
+  /// Copies each variant's palette into its palbank of [`OBJ_PALETTE`].
+  ///
+  /// Entry 0 of each palette is copied too, though objects always treat index
+  /// 0 as transparent.
+  #[inline]
+  #[cfg(feature = "on_gba")]
+  pub fn load(&self, palettes: &[[Color; 16]; N]) {
+    for (variant, palette) in palettes.iter().enumerate() {
+      let bank = obj_palbank(usize::from(self.palbank(variant)));
+      for (addr, color) in bank.iter().zip(palette.iter()) {
+        addr.write(*color);
+      }
+    }
+  }
+
+  /// Sets an object to use the given variant.
+  ///
+  /// ## Panics
+  /// * If `variant` is `N` or more.
+  #[inline]
+  #[cfg_attr(feature = "track_caller", track_caller)]
+  pub fn apply(&self, attr: &mut ObjAttr, variant: usize) {
+    set_variant(attr, self.palbank(variant));
+  }
+}
+
+/// Reads back the attributes of an object entry from OAM.
+///
+/// This reads the three attribute fields separately, so it never touches the
+/// affine parameter that shares the entry's fourth `u16`. It's mostly useful
+/// for debugging, to check what's actually in OAM compared to whatever shadow
+/// copy your program is keeping.
+///
+/// ## Panics
+/// * If `index` is 128 or more.
+#[inline]
+#[must_use]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn read_attr(index: usize) -> ObjAttr {
+  assert!(index < 128, "object index out of range");
+  ObjAttr(
+    OBJ_ATTR0.index(index).read(),
+    OBJ_ATTR1.index(index).read(),
+    OBJ_ATTR2.index(index).read(),
+  )
+}
+
+/// Reorders object entries to avoid the object priority hardware bug.
+///
+/// If a lower index object has a larger priority number than a higher index
+/// object, and a background is between the two of them, the object that should
+/// be behind the background can show through it where the objects overlap
+/// (see the [video module](super) docs). This sorts the entries by their
+/// priority number, smallest first, so that index order always agrees with
+/// priority order.
+///
+/// The sort is stable: objects with the same priority keep their relative
+/// order, so their overlap with each other doesn't change. Write the entries
+/// to OAM after sorting.
+#[inline]
+pub fn sort_to_avoid_bug(attrs: &mut [ObjAttr]) {
+  // An insertion sort is small, stable, and doesn't need to allocate, and
+  // there are at most 128 entries anyway.
+  for i in 1..attrs.len() {
+    let mut j = i;
+    while j > 0 && attrs[j - 1].2.priority() > attrs[j].2.priority() {
+      attrs.swap(j - 1, j);
+      j -= 1;
+    }
+  }
+}
+
+/// Writes fixed point positions into object entries.
+///
+/// Each position is rounded to the nearest pixel (halves round up), and then
+/// wrapped to fit the 9-bit `x` and 8-bit `y` fields. The wrapping is what the
+/// hardware expects for objects partly off the left or top edge: an `x` of -3
+/// becomes 509, and a `y` of -3 becomes 253.
+///
+/// Entries and positions are paired up by index. If one slice is longer than
+/// the other, the extra elements are ignored.
+#[inline]
+pub fn commit_positions(
+  entries: &mut [ObjAttr], positions: &[(i16fx8, i16fx8)],
+) {
+  for (attr, (x, y)) in entries.iter_mut().zip(positions.iter()) {
+    attr.set_x((round_fx8(*x) & 0x1FF) as u16);
+    attr.set_y((round_fx8(*y) & 0xFF) as u16);
+  }
+}
+
+/// Positions a [`DoubleSizeAffine`](ObjDisplayStyle::DoubleSizeAffine) object.
+///
+/// A double size object's bounding box is twice as wide and twice as tall as
+/// the object, with the object's image centered inside it. This takes `x` and
+/// `y` as the top left of where the object would be at its normal size, and
+/// sets the attributes to the top left of the doubled box (which is up and
+/// left by half the object's size). The result is wrapped to fit the 9-bit
+/// `x` and 8-bit `y` fields just like with [`commit_positions`], so the
+/// object can slide off any edge of the screen.
+///
+/// The object's size is read from `attr`, so set its shape and size first.
+///
+/// ```
+/// # use gba::prelude::*;
+/// let mut attr = ObjAttr::new();
+/// attr.0 = attr.0.with_shape(ObjShape::Square);
+/// attr.1 = attr.1.with_size(3); // 64x64
+/// set_position_double(&mut attr, -16, -16);
+/// // the 128x128 box starts at (-48, -48)
+/// assert_eq!(attr.1.x(), 512 - 48);
+/// assert_eq!(attr.0.y(), 256 - 48);
+/// ```
+#[inline]
+pub fn set_position_double(attr: &mut ObjAttr, x: i16, y: i16) {
+  let (w, h) = obj_dimensions(attr.0.shape(), attr.1.size());
+  let box_x = i32::from(x) - i32::from(w / 2);
+  let box_y = i32::from(y) - i32::from(h / 2);
+  attr.set_x((box_x & 0x1FF) as u16);
+  attr.set_y((box_y & 0xFF) as u16);
+}
+
+/// Rounds an `i16fx8` to the nearest whole number (halves round up).
+#[inline]
+// With the `fixed` feature `i16fx8` is backed by an `i32`.
+#[allow(clippy::useless_conversion)]
+fn round_fx8(v: i16fx8) -> i32 {
+  (i32::from(v.to_bits()) + (1 << 7)) >> 8
+}
+
+/// The affine matrix that draws an object at `scale` times its normal size.
+///
+/// Affine parameters map from the screen back into the object's tiles, so the
+/// matrix actually holds the inverse of `scale`: drawing at half size (0.5)
+/// uses 2.0 for `A` and `D`. The inverse is rounded to the nearest 1/256.
+/// Scales smaller than 3/256 (either way from 0) have an inverse too big for
+/// the 16-bit affine parameters, so it saturates at about 128.0 (or -128.0
+/// for a negative scale).
+///
+/// An object drawn larger than its normal size gets clipped to its bounding
+/// box, so when growing an object use [`ObjDisplayStyle::DoubleSizeAffine`].
+///
+/// ```
+/// # use gba::prelude::*;
+/// let m = scale_matrix(i16fx8::from_bits(0x80)); // 0.5x
+/// assert_eq!(m.a.to_bits(), 0x200);
+/// assert_eq!(m.b.to_bits(), 0);
+/// assert_eq!(m.c.to_bits(), 0);
+/// assert_eq!(m.d.to_bits(), 0x200);
+/// ```
+///
+/// ## Panics
+/// * If `scale` is 0.
+#[inline]
+#[must_use]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn scale_matrix(scale: i16fx8) -> Mat2 {
+  let bits = fx8_bits(scale);
+  assert!(bits != 0, "can't draw an object at 0 scale");
+  let inv = ((1 << 16) + bits / 2) / bits;
+  let inv = fx8_from_bits(inv.clamp(i32::from(i16::MIN), i32::from(i16::MAX)));
+  Mat2::scale(inv, inv)
+}
+
+/// Writes a matrix to one of the 32 groups of object affine parameters.
+///
+/// ## Panics
+/// * If `affine_index` is 32 or more.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn write_affine(affine_index: usize, m: &Mat2) {
+  AFFINE_PARAM_A.index(affine_index).write(m.a);
+  AFFINE_PARAM_B.index(affine_index).write(m.b);
+  AFFINE_PARAM_C.index(affine_index).write(m.c);
+  AFFINE_PARAM_D.index(affine_index).write(m.d);
+}
+
+/// Sets a group of affine parameters to draw objects at `scale` times their
+/// normal size (see [`scale_matrix`]).
+///
+/// ## Panics
+/// * If `affine_index` is 32 or more, or `scale` is 0.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn set_scale(affine_index: usize, scale: i16fx8) {
+  write_affine(affine_index, &scale_matrix(scale));
+}
+
+/// Sets a group of affine parameters to draw objects rotated by `angle`.
+///
+/// The angle uses the same units as [`sin`](crate::fixed::sin), see
+/// [`Mat2::rotation`]. The corners of a rotated object can go outside its
+/// bounding box and be clipped, unless it uses
+/// [`ObjDisplayStyle::DoubleSizeAffine`].
+///
+/// ## Panics
+/// * If `affine_index` is 32 or more.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn set_rotation(affine_index: usize, angle: u16) {
+  write_affine(affine_index, &Mat2::rotation(angle));
+}
+
+/// Sets a group of affine parameters back to the identity matrix, which draws
+/// objects at their normal size and orientation.
+///
+/// ## Panics
+/// * If `affine_index` is 32 or more.
+#[inline]
+#[cfg(feature = "on_gba")]
+#[cfg_attr(feature = "track_caller", track_caller)]
+pub fn reset(affine_index: usize) {
+  write_affine(affine_index, &Mat2::identity());
+}
+
+/// An object to draw, positioned in world space.
+///
+/// The `x` and `y` fields are the world position of the object's top left
+/// corner. The position fields within `attr` are ignored, since they get set
+/// from the world position and the camera by [`cull_into`]. With
+/// [`batch_commit`] there's no camera, so `x` and `y` are screen positions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SpriteDesc {
+  /// World position of the left edge.
+  pub x: i32,
+  /// World position of the top edge.
+  pub y: i32,
+  /// Everything else about the object.
+  pub attr: ObjAttr,
+}
+
+/// A shadow copy of the object entries, to be written to OAM all at once.
+///
+/// Entries are added with [`push`](OamManager::push) (or [`cull_into`]) each
+/// frame, and then [`commit`](OamManager::commit) writes them to the start
+/// of OAM and hides every entry after them.
+#[derive(Debug, Clone)]
+pub struct OamManager {
+  attrs: [ObjAttr; 128],
+  len: usize,
+}
+impl OamManager {
+  /// Makes a manager with no entries.
+  #[inline]
+  #[must_use]
+  pub const fn new() -> Self {
+    Self { attrs: [ObjAttr::new(); 128], len: 0 }
+  }
+
+  /// The entries that have been added so far.
+  #[inline]
+  #[must_use]
+  pub fn as_slice(&self) -> &[ObjAttr] {
+    &self.attrs[..self.len]
+  }
+
+  /// The number of entries that have been added.
+  #[inline]
+  #[must_use]
+  pub const fn len(&self) -> usize {
+    self.len
+  }
+
+  /// If no entries have been added.
+  #[inline]
+  #[must_use]
+  pub const fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Removes all entries.
+  #[inline]
+  pub fn clear(&mut self) {
+    self.len = 0;
+  }
+
+  /// Adds an entry, returning `false` if all 128 entries are already used.
+  #[inline]
+  pub fn push(&mut self, attr: ObjAttr) -> bool {
+    match self.attrs.get_mut(self.len) {
+      Some(slot) => {
+        *slot = attr;
+        self.len += 1;
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Writes the entries to OAM, and hides all the other objects.
+  ///
+  /// This should be done during vblank, so that objects don't tear.
+  #[inline]
+  #[cfg(feature = "on_gba")]
+  pub fn commit(&self) {
+    for (i, attr) in self.as_slice().iter().enumerate() {
+      OBJ_ATTR_ALL.index(i).write(*attr);
+    }
+    let hidden = ObjAttr0::new().with_style(ObjDisplayStyle::NotDisplayed);
+    for i in self.len..128 {
+      OBJ_ATTR0.index(i).write(hidden);
+    }
+  }
+}
+impl Default for OamManager {
+  #[inline]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Replaces the entries of `oam` with the sprites that are within the view
+/// of a camera.
+///
+/// The camera's view is the 240x160 screen with its top left at (`camera_x`,
+/// `camera_y`) in world space. Sprites that are entirely outside the view
+/// don't get an entry, which saves OAM entries and also the per-scanline
+/// object drawing time. Sprites that are hidden with
+/// [`ObjDisplayStyle::NotDisplayed`] are skipped too. Double size affine
+/// sprites are checked with their doubled size.
+///
+/// The entries that are kept have their positions set relative to the camera,
+/// wrapped to the 9-bit `x` and 8-bit `y` fields. Returns the number of
+/// entries, which stops at 128.
+///
+/// ```
+/// # use gba::prelude::*;
+/// let mut attr = ObjAttr::new();
+/// attr.1 = attr.1.with_size(1); // 16x16
+/// let sprites = [
+///   SpriteDesc { x: 400, y: 400, attr },
+///   SpriteDesc { x: 100, y: 50, attr },
+///   // partly on the left edge of the view
+///   SpriteDesc { x: 2, y: 20, attr },
+/// ];
+/// let mut oam = OamManager::new();
+/// assert_eq!(cull_into(&sprites, &mut oam, 10, 0), 2);
+/// assert_eq!(oam.as_slice()[0].1.x(), 90);
+/// assert_eq!(oam.as_slice()[0].0.y(), 50);
+/// assert_eq!(oam.as_slice()[1].1.x(), 512 - 8);
+/// ```
+#[inline]
+pub fn cull_into(
+  entries: &[SpriteDesc], oam: &mut OamManager, camera_x: i32, camera_y: i32,
+) -> usize {
+  oam.clear();
+  for desc in entries {
+    let mut attr = desc.attr;
+    let (w, h) = obj_dimensions(attr.0.shape(), attr.1.size());
+    let (w, h) = match attr.0.style() {
+      ObjDisplayStyle::NotDisplayed => continue,
+      ObjDisplayStyle::DoubleSizeAffine => (i32::from(w) * 2, i32::from(h) * 2),
+      _ => (i32::from(w), i32::from(h)),
+    };
+    let x = desc.x.wrapping_sub(camera_x);
+    let y = desc.y.wrapping_sub(camera_y);
+    if x >= 240 || y >= 160 || x + w <= 0 || y + h <= 0 {
+      continue;
+    }
+    attr.set_x((x & 0x1FF) as u16);
+    attr.set_y((y & 0xFF) as u16);
+    if !oam.push(attr) {
+      break;
+    }
+  }
+  oam.len()
+}
+
+/// Culls sprites to a camera's view with [`cull_into`], and then writes the
+/// visible ones to OAM with [`OamManager::commit`].
+///
+/// Returns the number of objects committed. This should be done during vblank.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn cull_and_commit(
+  entries: &[SpriteDesc], oam: &mut OamManager, camera_x: i32, camera_y: i32,
+) -> usize {
+  let count = cull_into(entries, oam, camera_x, camera_y);
+  oam.commit();
+  count
+}
+
+/// Fills OAM entry data for [`batch_commit`].
+///
+/// Each [`SpriteDesc`] is used as is, with `x` and `y` being the screen
+/// position (wrapped to the 9-bit `x` and 8-bit `y` fields). The first three
+/// halfwords of `out[i]` get the attributes of `descs[i]`, in order, and the
+/// fourth halfword (which belongs to the affine parameters) isn't touched.
+/// Returns the number of entries filled, which stops at 128.
+///
+/// ```
+/// # use gba::prelude::*;
+/// let mut attr = ObjAttr::new();
+/// attr.set_tile_id(5);
+/// let descs = [
+///   SpriteDesc { x: 10, y: 20, attr },
+///   SpriteDesc { x: -4, y: 30, attr: ObjAttr::new() },
+/// ];
+/// let mut out = [[0xFFFF_u16; 4]; 128];
+/// assert_eq!(batch_entries(&descs, &mut out), 2);
+/// assert_eq!(out[0], [20, 10, 5, 0xFFFF]);
+/// assert_eq!(out[1], [30, 512 - 4, 0, 0xFFFF]);
+/// assert_eq!(out[2], [0xFFFF; 4]);
+/// ```
+#[inline]
+pub fn batch_entries(descs: &[SpriteDesc], out: &mut [[u16; 4]; 128]) -> usize {
+  let mut count = 0;
+  for (entry, desc) in out.iter_mut().zip(descs) {
+    let mut attr = desc.attr;
+    attr.set_x((desc.x & 0x1FF) as u16);
+    attr.set_y((desc.y & 0xFF) as u16);
+    *entry = [attr.0 .0, attr.1 .0, attr.2 .0, entry[3]];
+    count += 1;
+  }
+  count
+}
+
+/// Writes `descs` to the start of OAM, in order, with a single DMA.
+///
+/// This is for scenes with many objects (particles, bullets) that have their
+/// screen positions already worked out. The entries are built in a buffer
+/// (see [`batch_entries`]) and then copied with one DMA3 transfer, rather
+/// than with separate writes per object. The affine parameters that share
+/// OAM with the entries are read back first and kept as they are. OAM entries
+/// past the end of `descs` aren't changed, so hide any unused entries
+/// yourself (or use [`OamManager`]).
+///
+/// Returns the number of entries written, which stops at 128.
+///
+/// ## Timing
+/// Call this during vblank, since OAM can't be written while objects are
+/// being drawn. For all 128 entries this copies 1 KiB, which is 256 words
+/// of DMA at about 2 cycles each, plus reading back the affine parameters
+/// and filling the buffer. That's around 3% of the roughly 83,000 cycles of
+/// vblank, so there's plenty of time left for everything else.
+#[inline]
+#[cfg(feature = "on_gba")]
+pub fn batch_commit(descs: &[SpriteDesc]) -> usize {
+  use crate::{dma::dma3_copy_u32, Align4};
+  let mut buf = Align4([[0_u16; 4]; 128]);
+  let count = batch_entries(descs, &mut buf.0);
+  for (i, entry) in buf.0[..count].iter_mut().enumerate() {
+    let param = (OBJ_ATTR0.index(i).as_usize() + 6) as *const u16;
+    entry[3] = unsafe { param.read_volatile() };
+  }
+  unsafe {
+    dma3_copy_u32(
+      buf.0.as_ptr().cast(),
+      OBJ_ATTR0.index(0).as_usize() as *mut u32,
+      count * 2,
+    )
+  };
+  count
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn scale_matrix_saturates_tiny_scales() {
+    let a = |bits: i32| fx8_bits(scale_matrix(fx8_from_bits(bits)).a);
+    assert_eq!(a(1), i32::from(i16::MAX));
+    assert_eq!(a(2), i32::from(i16::MAX));
+    assert_eq!(a(3), 21845);
+    assert_eq!(a(-1), i32::from(i16::MIN));
+    assert_eq!(a(-2), -32767);
+    assert_eq!(a(-3), -21845);
+    assert_eq!(a(256), 256);
+  }
+
+  #[test]
+  #[should_panic = "0 scale"]
+  fn scale_matrix_rejects_zero() {
+    let _ = scale_matrix(fx8_from_bits(0));
+  }
+
+  #[test]
+  fn variant_set_palbanks_are_distinct() {
+    let set = VariantSet::<3>::new(4);
+    assert_eq!([set.palbank(0), set.palbank(1), set.palbank(2)], [4, 5, 6]);
+    let mut attr = ObjAttr::new();
+    attr.set_tile_id(37);
+    for variant in 0..3 {
+      set.apply(&mut attr, variant);
+      assert_eq!(attr.2.palbank(), 4 + variant as u16);
+      assert_eq!(attr.2.tile_id(), 37);
+    }
+    // all 16 palbanks can be used
+    assert_eq!(VariantSet::<16>::new(0).palbank(15), 15);
+  }
+
+  #[test]
+  #[should_panic]
+  fn variant_set_past_last_palbank() {
+    let _ = VariantSet::<3>::new(14);
+  }
+
+  #[test]
+  fn sort_to_avoid_bug_orders_by_priority() {
+    let entry = |priority: Priority, tile_id: u16| {
+      ObjAttr(
+        ObjAttr0::new(),
+        ObjAttr1::new(),
+        ObjAttr2::new().with_tile_id(tile_id).with_priority_level(priority),
+      )
+    };
+    let mut attrs = [
+      entry(Priority::Lowest, 0),
+      entry(Priority::High, 1),
+      entry(Priority::Highest, 2),
+      entry(Priority::High, 3),
+      entry(Priority::Lowest, 4),
+      entry(Priority::Highest, 5),
+    ];
+    sort_to_avoid_bug(&mut attrs);
+    assert!(attrs.windows(2).all(|w| w[0].2.priority() <= w[1].2.priority()));
+    // equal priorities keep their order
+    assert_eq!(attrs.map(|a| a.2.tile_id()), [2, 5, 1, 3, 0, 4]);
+  }
+
+  #[test]
+  fn commit_positions_rounds_and_wraps() {
+    let positions = [
+      (fx8_from_bits(10 << 8), fx8_from_bits(20 << 8)),
+      // 2.5 rounds up, 2.49 rounds down
+      (fx8_from_bits(0x280), fx8_from_bits(0x27F)),
+      // -2.5 rounds up to -2, and -3 wraps
+      (fx8_from_bits(-0x280), fx8_from_bits(-3 << 8)),
+      (fx8_from_bits(-0x281), fx8_from_bits(300 << 8)),
+    ];
+    let mut entries = [ObjAttr::new(); 5];
+    commit_positions(&mut entries, &positions);
+    let xy = entries.map(|a| (a.1.x(), a.0.y()));
+    assert_eq!(xy, [(10, 20), (3, 2), (510, 253), (509, 44), (0, 0)]);
+  }
+
+  #[test]
+  fn double_size_position_registers() {
+    let mut attr = ObjAttr::new();
+    attr.0 = attr.0.with_shape(ObjShape::Square);
+    attr.1 = attr.1.with_size(3);
+    set_position_double(&mut attr, -16, -16);
+    // the 128x128 box starts at (-48, -48), with the size bits kept
+    assert_eq!(attr.0.y(), 208);
+    assert_eq!(attr.1.x(), 464);
+    assert_eq!(attr.1.size(), 3);
+    assert_eq!(attr.1 .0 & 0x1FF, 464);
+    // a 64x32 object sliding off the bottom right
+    attr.0 = attr.0.with_shape(ObjShape::Horizontal);
+    set_position_double(&mut attr, 230, 150);
+    assert_eq!((attr.1.x(), attr.0.y()), (198, 134));
+    set_position_double(&mut attr, 0, 0);
+    assert_eq!((attr.1.x(), attr.0.y()), (480, 240));
+  }
+
+  #[test]
+  fn cull_into_keeps_only_visible() {
+    let mut attr = ObjAttr::new();
+    attr.1 = attr.1.with_size(1); // 16x16
+    attr.set_tile_id(7);
+    let mut hidden = attr;
+    hidden.0 = hidden.0.with_style(ObjDisplayStyle::NotDisplayed);
+    let mut double = attr;
+    double.0 = double.0.with_style(ObjDisplayStyle::DoubleSizeAffine);
+    let sprites = [
+      SpriteDesc { x: 400, y: 400, attr },
+      SpriteDesc { x: 120, y: 80, attr },
+      SpriteDesc { x: 120, y: 80, attr: hidden },
+      // just touching the view's edges from outside
+      SpriteDesc { x: 240, y: 0, attr },
+      SpriteDesc { x: -16, y: 0, attr },
+      SpriteDesc { x: 0, y: -16, attr },
+      // only the doubled size reaches into the view
+      SpriteDesc { x: -20, y: 0, attr: double },
+    ];
+    let mut oam = OamManager::new();
+    assert_eq!(cull_into(&sprites, &mut oam, 0, 0), 2);
+    let kept = oam.as_slice();
+    assert_eq!((kept[0].1.x(), kept[0].0.y()), (120, 80));
+    assert_eq!(kept[0].2.tile_id(), 7);
+    assert_eq!(kept[1].1.x(), 512 - 20);
+    // moving the camera brings the far sprite in, and replaces the old list
+    assert_eq!(cull_into(&sprites, &mut oam, 300, 300), 1);
+    assert_eq!((oam.as_slice()[0].1.x(), oam.as_slice()[0].0.y()), (100, 100));
+    // no more than 128 entries are kept
+    let many = [SpriteDesc { x: 0, y: 0, attr }; 200];
+    assert_eq!(cull_into(&many, &mut oam, 0, 0), 128);
+  }
+
+  #[test]
+  fn batch_entries_in_order_up_to_128() {
+    let descs: [SpriteDesc; 130] = core::array::from_fn(|i| {
+      let mut attr = ObjAttr::new();
+      attr.set_tile_id(i as u16);
+      SpriteDesc { x: i as i32, y: (i / 2) as i32, attr }
+    });
+    let mut out = [[0xABCD_u16; 4]; 128];
+    assert_eq!(batch_entries(&descs[..3], &mut out), 3);
+    assert_eq!(out[2], [1, 2, 2, 0xABCD]);
+    assert_eq!(out[3], [0xABCD; 4]);
+    // extra descriptors past the end of OAM are dropped
+    assert_eq!(batch_entries(&descs, &mut out), 128);
+    for (i, entry) in out.iter().enumerate() {
+      assert_eq!(entry[..3], [(i / 2) as u16, i as u16, i as u16]);
+      assert_eq!(entry[3], 0xABCD);
+    }
+    assert_eq!(batch_entries(&[], &mut out), 0);
+  }
+}